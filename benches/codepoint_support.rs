@@ -0,0 +1,26 @@
+use cosmic_text as ct;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn codepoint_support(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    // Only monospace fonts list their codepoints
+    let Some(id) = fs
+        .db()
+        .faces()
+        .find(|face| face.monospaced)
+        .map(|face| face.id)
+    else {
+        return;
+    };
+
+    // Ten thousand unique codepoints, as in a long CJK document
+    let word: String = (0x4E00..0x4E00 + 10_000)
+        .filter_map(char::from_u32)
+        .collect();
+    c.bench_function("supported codepoints in 10k unique codepoints", |b| {
+        b.iter(|| black_box(fs.get_font_supported_codepoints_in_word(id, &word)))
+    });
+}
+
+criterion_group!(benches, codepoint_support);
+criterion_main!(benches);
@@ -67,7 +67,88 @@ fn layout(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, layout, load_font_system);
+fn resize(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    buffer.set_wrap(&mut fs, ct::Wrap::Word);
+    buffer.set_size(&mut fs, Some(400.0), None);
+    buffer.set_text(
+        &mut fs,
+        &FIRST_CHAPTER_OF_MOBY_DICK.repeat(10),
+        &ct::Attrs::new(),
+        ct::Shaping::Advanced,
+    );
+
+    // Resizing must only re-wrap, never reshape
+    let shaped_line_count = fs.shaped_line_count();
+    let mut width = 400.0;
+    c.bench_function("resize large amount of text", |b| {
+        b.iter(|| {
+            width = if width > 200.0 { width - 1.0 } else { 400.0 };
+            buffer.set_size(&mut fs, Some(black_box(width)), None);
+        })
+    });
+    assert_eq!(fs.shaped_line_count(), shaped_line_count);
+}
+
+fn rewrap_bidi(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    buffer.set_size(&mut fs, Some(400.0), None);
+    buffer.set_text(
+        &mut fs,
+        &[
+            include_str!("../sample/arabic.txt"),
+            include_str!("../sample/hebrew.txt"),
+            FIRST_CHAPTER_OF_MOBY_DICK,
+        ]
+        .concat(),
+        &ct::Attrs::new(),
+        ct::Shaping::Advanced,
+    );
+
+    // Bidi levels and segmentation are only computed when a line is shaped, which is what the
+    // shaped line count counts, and are kept in the shaped line, so re-wrapping must not analyze
+    // the text again
+    let shaped_line_count = fs.shaped_line_count();
+    let mut width = 400.0;
+    let mut wraps = [ct::Wrap::Word, ct::Wrap::Glyph, ct::Wrap::WordOrGlyph]
+        .into_iter()
+        .cycle();
+    c.bench_function("re-wrap bidi text", |b| {
+        b.iter(|| {
+            width = if width > 200.0 { width - 1.0 } else { 400.0 };
+            buffer.set_wrap(&mut fs, wraps.next().unwrap());
+            buffer.set_size(&mut fs, Some(black_box(width)), None);
+        })
+    });
+    assert_eq!(fs.shaped_line_count(), shaped_line_count);
+}
+
+fn monospace_cjk(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    let attrs = ct::Attrs::new().family(ct::Family::Monospace);
+    // Every word of a terminal line looks up the monospace fonts for its scripts
+    let text = ("端末 漢字 かな カナ 한글 中文 ".repeat(10) + "\n").repeat(50);
+
+    c.bench_function("monospace CJK", |b| {
+        b.iter(|| {
+            buffer.lines.clear();
+            buffer.set_text(&mut fs, &text, &attrs, ct::Shaping::Advanced);
+            buffer.shape_until_scroll(&mut fs, false);
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    layout,
+    resize,
+    rewrap_bidi,
+    load_font_system,
+    monospace_cjk
+);
 
 criterion_main!(benches);
 
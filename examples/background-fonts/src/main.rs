@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Run this example with `cargo run --package background-fonts`
+//! or `cargo run --package background-fonts -- "my own text"`
+
+use cosmic_text::{fontdb, Attrs, Buffer, FontSystem, Metrics, Shaping};
+use std::time::{Duration, Instant};
+
+fn print_layout(font_system: &FontSystem, buffer: &Buffer) {
+    for run in buffer.layout_runs() {
+        let mut fonts: Vec<_> = run
+            .glyphs
+            .iter()
+            .filter_map(|glyph| font_system.db().face(glyph.font_id))
+            .map(|face| face.post_script_name.as_str())
+            .collect();
+        fonts.dedup();
+        println!("  {:?} is {} wide using {:?}", run.text, run.line_w, fonts);
+    }
+}
+
+fn main() {
+    let start = Instant::now();
+
+    // Start loading system fonts, which can take up to a second
+    let loader = FontSystem::load_system_fonts_async();
+
+    // Create a FontSystem right away with only the fonts the app bundles
+    let mut db = fontdb::Database::new();
+    db.load_font_data(include_bytes!("../../../fonts/Inter-Regular.ttf").to_vec());
+    let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), db);
+
+    let text = std::env::args()
+        .nth(1)
+        .unwrap_or("Hello, Rust! Привет, Rust!".to_string());
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, &text, &Attrs::new(), Shaping::Advanced);
+
+    println!("Interactive after {:?}:", start.elapsed());
+    print_layout(&font_system, &buffer);
+
+    // Keep handling events until the system fonts are loaded
+    while !loader.is_finished() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    font_system.merge_system_fonts(loader);
+    // Shape again so text can use the system fonts as fallbacks
+    buffer.set_text(&mut font_system, &text, &Attrs::new(), Shaping::Advanced);
+
+    println!(
+        "System fonts merged after {:?}, {} faces:",
+        start.elapsed(),
+        font_system.db().len()
+    );
+    print_layout(&font_system, &buffer);
+}
@@ -88,7 +88,7 @@ impl FamilyOwned {
         }
     }
 
-    pub fn as_family(&self) -> Family {
+    pub fn as_family(&self) -> Family<'_> {
         match self {
             FamilyOwned::Name(name) => Family::Name(name),
             FamilyOwned::Serif => Family::Serif,
@@ -144,6 +144,8 @@ impl FeatureTag {
     pub const CONTEXTUAL_ALTERNATES: Self = Self::new(b"calt");
     /// Discretionary ligatures (optional stylistic ligatures)
     pub const DISCRETIONARY_LIGATURES: Self = Self::new(b"dlig");
+    /// Historical ligatures (ligatures used in older texts)
+    pub const HISTORICAL_LIGATURES: Self = Self::new(b"hlig");
     /// Small caps (lowercase to small capitals)
     pub const SMALL_CAPS: Self = Self::new(b"smcp");
     /// All small caps (uppercase and lowercase to small capitals)
@@ -153,6 +155,27 @@ impl FeatureTag {
     /// Stylistic Set 2 (font-specific alternate glyphs)
     pub const STYLISTIC_SET_2: Self = Self::new(b"ss02");
 
+    /// Stylistic Set `n` (`ss01` to `ss20`)
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `n` is not in `1..=20`.
+    pub const fn stylistic_set(n: u8) -> Self {
+        assert!(n >= 1 && n <= 20, "stylistic sets are numbered 1 to 20");
+        Self([b's', b's', b'0' + n / 10, b'0' + n % 10])
+    }
+
+    /// Get the number of the stylistic set (`ss01` to `ss20`) this tag enables
+    pub fn stylistic_set_number(&self) -> Option<u8> {
+        match self.0 {
+            [b's', b's', tens @ b'0'..=b'2', ones @ b'0'..=b'9'] => {
+                let n = (tens - b'0') * 10 + (ones - b'0');
+                (1..=20).contains(&n).then_some(n)
+            }
+            _ => None,
+        }
+    }
+
     pub fn as_bytes(&self) -> &[u8; 4] {
         &self.0
     }
@@ -192,6 +215,85 @@ impl FontFeatures {
     }
 }
 
+/// Joining behavior of Arabic letters when shaping
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ArabicForm {
+    /// Use initial, medial and final forms based on the joining context
+    #[default]
+    Auto,
+    /// Always use isolated forms, by disabling the joining features
+    Isolated,
+}
+
+/// Ligature features enabled when shaping
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ligatures {
+    /// Standard ligatures (`liga`), such as fi and fl
+    pub common: bool,
+    /// Discretionary ligatures (`dlig`), for display text
+    pub discretionary: bool,
+    /// Historical ligatures (`hlig`)
+    pub historical: bool,
+    /// Contextual ligatures (`clig`)
+    pub contextual: bool,
+}
+
+impl Ligatures {
+    /// Ligatures enabled by default: common and contextual ligatures
+    pub const DEFAULT: Self = Self {
+        common: true,
+        discretionary: false,
+        historical: false,
+        contextual: true,
+    };
+
+    /// No ligatures, for example for code
+    pub const NONE: Self = Self {
+        common: false,
+        discretionary: false,
+        historical: false,
+        contextual: false,
+    };
+
+    /// Get the features differing from what shaping enables by default
+    pub(crate) fn features(&self) -> impl Iterator<Item = Feature> {
+        [
+            (
+                FeatureTag::STANDARD_LIGATURES,
+                self.common,
+                Self::DEFAULT.common,
+            ),
+            (
+                FeatureTag::DISCRETIONARY_LIGATURES,
+                self.discretionary,
+                Self::DEFAULT.discretionary,
+            ),
+            (
+                FeatureTag::HISTORICAL_LIGATURES,
+                self.historical,
+                Self::DEFAULT.historical,
+            ),
+            (
+                FeatureTag::CONTEXTUAL_LIGATURES,
+                self.contextual,
+                Self::DEFAULT.contextual,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, enabled, default)| enabled != default)
+        .map(|(tag, enabled, _)| Feature {
+            tag,
+            value: u32::from(enabled),
+        })
+    }
+}
+
+impl Default for Ligatures {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 /// A wrapper for letter spacing to get around that f32 doesn't implement Eq and Hash
 #[derive(Clone, Copy, Debug)]
 pub struct LetterSpacing(pub f32);
@@ -223,6 +325,24 @@ impl Hash for LetterSpacing {
     }
 }
 
+/// A wrapper for opacity to get around that f32 doesn't implement Eq and Hash
+#[derive(Clone, Copy, Debug)]
+pub struct Opacity(pub f32);
+
+impl PartialEq for Opacity {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Opacity {}
+
+impl Hash for Opacity {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.0.to_bits().hash(hasher);
+    }
+}
+
 /// Text attributes
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Attrs<'a> {
@@ -237,7 +357,17 @@ pub struct Attrs<'a> {
     pub metrics_opt: Option<CacheMetrics>,
     /// Letter spacing (tracking) in EM
     pub letter_spacing_opt: Option<LetterSpacing>,
+    /// Opacity multiplied with the alpha of the color
+    pub opacity_opt: Option<Opacity>,
     pub font_features: FontFeatures,
+    /// OpenType script tag overriding script detection when shaping
+    pub script_opt: Option<[u8; 4]>,
+    /// Joining behavior of Arabic letters
+    pub arabic_form: ArabicForm,
+    /// Ligatures enabled when shaping
+    pub ligatures: Ligatures,
+    /// Font used when shaping, skipping font matching and fallback
+    pub font_id_opt: Option<fontdb::ID>,
 }
 
 impl<'a> Attrs<'a> {
@@ -255,7 +385,12 @@ impl<'a> Attrs<'a> {
             cache_key_flags: CacheKeyFlags::empty(),
             metrics_opt: None,
             letter_spacing_opt: None,
+            opacity_opt: None,
             font_features: FontFeatures::new(),
+            script_opt: None,
+            arabic_form: ArabicForm::Auto,
+            ligatures: Ligatures::DEFAULT,
+            font_id_opt: None,
         }
     }
 
@@ -313,12 +448,57 @@ impl<'a> Attrs<'a> {
         self
     }
 
+    /// Set the opacity from `0.0` to `1.0`, multiplied with the alpha of the color of glyphs by
+    /// [`crate::LayoutGlyph::color`]. This allows fading text without changing its color
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity_opt = Some(Opacity(opacity.clamp(0.0, 1.0)));
+        self
+    }
+
     /// Set [`FontFeatures`]
     pub fn font_features(mut self, font_features: FontFeatures) -> Self {
         self.font_features = font_features;
         self
     }
 
+    /// Enable the stylistic set `n` (`ss01` to `ss20`), see [`crate::FontSystem::stylistic_sets`]
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `n` is not in `1..=20`.
+    pub fn stylistic_set(mut self, n: u8) -> Self {
+        self.font_features.enable(FeatureTag::stylistic_set(n));
+        self
+    }
+
+    /// Force the OpenType script tag, such as `*b"arab"`, used when shaping, instead of
+    /// detecting it from the text
+    pub fn script(mut self, script: [u8; 4]) -> Self {
+        self.script_opt = Some(script);
+        self
+    }
+
+    /// Set [`ArabicForm`], for example to show isolated letters on a keyboard
+    pub fn arabic_form(mut self, arabic_form: ArabicForm) -> Self {
+        self.arabic_form = arabic_form;
+        self
+    }
+
+    /// Set the [`Ligatures`] enabled when shaping, for example to disable them for code or to
+    /// enable discretionary ligatures for display text. Features set with
+    /// [`Self::font_features`] take precedence
+    pub fn ligatures(mut self, ligatures: Ligatures) -> Self {
+        self.ligatures = ligatures;
+        self
+    }
+
+    /// Force the font used when shaping, for example one chosen by a previous shaping pass.
+    /// Font matching and fallback are skipped, so glyphs missing from the font are not replaced
+    pub fn font_id(mut self, font_id: fontdb::ID) -> Self {
+        self.font_id_opt = Some(font_id);
+        self
+    }
+
     /// Check if font matches
     pub fn matches(&self, face: &fontdb::FaceInfo) -> bool {
         //TODO: smarter way of including emoji
@@ -326,12 +506,34 @@ impl<'a> Attrs<'a> {
             || (face.style == self.style && face.stretch == self.stretch)
     }
 
+    /// Check if font matches when ignoring stretch, for families without a face of the
+    /// requested stretch
+    pub(crate) fn matches_any_stretch(&self, face: &fontdb::FaceInfo) -> bool {
+        face.post_script_name.contains("Emoji") || face.style == self.style
+    }
+
     /// Check if this set of attributes can be shaped with another
     pub fn compatible(&self, other: &Self) -> bool {
         self.family == other.family
             && self.stretch == other.stretch
             && self.style == other.style
             && self.weight == other.weight
+            && self.script_opt == other.script_opt
+            && self.arabic_form == other.arabic_form
+            && self.ligatures == other.ligatures
+            && self.font_id_opt == other.font_id_opt
+    }
+
+    /// Check if text shaped with this set of attributes is identical to text shaped with another.
+    ///
+    /// Only [`Attrs::color_opt`], [`Attrs::opacity_opt`] and [`Attrs::metadata`] are ignored, as
+    /// they do not affect shaping
+    pub fn shaping_eq(&self, other: &Self) -> bool {
+        self.compatible(other)
+            && self.cache_key_flags == other.cache_key_flags
+            && self.metrics_opt == other.metrics_opt
+            && self.letter_spacing_opt == other.letter_spacing_opt
+            && self.font_features == other.font_features
     }
 }
 
@@ -344,6 +546,13 @@ pub struct FontMatchAttrs {
     weight: Weight,
 }
 
+impl FontMatchAttrs {
+    /// Check if font matches when ignoring stretch, see [`Attrs::matches_any_stretch`]
+    pub(crate) fn matches_any_stretch(&self, face: &fontdb::FaceInfo) -> bool {
+        face.post_script_name.contains("Emoji") || face.style == self.style
+    }
+}
+
 impl<'a> From<&Attrs<'a>> for FontMatchAttrs {
     fn from(attrs: &Attrs<'a>) -> Self {
         Self {
@@ -369,7 +578,17 @@ pub struct AttrsOwned {
     pub metrics_opt: Option<CacheMetrics>,
     /// Letter spacing (tracking) in EM
     pub letter_spacing_opt: Option<LetterSpacing>,
+    /// Opacity multiplied with the alpha of the color
+    pub opacity_opt: Option<Opacity>,
     pub font_features: FontFeatures,
+    /// OpenType script tag overriding script detection when shaping
+    pub script_opt: Option<[u8; 4]>,
+    /// Joining behavior of Arabic letters
+    pub arabic_form: ArabicForm,
+    /// Ligatures enabled when shaping
+    pub ligatures: Ligatures,
+    /// Font used when shaping, skipping font matching and fallback
+    pub font_id_opt: Option<fontdb::ID>,
 }
 
 impl AttrsOwned {
@@ -384,11 +603,16 @@ impl AttrsOwned {
             cache_key_flags: attrs.cache_key_flags,
             metrics_opt: attrs.metrics_opt,
             letter_spacing_opt: attrs.letter_spacing_opt,
+            opacity_opt: attrs.opacity_opt,
             font_features: attrs.font_features.clone(),
+            script_opt: attrs.script_opt,
+            arabic_form: attrs.arabic_form,
+            ligatures: attrs.ligatures,
+            font_id_opt: attrs.font_id_opt,
         }
     }
 
-    pub fn as_attrs(&self) -> Attrs {
+    pub fn as_attrs(&self) -> Attrs<'_> {
         Attrs {
             color_opt: self.color_opt,
             family: self.family_owned.as_family(),
@@ -399,7 +623,12 @@ impl AttrsOwned {
             cache_key_flags: self.cache_key_flags,
             metrics_opt: self.metrics_opt,
             letter_spacing_opt: self.letter_spacing_opt,
+            opacity_opt: self.opacity_opt,
             font_features: self.font_features.clone(),
+            script_opt: self.script_opt,
+            arabic_form: self.arabic_form,
+            ligatures: self.ligatures,
+            font_id_opt: self.font_id_opt,
         }
     }
 }
@@ -422,7 +651,7 @@ impl AttrsList {
     }
 
     /// Get the default [Attrs]
-    pub fn defaults(&self) -> Attrs {
+    pub fn defaults(&self) -> Attrs<'_> {
         self.defaults.as_attrs()
     }
 
@@ -454,7 +683,7 @@ impl AttrsList {
     /// Get the attribute span for an index
     ///
     /// This returns a span that contains the index
-    pub fn get_span(&self, index: usize) -> Attrs {
+    pub fn get_span(&self, index: usize) -> Attrs<'_> {
         self.spans
             .get(&index)
             .map(|v| v.as_attrs())
@@ -1,14 +1,17 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::collections::BTreeSet;
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
-use core::{cmp, fmt};
+use core::{cmp, fmt, mem, ops::Range};
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    Affinity, Align, Attrs, AttrsList, BidiParagraphs, BorrowedWithFontSystem, BufferLine, Color,
-    Cursor, FontSystem, LayoutCursor, LayoutGlyph, LayoutLine, LineEnding, LineIter, Motion,
-    Scroll, ShapeLine, Shaping, Wrap,
+    is_default_ignorable, Affinity, Align, Attrs, AttrsList, AttrsOwned, Baseline, BidiParagraphs,
+    BorrowedWithFontSystem, BoundsMode, BufferLine, Color, Cursor, DecorationLine, DecorationMode,
+    EmptyLineMetrics, Feature, FontSystem, FontSystemError, LayoutCursor, LayoutGlyph, LayoutLine,
+    LayoutOptions, LeadingDistribution, LineEnding, LineEndingPolicy, LineIter, Motion,
+    OverflowWrap, Scroll, ShapeLine, Shaping, VerticalAlign, Wrap,
 };
 
 /// A line of visible text for rendering
@@ -26,13 +29,39 @@ pub struct LayoutRun<'a> {
     pub line_y: f32,
     /// Y offset to top of line
     pub line_top: f32,
-    /// Y offset to next line
+    /// Y offset to next line, which is the line height used to lay out this line: the largest
+    /// line height of the [`Attrs::metrics`] of its glyphs, the line height chosen by
+    /// [`Buffer::set_empty_line_metrics`] for empty lines, or else the buffer [`Metrics`]
     pub line_height: f32,
     /// Width of line
     pub line_w: f32,
+    /// The shaped original text line
+    pub shape: &'a ShapeLine,
 }
 
 impl LayoutRun<'_> {
+    /// Y offset from the top of the line to its baseline, which glyph positions are relative to
+    pub fn baseline_y(&self) -> f32 {
+        self.line_y - self.line_top
+    }
+
+    /// Split the glyphs into consecutive runs shaped as the same script, with the ISO 15924 tag
+    /// of that script, such as `Latn` or `Arab`. A line can have multiple scripts, see
+    /// [`LayoutGlyph::script`]
+    pub fn script_runs(&self) -> impl Iterator<Item = ([u8; 4], &[LayoutGlyph])> + '_ {
+        let mut glyphs = self.glyphs;
+        core::iter::from_fn(move || {
+            let script = glyphs.first()?.script;
+            let len = glyphs
+                .iter()
+                .position(|glyph| glyph.script != script)
+                .unwrap_or(glyphs.len());
+            let (run, rest) = glyphs.split_at(len);
+            glyphs = rest;
+            Some((script, run))
+        })
+    }
+
     /// Return the pixel span `Some((x_left, x_width))` of the highlighted area between `cursor_start`
     /// and `cursor_end` within this run, or None if the cursor range does not intersect this run.
     /// This may return widths of zero if `cursor_start == cursor_end`, if the run is empty, or if the
@@ -72,6 +101,176 @@ impl LayoutRun<'_> {
         }
     }
 
+    /// Return the parameters the first shaping run of this run was passed to the shaper with,
+    /// for reproducing the shaping with other tools such as `hb-shape`, or `None` if no glyph
+    /// was shaped with the shaper, as with [`Shaping::Basic`]. The plans are recorded once per
+    /// shaping run, see [`crate::ShapeWord::shaping_plans`]
+    pub fn shaping_plan(&self) -> Option<ShapingPlan> {
+        self.shape
+            .spans
+            .iter()
+            .flat_map(|span| &span.words)
+            .flat_map(|word| &word.shaping_plans)
+            .filter(|(range, _)| self.glyphs.iter().any(|glyph| range.contains(&glyph.start)))
+            .min_by_key(|(range, _)| range.start)
+            .map(|(_, plan)| plan.clone())
+    }
+
+    /// Return the byte range in the line of the glyph cluster whose advance box contains `x`, or
+    /// `None` if `x` is outside all glyphs. This works for both LTR and RTL runs, since glyph
+    /// positions are always measured from the left
+    pub fn cluster_at_x(&self, x: f32) -> Option<Range<usize>> {
+        self.glyphs
+            .iter()
+            .find(|glyph| glyph.x <= x && x < glyph.x + glyph.w)
+            .map(|glyph| glyph.start..glyph.end)
+    }
+
+    /// Return the advance width, as shaped in context with kerning and ligatures, of the
+    /// grapheme containing byte `byte_offset` of the line, or `None` if no glyph of this run
+    /// covers it. The advance of a cluster of several graphemes, such as a ligature, is split
+    /// evenly between them
+    pub fn grapheme_advance(&self, byte_offset: usize) -> Option<f32> {
+        let glyph = self
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start <= byte_offset && byte_offset < glyph.end)?;
+        let cluster_w: f32 = self
+            .glyphs
+            .iter()
+            .filter(|other| other.start == glyph.start && other.end == glyph.end)
+            .map(|other| other.w)
+            .sum();
+        let graphemes = self
+            .text
+            .get(glyph.start..glyph.end)
+            .map_or(1, |cluster| cluster.graphemes(true).count().max(1));
+        Some(cluster_w / graphemes as f32)
+    }
+
+    /// Return the x position in this run of byte `index` of the line, at the start edge of the
+    /// glyph cluster covering it, or at the trailing edge of the cluster ending at `index`.
+    /// Positions inside a cluster of several graphemes, such as a ligature, are interpolated
+    /// evenly between its edges. In RTL clusters the start edge is on the right, so positions
+    /// move left as `index` increases. Returns `None` if no glyph of this run starts, covers or
+    /// ends at `index`
+    pub fn x_for_index(&self, index: usize) -> Option<f32> {
+        let (glyph, trailing) = match self
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start <= index && index < glyph.end)
+        {
+            Some(glyph) => (glyph, false),
+            None => (self.glyphs.iter().find(|glyph| glyph.end == index)?, true),
+        };
+
+        // The edges of all glyphs of the cluster
+        let (left, right) = self
+            .glyphs
+            .iter()
+            .filter(|other| other.start == glyph.start && other.end == glyph.end)
+            .fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(left, right), other| (left.min(other.x), right.max(other.x + other.w)),
+            );
+
+        let fraction = if trailing {
+            1.0
+        } else {
+            let cluster = self.text.get(glyph.start..glyph.end).unwrap_or_default();
+            // Graphemes before the one containing the index
+            let mut before = 0;
+            let mut total = 0;
+            for (i, _) in cluster.grapheme_indices(true).skip(1) {
+                if glyph.start + i <= index {
+                    before += 1;
+                }
+                total += 1;
+            }
+            before as f32 / (total + 1) as f32
+        };
+
+        let offset = (right - left) * fraction;
+        Some(if glyph.level.is_rtl() {
+            right - offset
+        } else {
+            left + offset
+        })
+    }
+
+    /// Return the permutation from visual to logical order of the glyphs in this run, as
+    /// reordered by rule L2 of the Unicode bidirectional algorithm. Element `i` is the logical
+    /// index, ordered by position in the text, of the `i`th glyph from the left
+    pub fn visual_order(&self) -> Vec<usize> {
+        let mut logical: Vec<usize> = (0..self.glyphs.len()).collect();
+        logical.sort_by_key(|&glyph_i| self.glyphs[glyph_i].start);
+        let mut logical_indices = alloc::vec![0; self.glyphs.len()];
+        for (logical_i, &glyph_i) in logical.iter().enumerate() {
+            logical_indices[glyph_i] = logical_i;
+        }
+
+        let mut visual: Vec<usize> = (0..self.glyphs.len()).collect();
+        visual.sort_by(|&a, &b| self.glyphs[a].x.total_cmp(&self.glyphs[b].x));
+        visual
+            .into_iter()
+            .map(|glyph_i| logical_indices[glyph_i])
+            .collect()
+    }
+
+    /// Return the segments of `line` to draw under, or through, the glyphs of this run, from
+    /// left to right.
+    ///
+    /// With [`DecorationMode::PerFont`] there is a segment for each sequence of glyphs from the
+    /// same font, placed with that font's metrics. With [`DecorationMode::Unified`] a single
+    /// segment spans the run at the lowest position and largest thickness of its fonts, so a
+    /// line crossing fonts does not step.
+    pub fn decorations(
+        &self,
+        font_system: &mut FontSystem,
+        line: DecorationLine,
+        mode: DecorationMode,
+    ) -> Vec<DecorationSegment> {
+        let mut glyphs: Vec<&LayoutGlyph> = self.glyphs.iter().collect();
+        glyphs.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+        let mut segments: Vec<(fontdb::ID, DecorationSegment)> = Vec::new();
+        for glyph in glyphs {
+            match segments.last_mut() {
+                Some((font_id, segment)) if *font_id == glyph.font_id => {
+                    segment.w = (glyph.x + glyph.w).max(segment.x + segment.w) - segment.x;
+                }
+                _ => {
+                    let (position, thickness) = font_system
+                        .get_font(glyph.font_id)
+                        .map_or((0.0, 0.0), |font| font.decoration_metrics(line));
+                    segments.push((
+                        glyph.font_id,
+                        DecorationSegment {
+                            x: glyph.x,
+                            w: glyph.w,
+                            y: self.line_y - position * glyph.font_size,
+                            thickness: thickness * glyph.font_size,
+                        },
+                    ));
+                }
+            }
+        }
+
+        let segments = segments.into_iter().map(|(_, segment)| segment);
+        match mode {
+            DecorationMode::PerFont => segments.collect(),
+            DecorationMode::Unified => segments
+                .reduce(|unified, segment| DecorationSegment {
+                    x: unified.x,
+                    w: segment.x + segment.w - unified.x,
+                    y: unified.y.max(segment.y),
+                    thickness: unified.thickness.max(segment.thickness),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
     fn cursor_from_glyph_left(&self, glyph: &LayoutGlyph) -> Cursor {
         if self.rtl {
             Cursor::new_with_affinity(self.line_i, glyph.end, Affinity::Before)
@@ -87,6 +286,129 @@ impl LayoutRun<'_> {
             Cursor::new_with_affinity(self.line_i, glyph.end, Affinity::Before)
         }
     }
+
+    /// Get the bounding box `(min_x, min_y, max_x, max_y)` of the glyph advances and line height
+    /// of this run, or `None` if there are no glyphs. Unless `trailing_whitespace` is set,
+    /// whitespace glyphs after the last other glyph are not included
+    fn advance_bounds(&self, trailing_whitespace: bool) -> Option<(f32, f32, f32, f32)> {
+        // Whitespace glyphs after the last other glyph of the run are trailing
+        let content_end = if trailing_whitespace {
+            usize::MAX
+        } else {
+            self.glyphs
+                .iter()
+                .filter(|glyph| !self.text[glyph.start..glyph.end].trim().is_empty())
+                .map(|glyph| glyph.end)
+                .max()
+                .unwrap_or(0)
+        };
+        // Glyphs of right-to-left runs are not in visual order
+        let (min_x, max_x) = self
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.start < content_end)
+            .map(|glyph| (glyph.x, glyph.x + glyph.w))
+            .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)))?;
+        Some((
+            min_x,
+            self.line_top,
+            max_x,
+            self.line_top + self.line_height,
+        ))
+    }
+}
+
+/// The parameters passed to the shaper for a run, see [`LayoutRun::shaping_plan`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapingPlan {
+    /// Lowercase ISO 15924 script tag, such as `arab` or `latn`
+    pub script: [u8; 4],
+    /// Lowercase BCP 47 language tag, if one was set on the shaping buffer
+    pub language: Option<String>,
+    /// True if shaped right-to-left
+    pub rtl: bool,
+    /// Font features enabled or disabled for the run, including those of [`Attrs::ligatures`]
+    /// and [`Attrs::arabic_form`], in the order they were passed
+    pub features: Vec<Feature>,
+}
+
+/// A horizontal decoration line segment, see [`LayoutRun::decorations`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecorationSegment {
+    /// X offset of the left of the segment
+    pub x: f32,
+    /// Width of the segment
+    pub w: f32,
+    /// Y offset of the top of the line
+    pub y: f32,
+    /// Thickness of the line
+    pub thickness: f32,
+}
+
+/// A segment of a visual line with a single direction, for accessibility trees
+#[derive(Clone, Debug, PartialEq)]
+pub struct A11yRun {
+    /// The index of the original text line
+    pub line_i: usize,
+    /// Byte range of the segment in the original text line
+    pub text_range: Range<usize>,
+    /// Bounding box of the segment as `(x, y, width, height)`
+    pub rect: (f32, f32, f32, f32),
+    /// True if the segment is right-to-left
+    pub rtl: bool,
+}
+
+/// The source text of a visual line, see [`Buffer::line_source_range`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineSource {
+    /// The index of the original text line
+    pub line_i: usize,
+    /// Byte range of the visual line in the original text line
+    pub range: Range<usize>,
+    /// True if whitespace before the range was trimmed at the wrap, and is on no visual line
+    pub trimmed_start: bool,
+    /// True if whitespace after the range was trimmed at the wrap, and is on no visual line
+    pub trimmed_end: bool,
+}
+
+/// A page of visual lines, see [`Buffer::paginate`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page {
+    /// Range of the visual lines on the page, counting the visual lines of all lines from the
+    /// start of the buffer
+    pub line_range: Range<usize>,
+    /// Y offset from the top of the first visual line of the buffer to the top of the page
+    pub y_offset: f32,
+}
+
+/// Visible glyphs as parallel arrays ready for GPU instancing, see [`Buffer::glyph_run_data`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlyphRunData {
+    /// Glyph id of each glyph
+    pub glyph_ids: Vec<u16>,
+    /// Font id of each glyph
+    pub font_ids: Vec<fontdb::ID>,
+    /// X position of each glyph origin
+    pub xs: Vec<f32>,
+    /// Y position of each glyph origin, including the line baseline
+    pub ys: Vec<f32>,
+    /// Font size of each glyph
+    pub font_sizes: Vec<f32>,
+    /// Color of each glyph
+    pub colors: Vec<Color>,
+    /// Glyphs grouped by font id and font size, for batched atlas lookups
+    pub batches: Vec<GlyphBatch>,
+}
+
+/// Indices of glyphs sharing a font id and font size in a [`GlyphRunData`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphBatch {
+    /// Font id of the glyphs
+    pub font_id: fontdb::ID,
+    /// Font size of the glyphs
+    pub font_size: f32,
+    /// Indices of the glyphs in the arrays of [`GlyphRunData`]
+    pub indices: Vec<usize>,
 }
 
 /// An iterator of visible text lines, see [`LayoutRun`]
@@ -106,7 +428,7 @@ impl<'b> LayoutRunIter<'b> {
             line_i: buffer.scroll.line,
             layout_i: 0,
             total_height: 0.0,
-            line_top: 0.0,
+            line_top: buffer.vertical_offset,
         }
     }
 }
@@ -128,8 +450,11 @@ impl<'b> Iterator for LayoutRunIter<'b> {
 
                 let line_top = self.line_top - self.buffer.scroll.vertical;
                 let glyph_height = layout_line.max_ascent + layout_line.max_descent;
-                let centering_offset = (line_height - glyph_height) / 2.0;
-                let line_y = line_top + centering_offset + layout_line.max_ascent;
+                let leading_above = self
+                    .buffer
+                    .leading_distribution
+                    .above(line_height - glyph_height);
+                let line_y = line_top + leading_above + layout_line.max_ascent;
                 if let Some(height) = self.buffer.height_opt {
                     if line_y > height {
                         return None;
@@ -149,6 +474,7 @@ impl<'b> Iterator for LayoutRunIter<'b> {
                     line_top,
                     line_height,
                     line_w: layout_line.w,
+                    shape,
                 });
             }
             self.line_i += 1;
@@ -212,8 +538,19 @@ pub struct Buffer {
     /// True if a redraw is requires. Set to false after processing
     redraw: bool,
     wrap: Wrap,
+    overflow_wrap: OverflowWrap,
     monospace_width: Option<f32>,
     tab_width: u16,
+    layout_options: LayoutOptions,
+    /// Locale overriding the [`FontSystem`] locale for fallback resolution
+    locale_opt: Option<String>,
+    /// Fonts shaping is restricted to, see [`Buffer::set_font_whitelist`]
+    font_whitelist: Option<Vec<fontdb::ID>>,
+    line_ending_policy: LineEndingPolicy,
+    empty_line_metrics: EmptyLineMetrics,
+    leading_distribution: LeadingDistribution,
+    /// Offset of the first line from the top of the buffer, set by [`Buffer::layout_in_box`]
+    vertical_offset: f32,
 }
 
 impl Clone for Buffer {
@@ -226,8 +563,16 @@ impl Clone for Buffer {
             scroll: self.scroll,
             redraw: self.redraw,
             wrap: self.wrap,
+            overflow_wrap: self.overflow_wrap,
             monospace_width: self.monospace_width,
             tab_width: self.tab_width,
+            layout_options: self.layout_options,
+            locale_opt: self.locale_opt.clone(),
+            font_whitelist: self.font_whitelist.clone(),
+            line_ending_policy: self.line_ending_policy,
+            empty_line_metrics: self.empty_line_metrics,
+            leading_distribution: self.leading_distribution,
+            vertical_offset: self.vertical_offset,
         }
     }
 }
@@ -254,8 +599,16 @@ impl Buffer {
             scroll: Scroll::default(),
             redraw: false,
             wrap: Wrap::WordOrGlyph,
+            overflow_wrap: OverflowWrap::default(),
             monospace_width: None,
             tab_width: 8,
+            layout_options: LayoutOptions::default(),
+            locale_opt: None,
+            font_whitelist: None,
+            line_ending_policy: LineEndingPolicy::default(),
+            empty_line_metrics: EmptyLineMetrics::default(),
+            leading_distribution: LeadingDistribution::default(),
+            vertical_offset: 0.0,
         }
     }
 
@@ -288,16 +641,23 @@ impl Buffer {
         for line in &mut self.lines {
             if line.shape_opt().is_some() {
                 line.reset_layout();
-                line.layout(
+                line.layout_with_options(
                     font_system,
                     self.metrics.font_size,
                     self.width_opt,
-                    self.wrap,
+                    self.overflow_wrap.apply(self.wrap),
                     self.monospace_width,
                     self.tab_width,
+                    &self.layout_options,
                 );
             }
         }
+        // In order, so previous lines are resolved first
+        for line_i in 0..self.lines.len() {
+            if self.lines[line_i].layout_opt().is_some() {
+                self.apply_empty_line_metrics(font_system, line_i);
+            }
+        }
 
         self.redraw = true;
 
@@ -525,7 +885,25 @@ impl Buffer {
         line_i: usize,
     ) -> Option<&ShapeLine> {
         let line = self.lines.get_mut(line_i)?;
-        Some(line.shape(font_system, self.tab_width))
+        Some(
+            font_system.with_locale(self.locale_opt.as_deref(), |font_system| {
+                font_system.with_font_whitelist(self.font_whitelist.as_deref(), |font_system| {
+                    line.shape_with_options(font_system, self.tab_width, &self.layout_options)
+                })
+            }),
+        )
+    }
+
+    /// Shape the provided line index and return its glyphs in logical order, with the contextual
+    /// forms of right-to-left text but without bidi reordering, see [`ShapeLine::logical_glyphs`]
+    pub fn line_logical_glyphs(
+        &mut self,
+        font_system: &mut FontSystem,
+        line_i: usize,
+    ) -> Option<Vec<LayoutGlyph>> {
+        let font_size = self.metrics.font_size;
+        self.line_shape(font_system, line_i)
+            .map(|shape| shape.logical_glyphs(font_size))
     }
 
     /// Lay out the provided line index and return the result
@@ -535,14 +913,119 @@ impl Buffer {
         line_i: usize,
     ) -> Option<&[LayoutLine]> {
         let line = self.lines.get_mut(line_i)?;
-        Some(line.layout(
-            font_system,
-            self.metrics.font_size,
-            self.width_opt,
-            self.wrap,
-            self.monospace_width,
-            self.tab_width,
-        ))
+        let laid_out = line.layout_opt().is_some();
+        font_system.with_locale(self.locale_opt.as_deref(), |font_system| {
+            font_system.with_font_whitelist(self.font_whitelist.as_deref(), |font_system| {
+                line.layout_with_options(
+                    font_system,
+                    self.metrics.font_size,
+                    self.width_opt,
+                    self.overflow_wrap.apply(self.wrap),
+                    self.monospace_width,
+                    self.tab_width,
+                    &self.layout_options,
+                );
+            });
+        });
+        if !laid_out {
+            self.apply_empty_line_metrics(font_system, line_i);
+        }
+        self.lines[line_i].layout_opt().map(Vec::as_slice)
+    }
+
+    /// Get the byte indices in line `line_i` where a cursor may stop, shaping it if needed.
+    ///
+    /// These are the extended grapheme cluster boundaries that are also boundaries of the shaped
+    /// glyph clusters, plus the start and end of the line. Unlike segmenting the text alone, a
+    /// cluster formed by the shaper, like an emoji ZWJ sequence drawn as one glyph, is never
+    /// split, so editors moving the cursor with these agree with the shaper.
+    pub fn grapheme_boundaries(
+        &mut self,
+        font_system: &mut FontSystem,
+        line_i: usize,
+    ) -> Option<Vec<usize>> {
+        let shape = self.line_shape(font_system, line_i)?;
+        let mut cluster_boundaries: Vec<usize> = shape
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .flat_map(|glyph| [glyph.start, glyph.end])
+            .collect();
+        cluster_boundaries.sort_unstable();
+        cluster_boundaries.dedup();
+
+        let text = self.lines[line_i].text();
+        Some(
+            text.grapheme_indices(true)
+                .map(|(i, _)| i)
+                .chain(core::iter::once(text.len()))
+                .filter(|&i| {
+                    i == 0 || i == text.len() || cluster_boundaries.binary_search(&i).is_ok()
+                })
+                .collect(),
+        )
+    }
+
+    /// Apply [`EmptyLineMetrics`] to the layout of line `line_i` if it is empty
+    fn apply_empty_line_metrics(&mut self, font_system: &mut FontSystem, line_i: usize) {
+        if !self.lines[line_i].text().is_empty() {
+            return;
+        }
+        let line_height_opt = match self.empty_line_metrics {
+            // Layout already uses the attributes of the line
+            EmptyLineMetrics::Attrs => return,
+            EmptyLineMetrics::Buffer => None,
+            EmptyLineMetrics::PreviousLine => {
+                let Some(prev_layout_line) = line_i
+                    .checked_sub(1)
+                    .and_then(|prev_i| self.line_layout(font_system, prev_i))
+                    .and_then(|layout| layout.last())
+                else {
+                    return;
+                };
+                prev_layout_line.line_height_opt
+            }
+        };
+        self.lines[line_i].set_empty_line_height(line_height_opt);
+    }
+
+    /// Override the advance of glyph `glyph_i` in layout line `layout_i` of line `line_i`, see
+    /// [`BufferLine::set_glyph_advance`]. Returns true if the glyph was found
+    pub fn set_glyph_advance(
+        &mut self,
+        line_i: usize,
+        layout_i: usize,
+        glyph_i: usize,
+        advance: f32,
+    ) -> bool {
+        let Some(line) = self.lines.get_mut(line_i) else {
+            return false;
+        };
+        let changed = line.set_glyph_advance(layout_i, glyph_i, advance);
+        if changed {
+            self.redraw = true;
+        }
+        changed
+    }
+
+    /// Set the attributes of `range` in line `line_i`, see [`BufferLine::set_attrs`]. Changes
+    /// to only the color, opacity or metadata, such as syntax highlighting, update the glyphs
+    /// without reshaping. Other changes reshape only this line
+    pub fn set_attrs(
+        &mut self,
+        font_system: &mut FontSystem,
+        line_i: usize,
+        range: Range<usize>,
+        attrs: &Attrs,
+    ) {
+        let Some(line) = self.lines.get_mut(line_i) else {
+            return;
+        };
+        if line.set_attrs(range, attrs, self.layout_options.preserve_span_boundaries) {
+            self.line_layout(font_system, line_i);
+        }
+        self.redraw = true;
     }
 
     /// Get the current [`Metrics`]
@@ -573,6 +1056,21 @@ impl Buffer {
         }
     }
 
+    /// Get the current [`OverflowWrap`]
+    pub fn overflow_wrap(&self) -> OverflowWrap {
+        self.overflow_wrap
+    }
+
+    /// Set the current [`OverflowWrap`], which decides whether words wider than the wrap width
+    /// are broken when using [`Wrap::Word`]
+    pub fn set_overflow_wrap(&mut self, font_system: &mut FontSystem, overflow_wrap: OverflowWrap) {
+        if overflow_wrap != self.overflow_wrap {
+            self.overflow_wrap = overflow_wrap;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
     /// Get the current `monospace_width`
     pub fn monospace_width(&self) -> Option<f32> {
         self.monospace_width
@@ -615,12 +1113,213 @@ impl Buffer {
         }
     }
 
+    /// Get the [`LayoutOptions`] used to shape and lay out lines, see
+    /// [`BufferLine::layout_with_options`]
+    pub fn layout_options(&self) -> LayoutOptions {
+        self.layout_options
+    }
+
+    /// Get whether shaping runs are split at every attribute span boundary
+    pub fn preserve_span_boundaries(&self) -> bool {
+        self.layout_options.preserve_span_boundaries
+    }
+
+    /// Split shaping runs at every attribute span boundary, even where adjacent spans resolve to
+    /// the same font. This keeps kerning and ligatures from crossing spans
+    pub fn set_preserve_span_boundaries(
+        &mut self,
+        font_system: &mut FontSystem,
+        preserve_span_boundaries: bool,
+    ) {
+        if preserve_span_boundaries != self.layout_options.preserve_span_boundaries {
+            self.layout_options.preserve_span_boundaries = preserve_span_boundaries;
+            // Shaping must be reset when run splitting is changed
+            for line in self.lines.iter_mut() {
+                if line.shape_opt().is_some() {
+                    line.reset_shaping();
+                }
+            }
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get whether zero-advance glyphs are emitted as separate [`LayoutGlyph`]s
+    pub fn emit_zero_advance_glyphs(&self) -> bool {
+        self.layout_options.emit_zero_advance_glyphs
+    }
+
+    /// Set whether zero-advance glyphs, such as combining marks, are emitted as separate
+    /// [`LayoutGlyph`]s in layout runs. When disabled, they are folded into
+    /// [`LayoutGlyph::marks`] of their base glyph, which changes the number of glyphs per run but
+    /// not what is drawn. Enabled by default
+    pub fn set_emit_zero_advance_glyphs(
+        &mut self,
+        font_system: &mut FontSystem,
+        emit_zero_advance_glyphs: bool,
+    ) {
+        if emit_zero_advance_glyphs != self.layout_options.emit_zero_advance_glyphs {
+            self.layout_options.emit_zero_advance_glyphs = emit_zero_advance_glyphs;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the grid that reported glyph X positions are snapped to, if any
+    pub fn position_quantization(&self) -> Option<f32> {
+        self.layout_options.position_quantization
+    }
+
+    /// Snap the X position of every [`LayoutGlyph`] to a multiple of `step`, for example `0.25`
+    /// to match a glyph atlas with quarter-pixel positions. Positions are rounded individually
+    /// from their exact values, so rounding errors do not accumulate along the line, and glyph
+    /// widths are left unchanged. With a `step` that divides a pixel, the [`crate::SubpixelBin`]
+    /// of the cache keys follows the quantized positions. `None` or a non-positive `step`
+    /// disables quantization, which is the default
+    pub fn set_position_quantization(
+        &mut self,
+        font_system: &mut FontSystem,
+        step_opt: Option<f32>,
+    ) {
+        let step_opt = step_opt.filter(|step| *step > 0.0);
+        if step_opt != self.layout_options.position_quantization {
+            self.layout_options.position_quantization = step_opt;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the current [`LineEndingPolicy`]
+    pub fn line_ending_policy(&self) -> LineEndingPolicy {
+        self.line_ending_policy
+    }
+
+    /// Set how the line endings of text set with [`Buffer::set_text`] and
+    /// [`Buffer::set_rich_text`] are normalized. The policy is also applied to the endings of the
+    /// current lines, except with [`LineEndingPolicy::Auto`] which cannot restore the original
+    /// endings
+    pub fn set_line_ending_policy(&mut self, line_ending_policy: LineEndingPolicy) {
+        self.line_ending_policy = line_ending_policy;
+        for line in self.lines.iter_mut() {
+            line.set_ending(line_ending_policy.apply(line.ending()));
+        }
+    }
+
+    /// Get the current [`EmptyLineMetrics`]
+    pub fn empty_line_metrics(&self) -> EmptyLineMetrics {
+        self.empty_line_metrics
+    }
+
+    /// Set where the line height of empty lines, such as blank lines between paragraphs, comes
+    /// from. With [`EmptyLineMetrics::PreviousLine`], the height is taken when the empty line is
+    /// laid out
+    pub fn set_empty_line_metrics(
+        &mut self,
+        font_system: &mut FontSystem,
+        empty_line_metrics: EmptyLineMetrics,
+    ) {
+        if empty_line_metrics != self.empty_line_metrics {
+            self.empty_line_metrics = empty_line_metrics;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the current [`LeadingDistribution`]
+    pub fn leading_distribution(&self) -> LeadingDistribution {
+        self.leading_distribution
+    }
+
+    /// Set where the leading of each line, the line height not taken by its glyphs, is placed.
+    /// Only the positions of lines change, so no layout is needed
+    pub fn set_leading_distribution(&mut self, leading_distribution: LeadingDistribution) {
+        if leading_distribution != self.leading_distribution {
+            self.leading_distribution = leading_distribution;
+            self.redraw = true;
+        }
+    }
+
+    /// Get the locale overriding the [`FontSystem`] locale, if any
+    pub fn locale(&self) -> Option<&str> {
+        self.locale_opt.as_deref()
+    }
+
+    /// Set the locale used for font fallback and language resolution when shaping this buffer.
+    /// `None` uses the [`FontSystem`] locale
+    pub fn set_locale(&mut self, font_system: &mut FontSystem, locale_opt: Option<&str>) {
+        if locale_opt != self.locale_opt.as_deref() {
+            self.locale_opt = locale_opt.map(Into::into);
+            // Shaping must be reset when locale is changed
+            for line in self.lines.iter_mut() {
+                if line.shape_opt().is_some() {
+                    line.reset_shaping();
+                }
+            }
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the fonts shaping of this buffer is restricted to, if any
+    pub fn font_whitelist(&self) -> Option<&[fontdb::ID]> {
+        self.font_whitelist.as_deref()
+    }
+
+    /// Restrict font matching and fallback when shaping this buffer to the fonts
+    /// `font_whitelist`, ignoring all other fonts in the database, for example to render a
+    /// document with only its embedded fonts. Characters missing from these fonts are drawn as
+    /// missing glyphs, as is all text if no whitelisted font matches its attributes. `None` uses
+    /// all fonts
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FontSystemError::NoFonts`] and keeps the current whitelist if `font_whitelist`
+    /// is empty
+    pub fn set_font_whitelist(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_whitelist: Option<Vec<fontdb::ID>>,
+    ) -> Result<(), FontSystemError> {
+        if font_whitelist.as_ref().is_some_and(Vec::is_empty) {
+            return Err(FontSystemError::NoFonts);
+        }
+        if font_whitelist != self.font_whitelist {
+            self.font_whitelist = font_whitelist;
+            // Shaping must be reset when the fonts change
+            for line in self.lines.iter_mut() {
+                if line.shape_opt().is_some() {
+                    line.reset_shaping();
+                }
+            }
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+        Ok(())
+    }
+
+    /// Get the current [`Baseline`]
+    pub fn baseline(&self) -> Baseline {
+        self.layout_options.baseline
+    }
+
+    /// Set the dominant [`Baseline`] that glyphs from different fonts are aligned on
+    pub fn set_baseline(&mut self, font_system: &mut FontSystem, baseline: Baseline) {
+        if baseline != self.layout_options.baseline {
+            self.layout_options.baseline = baseline;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
     /// Get the current buffer dimensions (width, height)
     pub fn size(&self) -> (Option<f32>, Option<f32>) {
         (self.width_opt, self.height_opt)
     }
 
     /// Set the current buffer dimensions
+    ///
+    /// Changing only the width re-wraps lines using their cached shaping, and changing only the
+    /// height keeps the existing layout.
     pub fn set_size(
         &mut self,
         font_system: &mut FontSystem,
@@ -645,22 +1344,354 @@ impl Buffer {
         let clamped_width_opt = width_opt.map(|width| width.max(0.0));
         let clamped_height_opt = height_opt.map(|height| height.max(0.0));
 
-        if metrics != self.metrics
-            || clamped_width_opt != self.width_opt
-            || clamped_height_opt != self.height_opt
-        {
-            assert_ne!(metrics.font_size, 0.0, "font size cannot be 0");
-            self.metrics = metrics;
-            self.width_opt = clamped_width_opt;
-            self.height_opt = clamped_height_opt;
-            self.relayout(font_system);
+        if metrics != self.metrics || clamped_width_opt != self.width_opt {
+            assert_ne!(metrics.font_size, 0.0, "font size cannot be 0");
+            self.metrics = metrics;
+            self.width_opt = clamped_width_opt;
+            self.height_opt = clamped_height_opt;
+            // Shaping does not depend on metrics or width, so only layout is redone
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        } else if clamped_height_opt != self.height_opt {
+            // Height only changes which lines are visible, so existing layout is kept
+            self.height_opt = clamped_height_opt;
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the current scroll location
+    pub fn scroll(&self) -> Scroll {
+        self.scroll
+    }
+
+    /// Wrap text to `width` and align it within a box of `width` and `height`, horizontally using
+    /// `h_align` for every line and vertically using `v_align` for the whole block.
+    ///
+    /// Scroll is reset, and the vertical offset is applied to [`Buffer::layout_runs`] and hit
+    /// testing until this is called again. Text taller than the box is aligned to the top.
+    /// Returns the rectangle `(x, y, width, height)` covered by the laid out text
+    pub fn layout_in_box(
+        &mut self,
+        font_system: &mut FontSystem,
+        width: f32,
+        height: f32,
+        h_align: Option<Align>,
+        v_align: VerticalAlign,
+    ) -> (f32, f32, f32, f32) {
+        for line in self.lines.iter_mut() {
+            line.set_align(h_align);
+        }
+        self.scroll = Scroll::default();
+        self.set_size(font_system, Some(width), Some(height));
+
+        let line_height = self.metrics.line_height;
+        let mut content_height = 0.0;
+        let mut x_min = f32::INFINITY;
+        let mut x_max = f32::NEG_INFINITY;
+        for line_i in 0..self.lines.len() {
+            let Some(layout) = self.line_layout(font_system, line_i) else {
+                continue;
+            };
+            for layout_line in layout {
+                content_height += layout_line.line_height_opt.unwrap_or(line_height);
+                for glyph in layout_line.glyphs.iter() {
+                    x_min = x_min.min(glyph.x);
+                    x_max = x_max.max(glyph.x + glyph.w);
+                }
+            }
+        }
+
+        let free_height = (height - content_height).max(0.0);
+        self.vertical_offset = match v_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Center => free_height / 2.0,
+            VerticalAlign::Bottom => free_height,
+        };
+        self.redraw = true;
+        self.shape_until_scroll(font_system, false);
+
+        if x_min > x_max {
+            (0.0, self.vertical_offset, 0.0, content_height)
+        } else {
+            (x_min, self.vertical_offset, x_max - x_min, content_height)
+        }
+    }
+
+    /// Choose the largest font size between `min_size` and `max_size` at which the text, wrapped
+    /// to `width`, fits in a box of `width` and `height`. The line height is scaled with the
+    /// font size.
+    ///
+    /// If the text does not fit at `min_size`, it is truncated at the end with `ellipsis` instead
+    /// of shrinking further. Scroll is reset. Returns the chosen font size
+    pub fn fit_to(
+        &mut self,
+        font_system: &mut FontSystem,
+        width: f32,
+        height: f32,
+        min_size: f32,
+        max_size: f32,
+        ellipsis: &str,
+    ) -> f32 {
+        let line_height_scale = self.metrics.line_height / self.metrics.font_size;
+        self.scroll = Scroll::default();
+        let fits_at = |buffer: &mut Self, font_system: &mut FontSystem, font_size: f32| {
+            let metrics = Metrics::relative(font_size, line_height_scale);
+            buffer.set_metrics_and_size(font_system, metrics, Some(width), Some(height));
+            buffer.fits_in(font_system, width, height)
+        };
+
+        if fits_at(self, font_system, max_size) {
+            return max_size;
+        }
+        if !fits_at(self, font_system, min_size) {
+            self.truncate_end_to_fit(font_system, width, height, ellipsis);
+            return min_size;
+        }
+
+        // Binary search to half a pixel, the lower bound always fits
+        let (mut low, mut high) = (min_size, max_size);
+        while high - low > 0.5 {
+            let mid = (low + high) / 2.0;
+            if fits_at(self, font_system, mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        fits_at(self, font_system, low);
+        low
+    }
+
+    /// True if all lines laid out with the current metrics and width fit in `width` and `height`
+    fn fits_in(&mut self, font_system: &mut FontSystem, width: f32, height: f32) -> bool {
+        let line_height = self.metrics.line_height;
+        let mut content_width: f32 = 0.0;
+        let mut content_height = 0.0;
+        for line_i in 0..self.lines.len() {
+            let Some(layout) = self.line_layout(font_system, line_i) else {
+                continue;
+            };
+            for layout_line in layout {
+                content_width = content_width.max(layout_line.w);
+                content_height += layout_line.line_height_opt.unwrap_or(line_height);
+            }
+        }
+        content_width <= width && content_height <= height
+    }
+
+    /// Find the smallest width at which the text wraps to at most `max_lines` lines, for example
+    /// to balance a block of text.
+    ///
+    /// Candidate widths are only re-wrapped, reusing the shaping of each line, and the width of
+    /// the buffer is restored afterwards. If the text has more than `max_lines` lines even
+    /// without wrapping, the width of its widest line is returned
+    pub fn min_width_for_lines(&mut self, font_system: &mut FontSystem, max_lines: usize) -> f32 {
+        let (width_opt, height_opt) = (self.width_opt, self.height_opt);
+        let wrap_at = |buffer: &mut Self, font_system: &mut FontSystem, width_opt: Option<f32>| {
+            buffer.set_size(font_system, width_opt, height_opt);
+            let mut line_count = 0;
+            let mut max_line_w: f32 = 0.0;
+            for line_i in 0..buffer.lines.len() {
+                for layout_line in buffer.line_layout(font_system, line_i).unwrap_or_default() {
+                    line_count += 1;
+                    max_line_w = max_line_w.max(layout_line.w);
+                }
+            }
+            (line_count, max_line_w)
+        };
+
+        let (line_count, mut width) = wrap_at(self, font_system, None);
+        if line_count <= max_lines {
+            // Binary search while the upper bound wraps to at most `max_lines`
+            let mut low = 0.0;
+            while width - low > 0.01 {
+                let mid = (low + width) / 2.0;
+                let (line_count, max_line_w) = wrap_at(self, font_system, Some(mid));
+                if line_count <= max_lines {
+                    // Wrapping at the widest line gives the same breaks
+                    width = max_line_w;
+                } else {
+                    low = mid;
+                }
+            }
+        }
+
+        self.set_size(font_system, width_opt, height_opt);
+        width
+    }
+
+    /// Keep the most graphemes of the text followed by `ellipsis` that fit in a box of `width`
+    /// and `height`, dropping the rest
+    fn truncate_end_to_fit(
+        &mut self,
+        font_system: &mut FontSystem,
+        width: f32,
+        height: f32,
+        ellipsis: &str,
+    ) {
+        let lines = mem::take(&mut self.lines);
+        let boundaries: Vec<(usize, usize)> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line_i, line)| {
+                line.text()
+                    .grapheme_indices(true)
+                    .map(move |(i, _)| (line_i, i))
+                    .chain(core::iter::once((line_i, line.text().len())))
+            })
+            .collect();
+
+        let truncated = |&(line_i, index): &(usize, usize)| {
+            let mut candidate = lines[..=line_i].to_vec();
+            let last = &mut candidate[line_i];
+            let mut removed = last.split_off(index);
+            let ellipsis_attrs = AttrsList::new(&removed.attrs_list().get_span(0));
+            removed.set_text(ellipsis, last.ending(), ellipsis_attrs);
+            last.append(removed);
+            candidate
+        };
+
+        // Binary search for the last boundary that fits, keeping at least the ellipsis
+        let (mut low, mut high) = (0, boundaries.len());
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            self.lines = truncated(&boundaries[mid]);
+            if self.fits_in(font_system, width, height) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        self.lines = match boundaries.get(low) {
+            Some(boundary) => truncated(boundary),
+            None => lines,
+        };
+        self.redraw = true;
+        self.shape_until_scroll(font_system, false);
+    }
+
+    /// Take the rectangles `(x, y, width, height)` of visible lines that were laid out since the
+    /// last call, such as edited lines, for scissoring partial redraws.
+    ///
+    /// Lines laid out while scrolled out of view are not returned. Rectangles span the buffer
+    /// width, or the widest laid out line if the buffer has no width
+    pub fn take_dirty_rects(&mut self) -> Vec<(f32, f32, f32, f32)> {
+        let mut dirty = Vec::with_capacity(self.lines.len());
+        for line in self.lines.iter_mut() {
+            dirty.push(line.take_layout_dirty());
+        }
+
+        let mut rects: Vec<(usize, (f32, f32, f32, f32))> = Vec::new();
+        let mut max_line_w: f32 = 0.0;
+        for run in self.layout_runs() {
+            max_line_w = max_line_w.max(run.line_w);
+            if !dirty[run.line_i] {
+                continue;
+            }
+            match rects.last_mut() {
+                Some((line_i, rect)) if *line_i == run.line_i => {
+                    rect.3 = run.line_top + run.line_height - rect.1;
+                }
+                _ => rects.push((run.line_i, (0.0, run.line_top, 0.0, run.line_height))),
+            }
+        }
+
+        let width = self.width_opt.unwrap_or(max_line_w);
+        rects
+            .into_iter()
+            .map(|(_, (x, y, _, h))| (x, y, width, h))
+            .collect()
+    }
+
+    /// Truncate the middle of each line wider than `width`, replacing whole segments between
+    /// `separators` with `ellipsis` until it fits, so the first and last segments stay visible.
+    ///
+    /// Segments are removed outwards from the middle of the line. If removing every middle
+    /// segment is not enough, only the first and last segments are kept.
+    /// Returns true if any line was truncated
+    pub fn truncate_middle_at(
+        &mut self,
+        font_system: &mut FontSystem,
+        width: f32,
+        separators: &[char],
+        ellipsis: &str,
+    ) -> bool {
+        let mut truncated = false;
+        for line_i in 0..self.lines.len() {
+            if self.unwrapped_width(font_system, &mut self.lines[line_i].clone()) <= width {
+                continue;
+            }
+
+            let seps: Vec<usize> = self.lines[line_i]
+                .text()
+                .char_indices()
+                .filter(|(_, c)| separators.contains(c))
+                .map(|(i, _)| i)
+                .collect();
+            if seps.len() < 2 {
+                continue;
+            }
+
+            // Remove the segments between separators `k` and `j`
+            let mut k = (seps.len() - 1) / 2;
+            let mut j = k + 1;
+            let mut grow_end = true;
+            let candidate = loop {
+                let mut candidate = self.lines[line_i].clone();
+                let sep_len = candidate.text()[seps[k]..]
+                    .chars()
+                    .next()
+                    .map_or(0, char::len_utf8);
+                let tail = candidate.split_off(seps[j]);
+                let mut removed = candidate.split_off(seps[k] + sep_len);
+                let ellipsis_attrs = AttrsList::new(&removed.attrs_list().get_span(0));
+                removed.set_text(ellipsis, candidate.ending(), ellipsis_attrs);
+                candidate.append(removed);
+                candidate.append(tail);
+
+                let at_ends = k == 0 && j == seps.len() - 1;
+                if at_ends || self.unwrapped_width(font_system, &mut candidate) <= width {
+                    break candidate;
+                }
+                if (grow_end && j < seps.len() - 1) || k == 0 {
+                    j += 1;
+                } else {
+                    k -= 1;
+                }
+                grow_end = !grow_end;
+            };
+
+            self.lines[line_i] = candidate;
+            truncated = true;
+        }
+
+        if truncated {
+            self.redraw = true;
             self.shape_until_scroll(font_system, false);
         }
+        truncated
     }
 
-    /// Get the current scroll location
-    pub fn scroll(&self) -> Scroll {
-        self.scroll
+    /// Width of `line` laid out without wrapping
+    fn unwrapped_width(&self, font_system: &mut FontSystem, line: &mut BufferLine) -> f32 {
+        line.reset_layout();
+        font_system.with_locale(self.locale_opt.as_deref(), |font_system| {
+            font_system.with_font_whitelist(self.font_whitelist.as_deref(), |font_system| {
+                line.layout_with_options(
+                    font_system,
+                    self.metrics.font_size,
+                    None,
+                    Wrap::None,
+                    self.monospace_width,
+                    self.tab_width,
+                    &self.layout_options,
+                )
+                .iter()
+                .fold(0.0f32, |width, layout_line| width.max(layout_line.w))
+            })
+        })
     }
 
     /// Set the current scroll location
@@ -683,11 +1714,57 @@ impl Buffer {
         for (range, ending) in LineIter::new(text) {
             self.lines.push(BufferLine::new(
                 &text[range],
-                ending,
+                self.line_ending_policy.apply(ending),
+                AttrsList::new(attrs),
+                shaping,
+            ));
+        }
+        if self.lines.is_empty() {
+            self.lines.push(BufferLine::new(
+                "",
+                LineEnding::default(),
                 AttrsList::new(attrs),
                 shaping,
             ));
         }
+        self.scroll = Scroll::default();
+        self.shape_until_scroll(font_system, false);
+    }
+
+    /// Set text of buffer like [`Buffer::set_text`], shaping each byte range of `text` in
+    /// `font_runs` with its font instead of resolving fonts and fallbacks, see [`Attrs::font_id`].
+    ///
+    /// This allows a second shaping pass to reuse the fonts chosen by a first pass, for example
+    /// collected from [`LayoutGlyph::font_id`], so it is cheaper and cannot pick different fonts.
+    /// Text outside of `font_runs` resolves fonts as usual
+    pub fn set_text_with_font_runs(
+        &mut self,
+        font_system: &mut FontSystem,
+        text: &str,
+        font_runs: &[(Range<usize>, fontdb::ID)],
+        attrs: &Attrs,
+        shaping: Shaping,
+    ) {
+        self.lines.clear();
+        for (range, ending) in LineIter::new(text) {
+            let mut attrs_list = AttrsList::new(attrs);
+            for (font_range, font_id) in font_runs {
+                let start = font_range.start.max(range.start);
+                let end = font_range.end.min(range.end);
+                if start < end {
+                    attrs_list.add_span(
+                        start - range.start..end - range.start,
+                        &attrs.clone().font_id(*font_id),
+                    );
+                }
+            }
+            self.lines.push(BufferLine::new(
+                &text[range],
+                self.line_ending_policy.apply(ending),
+                attrs_list,
+                shaping,
+            ));
+        }
         if self.lines.is_empty() {
             self.lines.push(BufferLine::new(
                 "",
@@ -700,6 +1777,33 @@ impl Buffer {
         self.shape_until_scroll(font_system, false);
     }
 
+    /// Append a line of `text` to the end of the buffer, shaping and laying out only the new line.
+    ///
+    /// This is useful for logs and consoles, where rebuilding the whole buffer on every line
+    /// would reshape all previous lines. Existing lines, and so cursors and selections in them,
+    /// are left untouched, except that a last line without a line ending is given one
+    pub fn append_line(
+        &mut self,
+        font_system: &mut FontSystem,
+        text: &str,
+        attrs: &Attrs,
+        shaping: Shaping,
+    ) {
+        if let Some(last) = self.lines.last_mut() {
+            if last.ending() == LineEnding::None {
+                last.set_ending(self.line_ending_policy.apply(LineEnding::default()));
+            }
+        }
+        self.lines.push(BufferLine::new(
+            text,
+            LineEnding::default(),
+            AttrsList::new(attrs),
+            shaping,
+        ));
+        self.line_layout(font_system, self.lines.len() - 1);
+        self.redraw = true;
+    }
+
     /// Set text of buffer, using an iterator of styled spans (pairs of text and attributes)
     ///
     /// ```
@@ -742,16 +1846,26 @@ impl Buffer {
         let mut spans_iter = spans_data.into_iter();
         let mut maybe_span = spans_iter.next();
 
-        // split the string into lines, as ranges
+        // split the string into lines, as ranges with the line ending that follows them
         let string_start = string.as_ptr() as usize;
-        let mut lines_iter = BidiParagraphs::new(&string).map(|line: &str| {
+        let line_ending_policy = self.line_ending_policy;
+        let mut consumed = 0;
+        let mut lines_iter = BidiParagraphs::new(&string).filter_map(|line: &str| {
             let start = line.as_ptr() as usize - string_start;
+            if start < consumed {
+                // The second half of a two character line ending, such as `\r\n`
+                return None;
+            }
             let end = start + line.len();
-            start..end
+            let ending = match LineEnding::from_prefix(&string[end..]) {
+                // Other paragraph separators are replaced by the default line ending
+                LineEnding::None if end < string.len() => LineEnding::default(),
+                ending => ending,
+            };
+            consumed = end + ending.as_str().len();
+            Some((start..end, line_ending_policy.apply(ending)))
         });
         let mut maybe_line = lines_iter.next();
-        //TODO: set this based on information from spans
-        let line_ending = LineEnding::default();
 
         let mut line_count = 0;
         let mut attrs_list = self
@@ -767,14 +1881,16 @@ impl Buffer {
             .unwrap_or_default();
 
         loop {
-            let (Some(line_range), Some((attrs, span_range))) = (&maybe_line, &maybe_span) else {
+            let (Some((line_range, line_ending)), Some((attrs, span_range))) =
+                (maybe_line.clone(), &maybe_span)
+            else {
                 // this is reached only if this text is empty
                 if self.lines.len() == line_count {
                     self.lines.push(BufferLine::empty());
                 }
                 self.lines[line_count].reset_new(
                     String::new(),
-                    line_ending,
+                    LineEnding::default(),
                     AttrsList::new(default_attrs),
                     shaping,
                 );
@@ -865,10 +1981,364 @@ impl Buffer {
     }
 
     /// Get the visible layout runs for rendering and other tasks
-    pub fn layout_runs(&self) -> LayoutRunIter {
+    pub fn layout_runs(&self) -> LayoutRunIter<'_> {
         LayoutRunIter::new(self)
     }
 
+    /// Get the ids of all fonts used by laid out lines, including fallback fonts, for example to
+    /// embed exactly the fonts needed when exporting a document.
+    ///
+    /// Lines that were not laid out, such as those past the scroll position that were never
+    /// shaped, are not included.
+    pub fn used_font_ids(&self) -> BTreeSet<fontdb::ID> {
+        self.lines
+            .iter()
+            .filter_map(BufferLine::layout_opt)
+            .flatten()
+            .flat_map(|layout_line| layout_line.glyphs.iter())
+            .flat_map(LayoutGlyph::with_marks)
+            .map(|glyph| glyph.font_id)
+            .collect()
+    }
+
+    /// Get the distinct `(font_id, font_size)` pairs of all glyphs in laid out lines, sorted by
+    /// font id and size, for example to allocate glyph atlas pages before rendering.
+    ///
+    /// Like [`Buffer::used_font_ids`], lines that were not laid out are not included.
+    pub fn required_atlas_keys(&self) -> Vec<(fontdb::ID, f32)> {
+        let keys: BTreeSet<(fontdb::ID, u32)> = self
+            .lines
+            .iter()
+            .filter_map(BufferLine::layout_opt)
+            .flatten()
+            .flat_map(|layout_line| layout_line.glyphs.iter())
+            .flat_map(LayoutGlyph::with_marks)
+            .map(|glyph| (glyph.font_id, glyph.font_size.to_bits()))
+            .collect();
+        keys.into_iter()
+            .map(|(font_id, font_size)| (font_id, f32::from_bits(font_size)))
+            .collect()
+    }
+
+    /// Get the rectangles `(x, y, width, height)` covering the bytes `range` of line `line_i`,
+    /// for example to highlight a search match or a link.
+    ///
+    /// A rectangle is returned for every visual piece of the range: one per wrapped visible
+    /// line, and more where bidirectional text reorders the range into separate pieces. Clusters
+    /// partially covered by the range, such as ligatures, are covered whole
+    pub fn range_rects(&self, line_i: usize, range: Range<usize>) -> Vec<(f32, f32, f32, f32)> {
+        let mut rects = Vec::new();
+        if range.is_empty() {
+            return rects;
+        }
+        for run in self.layout_runs().filter(|run| run.line_i == line_i) {
+            let mut glyphs: Vec<&LayoutGlyph> = run.glyphs.iter().collect();
+            glyphs.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+            let mut rect_opt: Option<(f32, f32)> = None;
+            for glyph in glyphs {
+                if glyph.start < range.end && range.start < glyph.end {
+                    let (start, end) = rect_opt.get_or_insert((glyph.x, glyph.x));
+                    *start = start.min(glyph.x);
+                    *end = end.max(glyph.x + glyph.w);
+                } else if let Some((start, end)) = rect_opt.take() {
+                    rects.push((start, run.line_top, end - start, run.line_height));
+                }
+            }
+            if let Some((start, end)) = rect_opt {
+                rects.push((start, run.line_top, end - start, run.line_height));
+            }
+        }
+        rects
+    }
+
+    /// Get the bounding box `(min_x, min_y, max_x, max_y)` of the glyph advances and line heights
+    /// of the visible laid out text, or `None` if there are no glyphs. Runs without glyphs, such as
+    /// empty lines, are not included.
+    ///
+    /// This is [`Buffer::bounds`] with [`BoundsMode::AdvanceWithTrailingWhitespace`] when there
+    /// are glyphs, without needing a [`FontSystem`]
+    pub fn layout_bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        self.layout_runs()
+            .filter_map(|run| run.advance_bounds(true))
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+    }
+
+    /// Get the bounding box `(min_x, min_y, max_x, max_y)` of the visible laid out text, or
+    /// `None` if there is nothing to bound. See [`BoundsMode`] for the extents used.
+    ///
+    /// With advance bounds, every run contributes the full height of its line, and the horizontal
+    /// extent is zero if there are no glyphs
+    pub fn bounds(
+        &self,
+        font_system: &mut FontSystem,
+        mode: BoundsMode,
+    ) -> Option<(f32, f32, f32, f32)> {
+        let mut bounds_opt: Option<(f32, f32, f32, f32)> = None;
+        let mut add = |bounds: (f32, f32, f32, f32)| {
+            let (min_x, min_y, max_x, max_y) = bounds_opt.get_or_insert(bounds);
+            *min_x = min_x.min(bounds.0);
+            *min_y = min_y.min(bounds.1);
+            *max_x = max_x.max(bounds.2);
+            *max_y = max_y.max(bounds.3);
+        };
+
+        let mut has_glyphs = false;
+        for run in self.layout_runs() {
+            match mode {
+                BoundsMode::Advance | BoundsMode::AdvanceWithTrailingWhitespace => {
+                    let trailing_whitespace = mode == BoundsMode::AdvanceWithTrailingWhitespace;
+                    match run.advance_bounds(trailing_whitespace) {
+                        Some(bounds) => {
+                            has_glyphs = true;
+                            add(bounds);
+                        }
+                        None => add((
+                            f32::INFINITY,
+                            run.line_top,
+                            f32::NEG_INFINITY,
+                            run.line_top + run.line_height,
+                        )),
+                    }
+                }
+                BoundsMode::Ink => {
+                    for glyph in run.glyphs.iter().flat_map(LayoutGlyph::with_marks) {
+                        let Some(font) = font_system.get_font(glyph.font_id) else {
+                            continue;
+                        };
+                        let face = font.rustybuzz();
+                        let Some(rect) =
+                            face.glyph_bounding_box(ttf_parser::GlyphId(glyph.glyph_id))
+                        else {
+                            continue;
+                        };
+                        has_glyphs = true;
+                        let scale = glyph.font_size / face.units_per_em() as f32;
+                        let x = glyph.x + glyph.x_offset * glyph.font_size;
+                        let y = run.line_y + glyph.y - glyph.y_offset * glyph.font_size;
+                        add((
+                            x + f32::from(rect.x_min) * scale,
+                            y - f32::from(rect.y_max) * scale,
+                            x + f32::from(rect.x_max) * scale,
+                            y - f32::from(rect.y_min) * scale,
+                        ));
+                    }
+                }
+            }
+        }
+
+        if !has_glyphs {
+            if let Some((min_x, _, max_x, _)) = bounds_opt.as_mut() {
+                *min_x = 0.0;
+                *max_x = 0.0;
+            }
+        }
+        bounds_opt
+    }
+
+    /// Get the attributes in effect at `cursor`, or `None` if the cursor line does not exist.
+    ///
+    /// At a span boundary the attributes of the following span are returned. At the end of a
+    /// line, the attributes of the last character are returned
+    pub fn attrs_at(&self, cursor: Cursor) -> Option<AttrsOwned> {
+        let line = self.lines.get(cursor.line)?;
+        let text = line.text();
+        let index = if cursor.index >= text.len() {
+            text[..text.len().min(cursor.index)]
+                .char_indices()
+                .next_back()
+                .map_or(0, |(i, _)| i)
+        } else {
+            cursor.index
+        };
+        Some(AttrsOwned::new(&line.attrs_list().get_span(index)))
+    }
+
+    /// Get the visible glyphs as parallel arrays, using `color` for glyphs without a color
+    /// override
+    pub fn glyph_run_data(&self, color: Color) -> GlyphRunData {
+        let mut data = GlyphRunData::default();
+        for run in self.layout_runs() {
+            for glyph in run.glyphs.iter() {
+                let index = data.glyph_ids.len();
+                data.glyph_ids.push(glyph.glyph_id);
+                data.font_ids.push(glyph.font_id);
+                data.xs.push(glyph.x + glyph.font_size * glyph.x_offset);
+                data.ys
+                    .push(run.line_y + glyph.y - glyph.font_size * glyph.y_offset);
+                data.font_sizes.push(glyph.font_size);
+                data.colors.push(glyph.color(color));
+
+                match data.batches.iter_mut().find(|batch| {
+                    batch.font_id == glyph.font_id && batch.font_size == glyph.font_size
+                }) {
+                    Some(batch) => batch.indices.push(index),
+                    None => data.batches.push(GlyphBatch {
+                        font_id: glyph.font_id,
+                        font_size: glyph.font_size,
+                        indices: alloc::vec![index],
+                    }),
+                }
+            }
+        }
+        data
+    }
+
+    /// Get the source text of the visual line `visual_line`, counting the visual lines of all
+    /// lines from the start of the buffer, for example to map a wrapped line back to the logical
+    /// line for syntax highlighting. The ranges of the visual lines of a line do not overlap.
+    ///
+    /// Returns `None` if `visual_line` is past the last visual line, or if a line up to it was
+    /// not laid out.
+    pub fn line_source_range(&self, visual_line: usize) -> Option<LineSource> {
+        let mut first_visual_line = 0;
+        for (line_i, line) in self.lines.iter().enumerate() {
+            let layout = line.layout_opt()?;
+            let Some(layout_i) = visual_line
+                .checked_sub(first_visual_line)
+                .filter(|&layout_i| layout_i < layout.len())
+            else {
+                first_visual_line += layout.len();
+                continue;
+            };
+
+            // Visual lines without glyphs start where the previous one ended
+            let mut ranges = Vec::with_capacity(layout.len());
+            let mut end = 0;
+            for layout_line in layout {
+                let range = layout_line
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.start..glyph.end)
+                    .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+                    .unwrap_or(end..end);
+                end = range.end;
+                ranges.push(range);
+            }
+
+            let range = ranges[layout_i].clone();
+            let prev_end = layout_i
+                .checked_sub(1)
+                .map_or(0, |prev_i| ranges[prev_i].end);
+            let next_start = ranges
+                .get(layout_i + 1)
+                .map_or(line.text().len(), |next| next.start);
+            return Some(LineSource {
+                line_i,
+                trimmed_start: range.start > prev_end,
+                trimmed_end: range.end < next_start,
+                range,
+            });
+        }
+        None
+    }
+
+    /// Split the visual lines into pages of at most `page_height`, never splitting a visual line.
+    /// A page only holds more than that if its first line is taller than `page_height`.
+    ///
+    /// When a line is wrapped across two pages, each part keeps at least `min_lines` visual
+    /// lines if the page can give them up, avoiding widows and orphans. Pass `1` to fill every
+    /// page.
+    ///
+    /// Only the lines up to the first line that was not laid out are paginated.
+    pub fn paginate(&self, page_height: f32, min_lines: usize) -> Vec<Page> {
+        // Height of each visual line and the index of its line
+        let mut visual_lines = Vec::new();
+        for (line_i, line) in self.lines.iter().enumerate() {
+            let Some(layout) = line.layout_opt() else {
+                break;
+            };
+            visual_lines.extend(layout.iter().map(|layout_line| {
+                let height = layout_line
+                    .line_height_opt
+                    .unwrap_or(self.metrics.line_height);
+                (line_i, height)
+            }));
+        }
+
+        let mut pages = Vec::new();
+        let mut start = 0;
+        let mut y_offset = 0.0;
+        while start < visual_lines.len() {
+            let mut end = start + 1;
+            let mut height = visual_lines[start].1;
+            while let Some(&(_, line_height)) = visual_lines.get(end) {
+                if height + line_height > page_height {
+                    break;
+                }
+                height += line_height;
+                end += 1;
+            }
+
+            // Keep enough visual lines of a line split across pages on both pages
+            if let Some(&(line_i, _)) = visual_lines.get(end).filter(|_| min_lines > 1) {
+                let before = visual_lines[start..end]
+                    .iter()
+                    .rev()
+                    .take_while(|(other_i, _)| *other_i == line_i)
+                    .count();
+                let after = visual_lines[end..]
+                    .iter()
+                    .take_while(|(other_i, _)| *other_i == line_i)
+                    .count();
+                let moved = if before == 0 || (before >= min_lines && after >= min_lines) {
+                    0
+                } else if after < min_lines && before >= 2 * min_lines - after {
+                    min_lines - after
+                } else {
+                    before
+                };
+                if moved < end - start {
+                    end -= moved;
+                }
+            }
+
+            pages.push(Page {
+                line_range: start..end,
+                y_offset,
+            });
+            y_offset += visual_lines[start..end]
+                .iter()
+                .map(|(_, height)| height)
+                .sum::<f32>();
+            start = end;
+        }
+        pages
+    }
+
+    /// Get the visible text as runs of a single direction per visual line, with their byte ranges
+    /// and bounding boxes, in the shape expected by accessibility trees
+    pub fn accessibility_runs(&self) -> Vec<A11yRun> {
+        let mut a11y_runs: Vec<A11yRun> = Vec::new();
+        for run in self.layout_runs() {
+            let mut segment_opt: Option<A11yRun> = None;
+            for glyph in run.glyphs.iter() {
+                let rtl = glyph.level.is_rtl();
+                match &mut segment_opt {
+                    Some(segment) if segment.rtl == rtl => {
+                        let (x, y, w, h) = segment.rect;
+                        let x_min = x.min(glyph.x);
+                        let x_max = (x + w).max(glyph.x + glyph.w);
+                        segment.rect = (x_min, y, x_max - x_min, h);
+                        segment.text_range.start = segment.text_range.start.min(glyph.start);
+                        segment.text_range.end = segment.text_range.end.max(glyph.end);
+                    }
+                    _ => {
+                        a11y_runs.extend(segment_opt.take());
+                        segment_opt = Some(A11yRun {
+                            line_i: run.line_i,
+                            text_range: glyph.start..glyph.end,
+                            rect: (glyph.x, run.line_top, glyph.w, run.line_height),
+                            rtl,
+                        });
+                    }
+                }
+            }
+            a11y_runs.extend(segment_opt);
+        }
+        a11y_runs
+    }
+
     /// Convert x, y position to Cursor (hit detection)
     pub fn hit(&self, x: f32, y: f32) -> Option<Cursor> {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
@@ -968,6 +2438,138 @@ impl Buffer {
         new_cursor_opt
     }
 
+    /// Move `cursor` to the position nearest to `desired_x` on the visual line above it, returning
+    /// the new cursor and the desired x to pass to the next vertical movement. If `desired_x` is
+    /// `None`, the x of `cursor` is used, so that moving through shorter lines and back returns
+    /// to the same column. The cursor does not move on the first visual line
+    pub fn cursor_up(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        desired_x: Option<f32>,
+    ) -> (Cursor, f32) {
+        self.cursor_vertical(font_system, cursor, desired_x, false)
+    }
+
+    /// Move `cursor` to the position nearest to `desired_x` on the visual line below it, see
+    /// [`Buffer::cursor_up`]. The cursor does not move on the last visual line
+    pub fn cursor_down(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        desired_x: Option<f32>,
+    ) -> (Cursor, f32) {
+        self.cursor_vertical(font_system, cursor, desired_x, true)
+    }
+
+    fn cursor_vertical(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        desired_x: Option<f32>,
+        down: bool,
+    ) -> (Cursor, f32) {
+        let Some(layout_len) = self
+            .line_layout(font_system, cursor.line)
+            .map(|layout| layout.len())
+        else {
+            return (cursor, desired_x.unwrap_or(0.0));
+        };
+
+        // Find the visual line of the cursor, preferring an exact match of the affinity
+        let mut layout_i = 0;
+        let mut cursor_x = None;
+        for exact in [true, false] {
+            let found = (0..layout_len).find_map(|i| {
+                self.layout_line_stops(cursor.line, i)
+                    .into_iter()
+                    .find(|(stop, _)| {
+                        stop.index == cursor.index && (!exact || stop.affinity == cursor.affinity)
+                    })
+                    .map(|(_, x)| (i, x))
+            });
+            if let Some((i, x)) = found {
+                layout_i = i;
+                cursor_x = Some(x);
+                break;
+            }
+        }
+        let desired_x = desired_x.or(cursor_x).unwrap_or(0.0);
+
+        let (line_i, layout_i) = if down {
+            if layout_i + 1 < layout_len {
+                (cursor.line, layout_i + 1)
+            } else if cursor.line + 1 < self.lines.len() {
+                (cursor.line + 1, 0)
+            } else {
+                return (cursor, desired_x);
+            }
+        } else if layout_i > 0 {
+            (cursor.line, layout_i - 1)
+        } else if cursor.line > 0 {
+            let Some(prev_len) = self
+                .line_layout(font_system, cursor.line - 1)
+                .map(|layout| layout.len())
+            else {
+                return (cursor, desired_x);
+            };
+            (cursor.line - 1, prev_len.saturating_sub(1))
+        } else {
+            return (cursor, desired_x);
+        };
+        if self.line_layout(font_system, line_i).is_none() {
+            return (cursor, desired_x);
+        }
+
+        let new_cursor = self
+            .layout_line_stops(line_i, layout_i)
+            .into_iter()
+            .min_by(|(_, a), (_, b)| (a - desired_x).abs().total_cmp(&(b - desired_x).abs()))
+            .map_or(Cursor::new(line_i, 0), |(stop, _)| stop);
+        (new_cursor, desired_x)
+    }
+
+    /// Get the cursor positions at every grapheme boundary of the glyphs of a laid out visual
+    /// line, with their x offsets. Grapheme clusters of one glyph, such as ligatures, are split
+    /// evenly
+    fn layout_line_stops(&self, line_i: usize, layout_i: usize) -> Vec<(Cursor, f32)> {
+        let Some(line) = self.lines.get(line_i) else {
+            return Vec::new();
+        };
+        let Some(layout_line) = line.layout_opt().and_then(|layout| layout.get(layout_i)) else {
+            return Vec::new();
+        };
+        let text = line.text();
+        let mut stops = Vec::new();
+        for glyph in layout_line.glyphs.iter() {
+            let cluster = &text[glyph.start..glyph.end];
+            let offsets: Vec<usize> = cluster
+                .grapheme_indices(true)
+                .map(|(i, _)| i)
+                .chain(core::iter::once(cluster.len()))
+                .collect();
+            let total = offsets.len().saturating_sub(1).max(1);
+            let egc_w = glyph.w / total as f32;
+            for (k, offset) in offsets.iter().enumerate() {
+                let affinity = if k < total {
+                    Affinity::After
+                } else {
+                    Affinity::Before
+                };
+                let x = if glyph.level.is_rtl() {
+                    glyph.x + glyph.w - k as f32 * egc_w
+                } else {
+                    glyph.x + k as f32 * egc_w
+                };
+                stops.push((
+                    Cursor::new_with_affinity(line_i, glyph.start + offset, affinity),
+                    x,
+                ));
+            }
+        }
+        stops
+    }
+
     /// Apply a [`Motion`] to a [`Cursor`]
     pub fn cursor_motion(
         &mut self,
@@ -1011,14 +2613,16 @@ impl Buffer {
             Motion::Previous => {
                 let line = self.lines.get(cursor.line)?;
                 if cursor.index > 0 {
-                    // Find previous character index
+                    // Find previous character index, skipping graphemes of default ignorable
+                    // code points so the cursor does not stop twice at the same position
                     let mut prev_index = 0;
-                    for (i, _) in line.text().grapheme_indices(true) {
-                        if i < cursor.index {
-                            prev_index = i;
-                        } else {
+                    for (i, grapheme) in line.text().grapheme_indices(true) {
+                        if i >= cursor.index {
                             break;
                         }
+                        if i == 0 || !grapheme.chars().all(is_default_ignorable) {
+                            prev_index = i;
+                        }
                     }
 
                     cursor.index = prev_index;
@@ -1033,10 +2637,14 @@ impl Buffer {
             Motion::Next => {
                 let line = self.lines.get(cursor.line)?;
                 if cursor.index < line.text().len() {
-                    for (i, c) in line.text().grapheme_indices(true) {
-                        if i == cursor.index {
-                            cursor.index += c.len();
+                    // Find next character index, skipping graphemes of default ignorable code
+                    // points so the cursor does not stop twice at the same position
+                    let index = cursor.index;
+                    for (i, grapheme) in line.text().grapheme_indices(true) {
+                        if i == index || (i > index && grapheme.chars().all(is_default_ignorable)) {
+                            cursor.index = i + grapheme.len();
                             cursor.affinity = Affinity::Before;
+                        } else if i > index {
                             break;
                         }
                     }
@@ -1339,13 +2947,10 @@ impl Buffer {
         F: FnMut(i32, i32, u32, u32, Color),
     {
         for run in self.layout_runs() {
-            for glyph in run.glyphs.iter() {
+            for glyph in run.glyphs.iter().flat_map(LayoutGlyph::with_marks) {
                 let physical_glyph = glyph.physical((0., 0.), 1.0);
 
-                let glyph_color = match glyph.color_opt {
-                    Some(some) => some,
-                    None => color,
-                };
+                let glyph_color = glyph.color(color);
 
                 cache.with_pixels(
                     font_system,
@@ -1383,11 +2988,28 @@ impl BorrowedWithFontSystem<'_, Buffer> {
         self.inner.line_shape(self.font_system, line_i)
     }
 
+    /// Get the bounding box of the visible laid out text, see [`Buffer::bounds`]
+    pub fn bounds(&mut self, mode: BoundsMode) -> Option<(f32, f32, f32, f32)> {
+        self.inner.bounds(self.font_system, mode)
+    }
+
+    /// Shape the provided line index and return its glyphs in logical order, see
+    /// [`Buffer::line_logical_glyphs`]
+    pub fn line_logical_glyphs(&mut self, line_i: usize) -> Option<Vec<LayoutGlyph>> {
+        self.inner.line_logical_glyphs(self.font_system, line_i)
+    }
+
     /// Lay out the provided line index and return the result
     pub fn line_layout(&mut self, line_i: usize) -> Option<&[LayoutLine]> {
         self.inner.line_layout(self.font_system, line_i)
     }
 
+    /// Get the byte indices in line `line_i` where a cursor may stop, see
+    /// [`Buffer::grapheme_boundaries`]
+    pub fn grapheme_boundaries(&mut self, line_i: usize) -> Option<Vec<usize>> {
+        self.inner.grapheme_boundaries(self.font_system, line_i)
+    }
+
     /// Set the current [`Metrics`]
     ///
     /// # Panics
@@ -1402,6 +3024,17 @@ impl BorrowedWithFontSystem<'_, Buffer> {
         self.inner.set_wrap(self.font_system, wrap);
     }
 
+    /// Set the current [`OverflowWrap`]
+    pub fn set_overflow_wrap(&mut self, overflow_wrap: OverflowWrap) {
+        self.inner
+            .set_overflow_wrap(self.font_system, overflow_wrap);
+    }
+
+    /// Set the dominant [`Baseline`] that glyphs from different fonts are aligned on
+    pub fn set_baseline(&mut self, baseline: Baseline) {
+        self.inner.set_baseline(self.font_system, baseline);
+    }
+
     /// Set the current buffer dimensions
     pub fn set_size(&mut self, width_opt: Option<f32>, height_opt: Option<f32>) {
         self.inner.set_size(self.font_system, width_opt, height_opt);
@@ -1427,11 +3060,133 @@ impl BorrowedWithFontSystem<'_, Buffer> {
         self.inner.set_tab_width(self.font_system, tab_width);
     }
 
+    /// Set the locale used when shaping this buffer, see [`Buffer::set_locale`]
+    pub fn set_locale(&mut self, locale_opt: Option<&str>) {
+        self.inner.set_locale(self.font_system, locale_opt);
+    }
+
+    /// Restrict font matching and fallback when shaping to the fonts `font_whitelist`, see
+    /// [`Buffer::set_font_whitelist`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FontSystemError::NoFonts`] if `font_whitelist` is empty
+    pub fn set_font_whitelist(
+        &mut self,
+        font_whitelist: Option<Vec<fontdb::ID>>,
+    ) -> Result<(), FontSystemError> {
+        self.inner
+            .set_font_whitelist(self.font_system, font_whitelist)
+    }
+
+    /// Split shaping runs at every attribute span boundary, see
+    /// [`Buffer::set_preserve_span_boundaries`]
+    pub fn set_preserve_span_boundaries(&mut self, preserve_span_boundaries: bool) {
+        self.inner
+            .set_preserve_span_boundaries(self.font_system, preserve_span_boundaries);
+    }
+
+    /// Set whether zero-advance glyphs are emitted as separate glyphs, see
+    /// [`Buffer::set_emit_zero_advance_glyphs`]
+    pub fn set_emit_zero_advance_glyphs(&mut self, emit_zero_advance_glyphs: bool) {
+        self.inner
+            .set_emit_zero_advance_glyphs(self.font_system, emit_zero_advance_glyphs);
+    }
+
+    /// Snap glyph X positions to a multiple of `step`, see [`Buffer::set_position_quantization`]
+    pub fn set_position_quantization(&mut self, step_opt: Option<f32>) {
+        self.inner
+            .set_position_quantization(self.font_system, step_opt);
+    }
+
+    /// Take the rectangles of visible lines laid out since the last call, see
+    /// [`Buffer::take_dirty_rects`]
+    pub fn take_dirty_rects(&mut self) -> Vec<(f32, f32, f32, f32)> {
+        self.inner.take_dirty_rects()
+    }
+
+    /// Set where the line height of empty lines comes from, see
+    /// [`Buffer::set_empty_line_metrics`]
+    pub fn set_empty_line_metrics(&mut self, empty_line_metrics: EmptyLineMetrics) {
+        self.inner
+            .set_empty_line_metrics(self.font_system, empty_line_metrics);
+    }
+
+    /// Truncate the middle of each line at separators to fit `width`, see
+    /// [`Buffer::truncate_middle_at`]
+    pub fn truncate_middle_at(&mut self, width: f32, separators: &[char], ellipsis: &str) -> bool {
+        self.inner
+            .truncate_middle_at(self.font_system, width, separators, ellipsis)
+    }
+
+    /// Wrap text to `width` and align it within a box of `width` and `height`, see
+    /// [`Buffer::layout_in_box`]
+    pub fn layout_in_box(
+        &mut self,
+        width: f32,
+        height: f32,
+        h_align: Option<Align>,
+        v_align: VerticalAlign,
+    ) -> (f32, f32, f32, f32) {
+        self.inner
+            .layout_in_box(self.font_system, width, height, h_align, v_align)
+    }
+
+    /// Find the smallest width at which the text wraps to at most `max_lines` lines, see
+    /// [`Buffer::min_width_for_lines`]
+    pub fn min_width_for_lines(&mut self, max_lines: usize) -> f32 {
+        self.inner.min_width_for_lines(self.font_system, max_lines)
+    }
+
+    /// Choose the largest font size between `min_size` and `max_size` that fits a box, see
+    /// [`Buffer::fit_to`]
+    pub fn fit_to(
+        &mut self,
+        width: f32,
+        height: f32,
+        min_size: f32,
+        max_size: f32,
+        ellipsis: &str,
+    ) -> f32 {
+        self.inner.fit_to(
+            self.font_system,
+            width,
+            height,
+            min_size,
+            max_size,
+            ellipsis,
+        )
+    }
+
     /// Set text of buffer, using provided attributes for each line by default
     pub fn set_text(&mut self, text: &str, attrs: &Attrs, shaping: Shaping) {
         self.inner.set_text(self.font_system, text, attrs, shaping);
     }
 
+    /// Set text of buffer, shaping ranges with the given fonts, see
+    /// [`Buffer::set_text_with_font_runs`]
+    pub fn set_text_with_font_runs(
+        &mut self,
+        text: &str,
+        font_runs: &[(Range<usize>, fontdb::ID)],
+        attrs: &Attrs,
+        shaping: Shaping,
+    ) {
+        self.inner
+            .set_text_with_font_runs(self.font_system, text, font_runs, attrs, shaping);
+    }
+
+    /// Set the attributes of `range` in line `line_i`, see [`Buffer::set_attrs`]
+    pub fn set_attrs(&mut self, line_i: usize, range: Range<usize>, attrs: &Attrs) {
+        self.inner.set_attrs(self.font_system, line_i, range, attrs);
+    }
+
+    /// Append a line of `text`, shaping only the new line, see [`Buffer::append_line`]
+    pub fn append_line(&mut self, text: &str, attrs: &Attrs, shaping: Shaping) {
+        self.inner
+            .append_line(self.font_system, text, attrs, shaping);
+    }
+
     /// Set text of buffer, using an iterator of styled spans (pairs of text and attributes)
     ///
     /// ```
@@ -1463,6 +3218,16 @@ impl BorrowedWithFontSystem<'_, Buffer> {
             .set_rich_text(self.font_system, spans, default_attrs, shaping, alignment);
     }
 
+    /// Move `cursor` to the visual line above it, see [`Buffer::cursor_up`]
+    pub fn cursor_up(&mut self, cursor: Cursor, desired_x: Option<f32>) -> (Cursor, f32) {
+        self.inner.cursor_up(self.font_system, cursor, desired_x)
+    }
+
+    /// Move `cursor` to the visual line below it, see [`Buffer::cursor_down`]
+    pub fn cursor_down(&mut self, cursor: Cursor, desired_x: Option<f32>) -> (Cursor, f32) {
+        self.inner.cursor_down(self.font_system, cursor, desired_x)
+    }
+
     /// Apply a [`Motion`] to a [`Cursor`]
     pub fn cursor_motion(
         &mut self,
@@ -1,9 +1,11 @@
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
 use core::mem;
+use core::ops::Range;
 
 use crate::{
-    Align, Attrs, AttrsList, Cached, FontSystem, LayoutLine, LineEnding, ShapeLine, Shaping, Wrap,
+    math, Align, Attrs, AttrsList, Baseline, Cached, FontSystem, LayoutGlyph, LayoutLine,
+    LayoutOptions, LineEnding, ShapeLine, Shaping, Wrap,
 };
 
 /// A line (or paragraph) of text that is shaped and laid out
@@ -17,6 +19,8 @@ pub struct BufferLine {
     layout_opt: Cached<Vec<LayoutLine>>,
     shaping: Shaping,
     metadata: Option<usize>,
+    /// True if laid out since the last [`crate::Buffer::take_dirty_rects`]
+    layout_dirty: bool,
 }
 
 impl BufferLine {
@@ -38,6 +42,7 @@ impl BufferLine {
             layout_opt: Cached::Empty,
             shaping,
             metadata: None,
+            layout_dirty: false,
         }
     }
 
@@ -132,6 +137,70 @@ impl BufferLine {
         }
     }
 
+    /// Set the attributes of `range`, see [`AttrsList::add_span`]
+    ///
+    /// Changes to only the color, opacity or metadata are applied to the shaped and laid out
+    /// glyphs in place, other changes reset shape and layout. With `preserve_span_boundaries`,
+    /// shaping runs are split at any change of attributes, so every change resets them.
+    /// Returns true if the line was reset
+    pub fn set_attrs(
+        &mut self,
+        range: Range<usize>,
+        attrs: &Attrs,
+        preserve_span_boundaries: bool,
+    ) -> bool {
+        let mut attrs_list = self.attrs_list.clone();
+        attrs_list.add_span(range.clone(), attrs);
+        if attrs_list == self.attrs_list {
+            return false;
+        }
+
+        let restyle = !preserve_span_boundaries
+            && self.text.get(range.clone()).is_some_and(|text| {
+                text.char_indices().all(|(i, _)| {
+                    let mut restyled = self.attrs_list.get_span(range.start + i);
+                    restyled.color_opt = attrs.color_opt;
+                    restyled.opacity_opt = attrs.opacity_opt;
+                    restyled.metadata = attrs.metadata;
+                    restyled == *attrs
+                })
+            });
+        self.attrs_list = attrs_list;
+        if restyle {
+            self.restyle_glyphs();
+            false
+        } else {
+            self.reset_shaping();
+            true
+        }
+    }
+
+    /// Update the color, opacity and metadata of shaped and laid out glyphs from the attributes
+    /// list
+    fn restyle_glyphs(&mut self) {
+        let attrs_list = &self.attrs_list;
+        if let Some(shape) = self.shape_opt.get_mut() {
+            for span in shape.spans.iter_mut() {
+                for word in span.words.iter_mut() {
+                    for glyph in word.glyphs.iter_mut() {
+                        let attrs = attrs_list.get_span(glyph.start);
+                        glyph.color_opt = attrs.color_opt;
+                        glyph.opacity = attrs.opacity_opt.map_or(1.0, |opacity| opacity.0);
+                        glyph.metadata = attrs.metadata;
+                    }
+                }
+            }
+        }
+        if let Some(layout) = self.layout_opt.get_mut() {
+            for layout_line in layout.iter_mut() {
+                for glyph in layout_line.glyphs.iter_mut() {
+                    restyle_layout_glyph(glyph, attrs_list);
+                }
+            }
+            self.layout_dirty = true;
+        }
+    }
+
     /// Get the Text alignment
     pub fn align(&self) -> Option<Align> {
         self.align
@@ -140,7 +209,8 @@ impl BufferLine {
     /// Set the text alignment
     ///
     /// Will reset layout if it differs from current alignment.
-    /// Setting to None will use `Align::Right` for RTL lines, and `Align::Left` for LTR lines.
+    /// Setting to None will use `Align::Start`, which is `Align::Right` for RTL lines and
+    /// `Align::Left` for LTR lines.
     /// Returns true if the line was reset
     pub fn set_align(&mut self, align: Option<Align>) -> bool {
         if align != self.align {
@@ -203,19 +273,32 @@ impl BufferLine {
     }
 
     /// Shape line, will cache results
-    #[allow(clippy::missing_panics_doc)]
     pub fn shape(&mut self, font_system: &mut FontSystem, tab_width: u16) -> &ShapeLine {
+        self.shape_with_options(font_system, tab_width, &LayoutOptions::default())
+    }
+
+    /// Shape line with the given [`LayoutOptions`], will cache results.
+    ///
+    /// The cache is not keyed on the options, call [`Self::reset_shaping`] after changing them
+    #[allow(clippy::missing_panics_doc)]
+    pub fn shape_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        tab_width: u16,
+        options: &LayoutOptions,
+    ) -> &ShapeLine {
         if self.shape_opt.is_unused() {
             let mut line = self
                 .shape_opt
                 .take_unused()
                 .unwrap_or_else(ShapeLine::empty);
-            line.build(
+            line.build_with_options(
                 font_system,
                 &self.text,
                 &self.attrs_list,
                 self.shaping,
                 tab_width,
+                options,
             );
             self.shape_opt.set_used(line);
             self.layout_opt.set_unused();
@@ -229,7 +312,6 @@ impl BufferLine {
     }
 
     /// Layout line, will cache results
-    #[allow(clippy::missing_panics_doc)]
     pub fn layout(
         &mut self,
         font_system: &mut FontSystem,
@@ -238,6 +320,31 @@ impl BufferLine {
         wrap: Wrap,
         match_mono_width: Option<f32>,
         tab_width: u16,
+    ) -> &[LayoutLine] {
+        self.layout_with_options(
+            font_system,
+            font_size,
+            width_opt,
+            wrap,
+            match_mono_width,
+            tab_width,
+            &LayoutOptions::default(),
+        )
+    }
+
+    /// Layout line with the given [`LayoutOptions`], will cache results.
+    ///
+    /// The cache is not keyed on the options, call [`Self::reset_shaping`] after changing them
+    #[allow(clippy::missing_panics_doc, clippy::too_many_arguments)]
+    pub fn layout_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        match_mono_width: Option<f32>,
+        tab_width: u16,
+        options: &LayoutOptions,
     ) -> &[LayoutLine] {
         if self.layout_opt.is_unused() {
             let align = self.align;
@@ -245,7 +352,7 @@ impl BufferLine {
                 .layout_opt
                 .take_unused()
                 .unwrap_or_else(|| Vec::with_capacity(1));
-            let shape = self.shape(font_system, tab_width);
+            let shape = self.shape_with_options(font_system, tab_width, options);
             shape.layout_to_buffer(
                 &mut font_system.shape_buffer,
                 font_size,
@@ -255,7 +362,17 @@ impl BufferLine {
                 &mut layout,
                 match_mono_width,
             );
+            if options.baseline != Baseline::Alphabetic {
+                align_baselines(font_system, &mut layout, options.baseline);
+            }
+            if !options.emit_zero_advance_glyphs {
+                fold_zero_advance_glyphs(&mut layout);
+            }
+            if let Some(step) = options.position_quantization {
+                quantize_glyph_positions(&mut layout, step);
+            }
             self.layout_opt.set_used(layout);
+            self.layout_dirty = true;
         }
         self.layout_opt.get().expect("layout not found")
     }
@@ -265,6 +382,66 @@ impl BufferLine {
         self.layout_opt.get()
     }
 
+    /// Return true if the line was laid out since the last call, and clear the flag
+    pub(crate) fn take_layout_dirty(&mut self) -> bool {
+        mem::take(&mut self.layout_dirty)
+    }
+
+    /// Override the line height of the layout of an empty line, `None` uses the buffer metrics.
+    /// Does nothing if the line is not empty or not laid out
+    pub(crate) fn set_empty_line_height(&mut self, line_height_opt: Option<f32>) {
+        if !self.text.is_empty() {
+            return;
+        }
+        if let Some(layout_line) = self
+            .layout_opt
+            .get_mut()
+            .and_then(|layout| layout.first_mut())
+        {
+            layout_line.line_height_opt = line_height_opt;
+        }
+    }
+
+    /// Override the advance of a laid out glyph, moving the glyphs after it on the same layout
+    /// line and updating the line width. This allows manual kerning without reshaping.
+    ///
+    /// The override is lost when the line is laid out again, and alignment is not recomputed.
+    /// Returns true if the glyph was found
+    pub fn set_glyph_advance(&mut self, layout_i: usize, glyph_i: usize, advance: f32) -> bool {
+        let rtl = self.shape_opt.get().is_some_and(|shape| shape.rtl);
+        let Some(layout_line) = self
+            .layout_opt
+            .get_mut()
+            .and_then(|layout| layout.get_mut(layout_i))
+        else {
+            return false;
+        };
+        let Some(glyph) = layout_line.glyphs.get_mut(glyph_i) else {
+            return false;
+        };
+
+        let delta = advance - glyph.w;
+        glyph.w = advance;
+        if rtl {
+            // Glyphs are laid out from the right edge, so this glyph and all after it move left
+            for glyph in layout_line.glyphs[glyph_i..].iter_mut() {
+                glyph.x -= delta;
+                for mark in glyph.marks.iter_mut() {
+                    mark.x -= delta;
+                }
+            }
+        } else {
+            for glyph in layout_line.glyphs[glyph_i + 1..].iter_mut() {
+                glyph.x += delta;
+                for mark in glyph.marks.iter_mut() {
+                    mark.x += delta;
+                }
+            }
+        }
+        layout_line.w += delta;
+        true
+    }
+
     /// Get line metadata. This will be None if [`BufferLine::set_metadata`] has not been called
     /// after the last reset of shaping and layout caches
     pub fn metadata(&self) -> Option<usize> {
@@ -289,6 +466,7 @@ impl BufferLine {
             layout_opt: Cached::Empty,
             shaping: Shaping::Advanced,
             metadata: None,
+            layout_dirty: false,
         }
     }
 
@@ -308,3 +486,94 @@ impl BufferLine {
         text
     }
 }
+
+/// Shift glyphs vertically so that `baseline` of every font lines up with `baseline` of the
+/// first font used in the line
+fn align_baselines(font_system: &mut FontSystem, layout: &mut [LayoutLine], baseline: Baseline) {
+    let mut offsets: Vec<(fontdb::ID, f32)> = Vec::new();
+    let mut offset_for = |font_system: &mut FontSystem, font_id| {
+        if let Some(&(_, offset)) = offsets.iter().find(|(id, _)| *id == font_id) {
+            return offset;
+        }
+        let offset = font_system
+            .get_font(font_id)
+            .map_or(0.0, |font| font.baseline_offset(baseline));
+        offsets.push((font_id, offset));
+        offset
+    };
+
+    let Some(dominant_id) = layout
+        .iter()
+        .flat_map(|line| line.glyphs.iter())
+        .min_by_key(|glyph| glyph.start)
+        .map(|glyph| glyph.font_id)
+    else {
+        return;
+    };
+    let dominant = offset_for(font_system, dominant_id);
+
+    for line in layout.iter_mut() {
+        for glyph in line.glyphs.iter_mut() {
+            let offset = offset_for(font_system, glyph.font_id);
+            glyph.y += (offset - dominant) * glyph.font_size;
+        }
+    }
+}
+
+/// Snap the X position of every glyph, including folded marks, to a multiple of `step`
+fn quantize_glyph_positions(layout: &mut [LayoutLine], step: f32) {
+    for glyph in layout.iter_mut().flat_map(|line| line.glyphs.iter_mut()) {
+        glyph.x = math::roundf(glyph.x / step) * step;
+        for mark in glyph.marks.iter_mut() {
+            mark.x = math::roundf(mark.x / step) * step;
+        }
+    }
+}
+
+/// Move zero-advance glyphs into [`LayoutGlyph::marks`] of the glyph they are drawn with: the
+/// glyph with an advance in the same cluster, or else the nearest one before or after them
+fn fold_zero_advance_glyphs(layout: &mut [LayoutLine]) {
+    for line in layout.iter_mut() {
+        let glyphs = &line.glyphs;
+        let is_base = |glyph: &LayoutGlyph| glyph.w != 0.0;
+        let targets: Vec<(usize, usize)> = glyphs
+            .iter()
+            .enumerate()
+            .filter(|(_, glyph)| !is_base(glyph))
+            .filter_map(|(i, mark)| {
+                let target = glyphs
+                    .iter()
+                    .position(|base| {
+                        is_base(base) && base.start <= mark.start && mark.end <= base.end
+                    })
+                    .or_else(|| glyphs[..i].iter().rposition(is_base))
+                    .or_else(|| glyphs[i..].iter().position(is_base).map(|j| i + j))?;
+                Some((i, target))
+            })
+            .collect();
+        if targets.is_empty() {
+            continue;
+        }
+
+        let mut slots: Vec<Option<LayoutGlyph>> = line.glyphs.drain(..).map(Some).collect();
+        for (i, target) in targets {
+            if let Some(mark) = slots[i].take() {
+                if let Some(base) = slots[target].as_mut() {
+                    base.marks.push(mark);
+                }
+            }
+        }
+        line.glyphs.extend(slots.into_iter().flatten());
+    }
+}
+
+/// Update the color, opacity and metadata of `glyph` and its folded marks from `attrs_list`
+fn restyle_layout_glyph(glyph: &mut LayoutGlyph, attrs_list: &AttrsList) {
+    let attrs = attrs_list.get_span(glyph.start);
+    glyph.color_opt = attrs.color_opt;
+    glyph.opacity = attrs.opacity_opt.map_or(1.0, |opacity| opacity.0);
+    glyph.metadata = attrs.metadata;
+    for mark in glyph.marks.iter_mut() {
+        restyle_layout_glyph(mark, attrs_list);
+    }
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{
     string::{String, ToString},
@@ -11,8 +12,8 @@ use unicode_segmentation::UnicodeSegmentation;
 #[cfg(feature = "swash")]
 use crate::Color;
 use crate::{
-    Action, Attrs, AttrsList, BorrowedWithFontSystem, BufferLine, BufferRef, Change, ChangeItem,
-    Cursor, Edit, FontSystem, LayoutRun, Selection, Shaping,
+    Action, Attrs, AttrsList, BorrowedWithFontSystem, Buffer, BufferLine, BufferRef, Change,
+    ChangeItem, Cursor, Edit, FontSystem, LayoutRun, Selection, Shaping,
 };
 
 /// A wrapper of [`Buffer`] for easy editing
@@ -25,6 +26,39 @@ pub struct Editor<'buffer> {
     cursor_moved: bool,
     auto_indent: bool,
     change: Option<Change>,
+    undo_stack: Vec<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    /// True while edits are recorded by [`Edit::action`] or applied by undo and redo
+    history_edit: bool,
+}
+
+/// Time within which single character insertions are coalesced into one undo step
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+const COALESCE_WINDOW: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// A change recorded for [`Editor::undo`] and [`Editor::redo`]
+#[derive(Clone, Debug)]
+struct HistoryEntry {
+    change: Change,
+    /// Cursor before the change, restored when undoing it
+    cursor_before: Cursor,
+    /// Cursor after the change, restored when redoing it
+    cursor_after: Cursor,
+    /// True if the next single character insertion can be added to this entry
+    coalesce: bool,
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    time: std::time::Instant,
+}
+
+/// True if `change` only inserts a single character other than a newline
+fn is_char_insert(change: &Change) -> bool {
+    match change.items.as_slice() {
+        [item] => {
+            let mut chars = item.text.chars();
+            item.insert && chars.next().is_some_and(|c| c != '\n') && chars.next().is_none()
+        }
+        _ => false,
+    }
 }
 
 fn cursor_glyph_opt(cursor: &Cursor, run: &LayoutRun) -> Option<(usize, f32)> {
@@ -104,7 +138,131 @@ impl<'buffer> Editor<'buffer> {
             cursor_moved: false,
             auto_indent: false,
             change: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            history_edit: false,
+        }
+    }
+
+    fn edit_buffer<F: FnOnce(&mut Buffer) -> T, T>(&mut self, f: F) -> T {
+        match &mut self.buffer_ref {
+            BufferRef::Owned(buffer) => f(buffer),
+            BufferRef::Borrowed(buffer) => f(buffer),
+            BufferRef::Arc(buffer) => f(Arc::make_mut(buffer)),
+        }
+    }
+
+    fn clear_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    /// True if there is a change to undo with [`Self::undo`]
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// True if there is an undone change to redo with [`Self::redo`]
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Undo the last change made by [`Edit::action`], restoring the text and the cursor from
+    /// before it. Consecutive single character insertions are undone together, until a newline
+    /// or a cursor movement.
+    ///
+    /// Edits made outside of [`Edit::action`], such as with [`Edit::insert_string`], and mutable
+    /// access to the buffer with [`Edit::with_buffer_mut`] or [`Edit::buffer_ref_mut`] clear the
+    /// history, as the recorded changes may no longer apply to the text
+    pub fn undo(&mut self, font_system: &mut FontSystem) {
+        let Some(mut entry) = self.undo_stack.pop() else {
+            return;
+        };
+        entry.change.reverse();
+        self.history_edit = true;
+        let applied = self.apply_change(&entry.change);
+        self.history_edit = false;
+        entry.change.reverse();
+        if !applied {
+            self.undo_stack.push(entry);
+            return;
         }
+        self.restore_cursor(font_system, entry.cursor_before);
+        entry.coalesce = false;
+        self.redo_stack.push(entry);
+    }
+
+    /// Redo the last change undone by [`Self::undo`], restoring the text and the cursor from
+    /// after it
+    pub fn redo(&mut self, font_system: &mut FontSystem) {
+        let Some(entry) = self.redo_stack.pop() else {
+            return;
+        };
+        self.history_edit = true;
+        let applied = self.apply_change(&entry.change);
+        self.history_edit = false;
+        if !applied {
+            self.redo_stack.push(entry);
+            return;
+        }
+        self.restore_cursor(font_system, entry.cursor_after);
+        self.undo_stack.push(entry);
+    }
+
+    fn restore_cursor(&mut self, font_system: &mut FontSystem, cursor: Cursor) {
+        self.cursor = cursor;
+        self.cursor_x_opt = None;
+        self.cursor_moved = true;
+        self.selection = Selection::None;
+        self.edit_buffer(|buffer| buffer.set_redraw(true));
+        self.shape_as_needed(font_system, false);
+    }
+
+    /// Record a change made by [`Edit::action`], coalescing single character insertions
+    fn record_change(&mut self, change: Change, cursor_before: Cursor) {
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        let now = std::time::Instant::now();
+
+        if change.items.is_empty() {
+            // A cursor movement starts a new undo step
+            if cursor_before != self.cursor {
+                if let Some(last) = self.undo_stack.last_mut() {
+                    last.coalesce = false;
+                }
+            }
+            return;
+        }
+        self.redo_stack.clear();
+
+        let coalesce = is_char_insert(&change);
+        if let Some(last) = self
+            .undo_stack
+            .last_mut()
+            .filter(|last| coalesce && last.coalesce && last.cursor_after == cursor_before)
+        {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            let in_window = now.duration_since(last.time) <= COALESCE_WINDOW;
+            #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+            let in_window = true;
+            if in_window {
+                last.change.items.extend(change.items);
+                last.cursor_after = self.cursor;
+                #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+                {
+                    last.time = now;
+                }
+                return;
+            }
+        }
+
+        self.undo_stack.push(HistoryEntry {
+            change,
+            cursor_before,
+            cursor_after: self.cursor,
+            coalesce,
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            time: now,
+        });
     }
 
     /// Draw the editor
@@ -196,13 +354,10 @@ impl<'buffer> Editor<'buffer> {
                     f(x, y, 1, line_height as u32, cursor_color);
                 }
 
-                for glyph in run.glyphs.iter() {
+                for glyph in run.glyphs.iter().flat_map(crate::LayoutGlyph::with_marks) {
                     let physical_glyph = glyph.physical((0., 0.), 1.0);
 
-                    let mut glyph_color = match glyph.color_opt {
-                        Some(some) => some,
-                        None => text_color,
-                    };
+                    let mut glyph_color = glyph.color(text_color);
                     if text_color != selected_text_color {
                         if let Some((start, end)) = selection_bounds {
                             if line_i >= start.line
@@ -241,9 +396,15 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
     }
 
     fn buffer_ref_mut(&mut self) -> &mut BufferRef<'buffer> {
+        // The buffer may be edited or replaced
+        self.clear_history();
         &mut self.buffer_ref
     }
 
+    fn set_redraw(&mut self, redraw: bool) {
+        self.edit_buffer(|buffer| buffer.set_redraw(redraw));
+    }
+
     fn cursor(&self) -> Cursor {
         self.cursor
     }
@@ -252,7 +413,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
         if self.cursor != cursor {
             self.cursor = cursor;
             self.cursor_moved = true;
-            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+            self.edit_buffer(|buffer| buffer.set_redraw(true));
         }
     }
 
@@ -263,7 +424,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
     fn set_selection(&mut self, selection: Selection) {
         if self.selection != selection {
             self.selection = selection;
-            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+            self.edit_buffer(|buffer| buffer.set_redraw(true));
         }
     }
 
@@ -280,21 +441,21 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
     }
 
     fn set_tab_width(&mut self, font_system: &mut FontSystem, tab_width: u16) {
-        self.with_buffer_mut(|buffer| buffer.set_tab_width(font_system, tab_width));
+        self.edit_buffer(|buffer| buffer.set_tab_width(font_system, tab_width));
     }
 
     fn shape_as_needed(&mut self, font_system: &mut FontSystem, prune: bool) {
         if self.cursor_moved {
             let cursor = self.cursor;
-            self.with_buffer_mut(|buffer| buffer.shape_until_cursor(font_system, cursor, prune));
+            self.edit_buffer(|buffer| buffer.shape_until_cursor(font_system, cursor, prune));
             self.cursor_moved = false;
         } else {
-            self.with_buffer_mut(|buffer| buffer.shape_until_scroll(font_system, prune));
+            self.edit_buffer(|buffer| buffer.shape_until_scroll(font_system, prune));
         }
     }
 
     fn delete_range(&mut self, start: Cursor, end: Cursor) {
-        let change_item = self.with_buffer_mut(|buffer| {
+        let change_item = self.edit_buffer(|buffer| {
             // Collect removed data for change tracking
             let mut change_lines = Vec::new();
 
@@ -353,6 +514,9 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
         if let Some(ref mut change) = self.change {
             change.items.push(change_item);
         }
+        if !self.history_edit {
+            self.clear_history();
+        }
     }
 
     fn insert_at(
@@ -366,7 +530,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             return cursor;
         }
 
-        let change_item = self.with_buffer_mut(|buffer| {
+        let change_item = self.edit_buffer(|buffer| {
             // Save cursor for change tracking
             let start = cursor;
 
@@ -473,6 +637,9 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
         if let Some(ref mut change) = self.change {
             change.items.push(change_item);
         }
+        if !self.history_edit {
+            self.clear_history();
+        }
 
         cursor
     }
@@ -562,11 +729,18 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
     fn action(&mut self, font_system: &mut FontSystem, action: Action) {
         let old_cursor = self.cursor;
 
+        // Record changes for undo, unless they are already being collected by the caller
+        let record = self.change.is_none();
+        if record {
+            self.start_change();
+            self.history_edit = true;
+        }
+
         match action {
             Action::Motion(motion) => {
                 let cursor = self.cursor;
                 let cursor_x_opt = self.cursor_x_opt;
-                if let Some((new_cursor, new_cursor_x_opt)) = self.with_buffer_mut(|buffer| {
+                if let Some((new_cursor, new_cursor_x_opt)) = self.edit_buffer(|buffer| {
                     buffer.cursor_motion(font_system, cursor, cursor_x_opt, motion)
                 }) {
                     self.cursor = new_cursor;
@@ -576,7 +750,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             Action::Escape => {
                 match self.selection {
                     Selection::None => {}
-                    _ => self.with_buffer_mut(|buffer| buffer.set_redraw(true)),
+                    _ => self.edit_buffer(|buffer| buffer.set_redraw(true)),
                 }
                 self.selection = Selection::None;
             }
@@ -614,7 +788,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
 
                 // Ensure line is properly shaped and laid out (for potential immediate commands)
                 let line_i = self.cursor.line;
-                self.with_buffer_mut(|buffer| {
+                self.edit_buffer(|buffer| {
                     buffer.line_layout(font_system, line_i);
                 });
             }
@@ -741,7 +915,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                     }
 
                     // Request redraw
-                    self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                    self.edit_buffer(|buffer| buffer.set_redraw(true));
                 }
             }
             Action::Unindent => {
@@ -802,7 +976,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                     }
 
                     // Request redraw
-                    self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                    self.edit_buffer(|buffer| buffer.set_redraw(true));
                 }
             }
             Action::Click { x, y } => {
@@ -812,7 +986,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                 {
                     if new_cursor != self.cursor {
                         self.cursor = new_cursor;
-                        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                        self.edit_buffer(|buffer| buffer.set_redraw(true));
                     }
                 }
             }
@@ -823,10 +997,10 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                 {
                     if new_cursor != self.cursor {
                         self.cursor = new_cursor;
-                        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                        self.edit_buffer(|buffer| buffer.set_redraw(true));
                     }
                     self.selection = Selection::Word(self.cursor);
-                    self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                    self.edit_buffer(|buffer| buffer.set_redraw(true));
                 }
             }
             Action::TripleClick { x, y } => {
@@ -838,25 +1012,25 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                         self.cursor = new_cursor;
                     }
                     self.selection = Selection::Line(self.cursor);
-                    self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                    self.edit_buffer(|buffer| buffer.set_redraw(true));
                 }
             }
             Action::Drag { x, y } => {
                 if self.selection == Selection::None {
                     self.selection = Selection::Normal(self.cursor);
-                    self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                    self.edit_buffer(|buffer| buffer.set_redraw(true));
                 }
 
                 if let Some(new_cursor) = self.with_buffer(|buffer| buffer.hit(x as f32, y as f32))
                 {
                     if new_cursor != self.cursor {
                         self.cursor = new_cursor;
-                        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                        self.edit_buffer(|buffer| buffer.set_redraw(true));
                     }
                 }
             }
             Action::Scroll { lines } => {
-                self.with_buffer_mut(|buffer| {
+                self.edit_buffer(|buffer| {
                     let mut scroll = buffer.scroll();
                     //TODO: align to layout lines
                     scroll.vertical += lines as f32 * buffer.metrics().line_height;
@@ -867,7 +1041,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
 
         if old_cursor != self.cursor {
             self.cursor_moved = true;
-            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+            self.edit_buffer(|buffer| buffer.set_redraw(true));
 
             /*TODO
             if let Some(glyph) = run.glyphs.get(new_cursor_glyph) {
@@ -885,6 +1059,13 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             }
             */
         }
+
+        if record {
+            self.history_edit = false;
+            if let Some(change) = self.finish_change() {
+                self.record_change(change, old_cursor);
+            }
+        }
     }
 
     fn cursor_position(&self) -> Option<(i32, i32)> {
@@ -897,6 +1078,16 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
 }
 
 impl BorrowedWithFontSystem<'_, Editor<'_>> {
+    /// Undo the last change, see [`Editor::undo`]
+    pub fn undo(&mut self) {
+        self.inner.undo(self.font_system);
+    }
+
+    /// Redo the last undone change, see [`Editor::redo`]
+    pub fn redo(&mut self) {
+        self.inner.redo(self.font_system);
+    }
+
     #[cfg(feature = "swash")]
     pub fn draw<F>(
         &mut self,
@@ -492,13 +492,10 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                     }
                 }
 
-                for glyph in run.glyphs.iter() {
+                for glyph in run.glyphs.iter().flat_map(crate::LayoutGlyph::with_marks) {
                     let physical_glyph = glyph.physical((0., 0.), 1.0);
 
-                    let glyph_color = match glyph.color_opt {
-                        Some(some) => some,
-                        None => foreground_color,
-                    };
+                    let glyph_color = glyph.color(foreground_color);
 
                     cache.with_pixels(
                         font_system,
@@ -1006,7 +1003,7 @@ impl<'buffer> Edit<'buffer> for ViEditor<'_, 'buffer> {
                                             }
                                             None
                                         })
-                                        .last()
+                                        .next_back()
                                     {
                                         cursor.index = i;
                                     }
@@ -1,14 +1,18 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::{mem, ops::Range};
+use core::{
+    fmt, mem,
+    ops::{Range, RangeInclusive},
+};
 use fontdb::Family;
 use unicode_script::Script;
 
-use crate::{BuildHasher, Font, FontMatchKey, FontSystem, HashMap, ShapeBuffer};
+use crate::{BuildHasher, Font, FontMatchKey, FontSystem, HashMap, ShapeBuffer, Style, Weight};
 
 #[cfg(not(any(all(unix, not(target_os = "android")), target_os = "windows")))]
 #[path = "other.rs"]
@@ -76,6 +80,71 @@ pub trait Fallback: Send + Sync {
     fn script_fallback(&self, script: Script, locale: &str) -> &[&'static str];
 }
 
+/// A [`Fallback`] combining several fallbacks in order of preference, such as an application
+/// font set followed by [`PlatformFallback`].
+///
+/// The script fallbacks are those of the first fallback with any for the script, so later
+/// fallbacks only fill in scripts that earlier ones do not cover. The common and forbidden
+/// fallbacks of all fallbacks are combined in order.
+pub struct ChainFallback {
+    fallbacks: Vec<Box<dyn Fallback>>,
+    common_fallback: Vec<&'static str>,
+    forbidden_fallback: Vec<&'static str>,
+}
+
+impl fmt::Debug for ChainFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainFallback")
+            .field("fallbacks", &self.fallbacks.len())
+            .field("common_fallback", &self.common_fallback)
+            .field("forbidden_fallback", &self.forbidden_fallback)
+            .finish()
+    }
+}
+
+impl ChainFallback {
+    /// Create a [`ChainFallback`] trying `fallbacks` in order
+    pub fn new(fallbacks: Vec<Box<dyn Fallback>>) -> Self {
+        let mut common_fallback = Vec::new();
+        let mut forbidden_fallback = Vec::new();
+        for fallback in fallbacks.iter() {
+            for (lists, families) in [
+                (&mut common_fallback, fallback.common_fallback()),
+                (&mut forbidden_fallback, fallback.forbidden_fallback()),
+            ] {
+                for family in families {
+                    if !lists.contains(family) {
+                        lists.push(*family);
+                    }
+                }
+            }
+        }
+        Self {
+            fallbacks,
+            common_fallback,
+            forbidden_fallback,
+        }
+    }
+}
+
+impl Fallback for ChainFallback {
+    fn common_fallback(&self) -> &[&'static str] {
+        &self.common_fallback
+    }
+
+    fn forbidden_fallback(&self) -> &[&'static str] {
+        &self.forbidden_fallback
+    }
+
+    fn script_fallback(&self, script: Script, locale: &str) -> &[&'static str] {
+        self.fallbacks
+            .iter()
+            .map(|fallback| fallback.script_fallback(script, locale))
+            .find(|families| !families.is_empty())
+            .unwrap_or(&[])
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct Fallbacks {
     lists: Vec<&'static str>,
@@ -163,6 +232,31 @@ impl Fallbacks {
     }
 }
 
+/// A family to try for characters in a range, registered with
+/// [`FontSystem::add_range_fallback`]
+#[derive(Debug)]
+pub(crate) struct RangeFallback {
+    pub(crate) range: RangeInclusive<char>,
+    pub(crate) family: String,
+    /// Only use this fallback when the primary family of the run has this name
+    pub(crate) primary_family: Option<String>,
+    /// Use the face of `family` closest to this weight and style, instead of the one closest to
+    /// the attributes of the run
+    pub(crate) style: Option<(Weight, Style)>,
+    /// Use this font instead of looking up `family`
+    pub(crate) font_id: Option<fontdb::ID>,
+}
+
+impl RangeFallback {
+    /// True if this fallback applies to `word` shaped with the primary family `primary_family`
+    fn applies(&self, primary_family: &str, word: &str) -> bool {
+        self.primary_family
+            .as_deref()
+            .map_or(true, |family| family == primary_family)
+            && word.chars().any(|c| self.range.contains(&c))
+    }
+}
+
 pub use platform::PlatformFallback;
 
 #[cfg(not(feature = "warn_on_missing_glyphs"))]
@@ -188,9 +282,17 @@ pub struct FontFallbackIter<'a> {
     default_i: usize,
     scripts: &'a [Script],
     word: &'a str,
+    range_i: usize,
     script_i: (usize, usize),
     common_i: usize,
     other_i: usize,
+    /// Order to try other fonts in when fallback style affinity is enabled
+    other_ids: Vec<fontdb::ID>,
+    /// Font returned first, used for fallback style affinity
+    primary_id: Option<fontdb::ID>,
+    /// Requested weight and style fallback family faces are matched to, see
+    /// [`FontSystem::set_fallback_face_matching`]
+    style_opt: Option<(Weight, Style)>,
     end: bool,
 }
 
@@ -213,27 +315,40 @@ impl<'a> FontFallbackIter<'a> {
             default_i: 0,
             scripts,
             word,
+            range_i: 0,
             script_i: (0, 0),
             common_i: 0,
             other_i: 0,
+            other_ids: Vec::new(),
+            primary_id: None,
+            style_opt: None,
             end: false,
         }
     }
 
+    /// Use the faces of fallback families closest to `weight` and `style`, if enabled with
+    /// [`FontSystem::set_fallback_face_matching`]
+    pub fn with_style(mut self, weight: Weight, style: Style) -> Self {
+        if self.font_system.fallback_face_matching() {
+            self.style_opt = Some((weight, style));
+        }
+        self
+    }
+
     pub fn check_missing(&mut self, word: &str) {
         if self.end {
             missing_warn!(
                 "Failed to find any fallback for {:?} locale '{}': '{}'",
                 self.scripts,
-                self.font_system.locale(),
+                self.font_system.shaping_locale(),
                 word
             );
         } else if self.other_i > 0 {
             missing_warn!(
                 "Failed to find preset fallback for {:?} locale '{}', used '{}': '{}'",
                 self.scripts,
-                self.font_system.locale(),
-                self.face_name(self.font_match_keys[self.other_i - 1].id),
+                self.font_system.shaping_locale(),
+                self.face_name(self.other_id(self.other_i - 1)),
                 word
             );
         } else if !self.scripts.is_empty() && self.common_i > 0 {
@@ -241,7 +356,7 @@ impl<'a> FontFallbackIter<'a> {
             missing_warn!(
                 "Failed to find script fallback for {:?} locale '{}', used '{}': '{}'",
                 self.scripts,
-                self.font_system.locale(),
+                self.font_system.shaping_locale(),
                 family,
                 word
             );
@@ -260,10 +375,41 @@ impl<'a> FontFallbackIter<'a> {
         }
     }
 
+    /// Count a fallback font tried for missing glyphs, see [`FontSystem::fallback_font_count`]
+    pub(crate) fn count_fallback_font(&mut self) {
+        self.font_system.fallback_font_count += 1;
+    }
+
     pub fn shape_caches(&mut self) -> &mut ShapeBuffer {
         &mut self.font_system.shape_buffer
     }
 
+    /// Get the id of the other font to try at `index`
+    fn other_id(&self, index: usize) -> fontdb::ID {
+        self.other_ids
+            .get(index)
+            .copied()
+            .unwrap_or_else(|| self.font_match_keys[index].id)
+    }
+
+    /// Order the other fonts so that the ones sharing the most scripts with the primary font
+    /// are tried first
+    fn sort_other_by_affinity(&mut self) {
+        let Some(primary_id) = self.primary_id else {
+            return;
+        };
+        let primary_scripts = self.font_system.font_scripts(primary_id).to_vec();
+        let candidates = self
+            .font_match_keys
+            .iter()
+            .map(|m_key| {
+                let scripts = self.font_system.font_scripts(m_key.id);
+                (m_key.id, script_overlap(&primary_scripts, scripts))
+            })
+            .collect();
+        self.other_ids = order_by_overlap(candidates);
+    }
+
     fn face_contains_family(&self, id: fontdb::ID, family_name: &str) -> bool {
         if let Some(face) = self.font_system.db().face(id) {
             face.families.iter().any(|(name, _)| name == family_name)
@@ -272,6 +418,13 @@ impl<'a> FontFallbackIter<'a> {
         }
     }
 
+    /// Get the face of `family` closest to the requested weight and style, if they are set
+    fn styled_family_font(&mut self, family: &str) -> Option<Arc<Font>> {
+        let (weight, style) = self.style_opt?;
+        let id = self.font_system.range_fallback_id(family, weight, style)?;
+        self.font_system.get_font(id)
+    }
+
     fn default_font_match_key(&self) -> Option<&FontMatchKey> {
         let default_family = self.default_families[self.default_i - 1];
         let default_family_name = self.font_system.db().family_name(default_family);
@@ -356,9 +509,9 @@ impl<'a> FontFallbackIter<'a> {
                     let script_as_lower = script.short_name().to_lowercase();
                     <[u8; 4]>::try_from(script_as_lower.as_bytes()).ok()
                 });
-                self.font_system.get_monospace_ids_for_scripts(scripts)
+                self.font_system.cached_monospace_ids_for_scripts(scripts)
             } else {
-                Vec::new()
+                Arc::default()
             };
 
             for m_key in font_match_keys_iter(is_mono) {
@@ -399,6 +552,56 @@ impl<'a> FontFallbackIter<'a> {
             }
         }
 
+        while self.range_i < self.font_system.range_fallbacks.len() {
+            let range_fallback = &self.font_system.range_fallbacks[self.range_i];
+            self.range_i += 1;
+            let primary_family = self
+                .default_families
+                .first()
+                .map_or("", |family| self.font_system.db().family_name(family));
+            if !range_fallback.applies(primary_family, self.word) {
+                continue;
+            }
+            let range_family = range_fallback.family.clone();
+            if let Some(id) = range_fallback.font_id {
+                // Skip fonts without any of the characters in the range, rather than shaping with
+                // them
+                let range = range_fallback.range.clone();
+                if self
+                    .word
+                    .chars()
+                    .filter(|c| range.contains(c))
+                    .any(|c| self.font_system.glyph_index(id, c).is_some())
+                {
+                    if let Some(font) = self.font_system.get_font(id) {
+                        return Some(font);
+                    }
+                }
+                continue;
+            } else if let Some((weight, style)) = range_fallback.style {
+                if let Some(font) = self
+                    .font_system
+                    .range_fallback_id(&range_family, weight, style)
+                    .and_then(|id| self.font_system.get_font(id))
+                {
+                    return Some(font);
+                }
+            } else if self.style_opt.is_some() {
+                if let Some(font) = self.styled_family_font(&range_family) {
+                    return Some(font);
+                }
+            } else {
+                for m_key in font_match_keys_iter(false) {
+                    if self.face_contains_family(m_key.id, &range_family) {
+                        if let Some(font) = self.font_system.get_font(m_key.id) {
+                            return Some(font);
+                        }
+                    }
+                }
+            }
+            log::debug!("failed to find range fallback family '{}'", range_family);
+        }
+
         while self.script_i.0 < self.scripts.len() {
             let script = self.scripts[self.script_i.0];
 
@@ -407,6 +610,11 @@ impl<'a> FontFallbackIter<'a> {
             while self.script_i.1 < script_families.len() {
                 let script_family = script_families[self.script_i.1];
                 self.script_i.1 += 1;
+                if self.style_opt.is_some() {
+                    if let Some(font) = self.styled_family_font(script_family) {
+                        return Some(font);
+                    }
+                }
                 for m_key in font_match_keys_iter(false) {
                     if self.face_contains_family(m_key.id, script_family) {
                         if let Some(font) = self.font_system.get_font(m_key.id) {
@@ -418,7 +626,7 @@ impl<'a> FontFallbackIter<'a> {
                     "failed to find family '{}' for script {:?} and locale '{}'",
                     script_family,
                     script,
-                    self.font_system.locale(),
+                    self.font_system.shaping_locale(),
                 );
             }
 
@@ -430,6 +638,11 @@ impl<'a> FontFallbackIter<'a> {
         while self.common_i < common_families.len() {
             let common_family = common_families[self.common_i];
             self.common_i += 1;
+            if self.style_opt.is_some() {
+                if let Some(font) = self.styled_family_font(common_family) {
+                    return Some(font);
+                }
+            }
             for m_key in font_match_keys_iter(false) {
                 if self.face_contains_family(m_key.id, common_family) {
                     if let Some(font) = self.font_system.get_font(m_key.id) {
@@ -443,8 +656,14 @@ impl<'a> FontFallbackIter<'a> {
         //TODO: do we need to do this?
         //TODO: do not evaluate fonts more than once!
         let forbidden_families = fallbacks.forbidden_fallback();
+        if self.other_i == 0
+            && self.other_ids.is_empty()
+            && self.font_system.fallback_style_affinity()
+        {
+            self.sort_other_by_affinity();
+        }
         while self.other_i < self.font_match_keys.len() {
-            let id = self.font_match_keys[self.other_i].id;
+            let id = self.other_id(self.other_i);
             self.other_i += 1;
             if forbidden_families
                 .iter()
@@ -465,8 +684,95 @@ impl Iterator for FontFallbackIter<'_> {
     type Item = Arc<Font>;
     fn next(&mut self) -> Option<Self::Item> {
         let mut fallbacks = mem::take(&mut self.font_system.fallbacks);
-        let item = self.next_item(&fallbacks);
+        let item = loop {
+            match self.next_item(&fallbacks) {
+                Some(font) if !self.font_system.font_allowed(font.id()) => {}
+                item => break item,
+            }
+        };
         mem::swap(&mut fallbacks, &mut self.font_system.fallbacks);
+        // With a font whitelist, nothing may match, so draw missing glyphs with a whitelisted font
+        let item = match item {
+            None if self.primary_id.is_none() => self.font_system.first_allowed_font(),
+            item => item,
+        };
+        if self.primary_id.is_none() {
+            self.primary_id = item.as_ref().map(|font| font.id());
+        }
         item
     }
 }
+
+/// Count the scripts in both sorted lists of script tags
+fn script_overlap(a: &[[u8; 4]], b: &[[u8; 4]]) -> usize {
+    a.iter()
+        .filter(|script| b.binary_search(script).is_ok())
+        .count()
+}
+
+/// Order font ids by descending script overlap, keeping the original order for equal overlaps
+fn order_by_overlap(mut candidates: Vec<(fontdb::ID, usize)>) -> Vec<fontdb::ID> {
+    candidates.sort_by_key(|&(_, overlap)| core::cmp::Reverse(overlap));
+    candidates.into_iter().map(|(id, _)| id).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn affinity_prefers_shared_scripts() {
+        let mut db = fontdb::Database::new();
+        let ids: Vec<_> = (0..3)
+            .map(|i| {
+                db.push_face_info(fontdb::FaceInfo {
+                    id: fontdb::ID::dummy(),
+                    source: fontdb::Source::Binary(Arc::new(Vec::<u8>::new())),
+                    index: 0,
+                    families: alloc::vec![(
+                        alloc::format!("Font {i}"),
+                        fontdb::Language::English_UnitedStates
+                    )],
+                    post_script_name: alloc::format!("Font{i}"),
+                    style: fontdb::Style::Normal,
+                    weight: fontdb::Weight::NORMAL,
+                    stretch: fontdb::Stretch::Normal,
+                    monospaced: false,
+                })
+            })
+            .collect();
+
+        let primary = [*b"cyrl", *b"grek", *b"latn"];
+        let unrelated = [*b"arab"];
+        let related = [*b"grek", *b"latn"];
+        assert_eq!(script_overlap(&primary, &unrelated), 0);
+        assert_eq!(script_overlap(&primary, &related), 2);
+
+        let order = order_by_overlap(alloc::vec![
+            (ids[0], script_overlap(&primary, &unrelated)),
+            (ids[1], script_overlap(&primary, &related)),
+            (ids[2], script_overlap(&primary, &unrelated)),
+        ]);
+        assert_eq!(order, [ids[1], ids[0], ids[2]]);
+    }
+
+    #[test]
+    fn range_fallback_scoped_to_primary() {
+        let scoped = RangeFallback {
+            range: '\u{0400}'..='\u{04FF}',
+            family: "Roboto Cyrillic".into(),
+            primary_family: Some("Roboto".into()),
+            style: None,
+            font_id: None,
+        };
+        assert!(scoped.applies("Roboto", "Привет"));
+        assert!(!scoped.applies("Open Sans", "Привет"));
+        assert!(!scoped.applies("Roboto", "Hello"));
+
+        let global = RangeFallback {
+            primary_family: None,
+            ..scoped
+        };
+        assert!(global.applies("Open Sans", "Привет"));
+    }
+}
@@ -6,21 +6,32 @@ pub use ttf_parser;
 #[cfg(feature = "peniko")]
 pub use peniko::Font as PenikoFont;
 
-use core::fmt;
+use core::{fmt, mem};
 
 use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 use rustybuzz::Face as RustybuzzFace;
+
+use crate::{Baseline, DecorationLine};
 use self_cell::self_cell;
 
 pub(crate) mod fallback;
-pub use fallback::{Fallback, PlatformFallback};
+pub use fallback::{ChainFallback, Fallback, PlatformFallback};
 
 pub use self::system::*;
 mod system;
 
+#[cfg(feature = "woff")]
+pub use self::woff::*;
+#[cfg(feature = "woff")]
+mod woff;
+#[cfg(feature = "woff")]
+pub use self::woff2::*;
+#[cfg(feature = "woff")]
+mod woff2;
+
 self_cell!(
     struct OwnedFace {
         owner: Arc<dyn AsRef<[u8]> + Send + Sync>,
@@ -93,6 +104,59 @@ impl Font {
         self.rustybuzz.borrow_dependent()
     }
 
+    /// Get the estimated size in bytes of this font, including the parsed face and the supported
+    /// codepoints but not the font data, which is shared with the database. Tables parsed lazily
+    /// by the shaper are not included
+    pub(crate) fn memory_usage(&self) -> usize {
+        let fallback_size = self.monospace_fallback.as_ref().map_or(0, |fallback| {
+            fallback.scripts.capacity() * mem::size_of::<[u8; 4]>()
+                + fallback.unicode_codepoints.capacity() * mem::size_of::<u32>()
+        });
+        mem::size_of::<Self>() + mem::size_of::<RustybuzzFace<'static>>() + fallback_size
+    }
+
+    /// Get the position of `baseline` relative to the origin of the font, in em units with
+    /// positive values above the alphabetic baseline.
+    ///
+    /// Values are read from the `BASE` table when the font has one, otherwise they are
+    /// approximated from the ascender and descender.
+    pub fn baseline_offset(&self, baseline: Baseline) -> f32 {
+        let face = self.rustybuzz();
+        let upem = face.units_per_em() as f32;
+        let base = |tag: &[u8; 4]| base_table_coord(face, tag).map(|coord| f32::from(coord) / upem);
+        let ascender = f32::from(face.ascender()) / upem;
+        let descender = f32::from(face.descender()) / upem;
+        match baseline {
+            Baseline::Alphabetic => 0.0,
+            Baseline::Ideographic => base(b"ideo").unwrap_or(descender),
+            Baseline::Hanging => base(b"hang").unwrap_or(ascender * 0.8),
+            Baseline::Central => match (base(b"ideo"), base(b"idtp")) {
+                (Some(bottom), Some(top)) => (bottom + top) / 2.0,
+                _ => (ascender + descender) / 2.0,
+            },
+        }
+    }
+
+    /// Get the position and thickness of `line` in em units, with positive positions above the
+    /// alphabetic baseline.
+    ///
+    /// Values are read from the `post` and `OS/2` tables when the font has them, otherwise
+    /// common defaults are used.
+    pub fn decoration_metrics(&self, line: DecorationLine) -> (f32, f32) {
+        let face = self.rustybuzz();
+        let upem = face.units_per_em() as f32;
+        let (metrics_opt, default_position) = match line {
+            DecorationLine::Underline => (face.underline_metrics(), -0.1),
+            DecorationLine::Strikethrough => (face.strikeout_metrics(), 0.25),
+        };
+        metrics_opt.map_or((default_position, 0.05), |metrics| {
+            (
+                f32::from(metrics.position) / upem,
+                f32::from(metrics.thickness) / upem,
+            )
+        })
+    }
+
     #[cfg(feature = "peniko")]
     pub fn as_peniko(&self) -> PenikoFont {
         self.data.clone()
@@ -197,6 +261,47 @@ impl Font {
     }
 }
 
+/// Look up the coordinate of a baseline `tag` in the horizontal axis of the `BASE` table.
+///
+/// The first base script record with baseline values is used, as fonts generally share the
+/// same baseline positions between scripts.
+fn base_table_coord(face: &ttf_parser::Face, tag: &[u8; 4]) -> Option<i16> {
+    fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+        let bytes = data.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn table_at(data: &[u8], base: usize, offset_pos: usize) -> Option<usize> {
+        match u16_at(data, base + offset_pos)? {
+            0 => None,
+            offset => Some(base + usize::from(offset)),
+        }
+    }
+
+    let data = face
+        .raw_face()
+        .table(ttf_parser::Tag::from_bytes(b"BASE"))?;
+
+    let axis = table_at(data, 0, 4)?;
+    let tag_list = table_at(data, axis, 0)?;
+    let script_list = table_at(data, axis, 2)?;
+
+    let tag_count = usize::from(u16_at(data, tag_list)?);
+    let tag_index = (0..tag_count)
+        .find(|i| data.get(tag_list + 2 + i * 4..tag_list + 6 + i * 4) == Some(tag.as_slice()))?;
+
+    let script_count = usize::from(u16_at(data, script_list)?);
+    (0..script_count).find_map(|i| {
+        let script = table_at(data, script_list, 2 + i * 6 + 4)?;
+        let values = table_at(data, script, 0)?;
+        if tag_index >= usize::from(u16_at(data, values + 2)?) {
+            return None;
+        }
+        let coord = table_at(data, values, 4 + tag_index * 2)?;
+        u16_at(data, coord + 2).map(|value| value as i16)
+    })
+}
+
 #[cfg(test)]
 mod test {
     #[test]
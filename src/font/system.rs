@@ -1,94 +1,385 @@
-use crate::{Attrs, Font, FontMatchAttrs, HashMap, ShapeBuffer};
+use crate::{Attrs, Color, FeatureTag, Font, FontMatchAttrs, HashMap, ShapeBuffer, Style, Weight};
+#[cfg(feature = "shape-run-cache")]
+use crate::{AttrsList, ShapeLine, Shaping};
 use alloc::boxed::Box;
 use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::fmt;
-use core::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut, Range, RangeInclusive};
+use core::{fmt, mem};
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
 
 // re-export fontdb and rustybuzz
 pub use fontdb;
 pub use rustybuzz;
 
-use super::fallback::{Fallback, Fallbacks, MonospaceFallbackInfo, PlatformFallback};
+use super::fallback::{
+    Fallback, Fallbacks, FontFallbackIter, MonospaceFallbackInfo, PlatformFallback, RangeFallback,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FontMatchKey {
+    pub(crate) stretch_diff: u16,
     pub(crate) font_weight_diff: u16,
     pub(crate) font_weight: u16,
+    pub(crate) duplicate_rank: usize,
     pub(crate) id: fontdb::ID,
 }
 
+/// Strategy for choosing between faces that match equally well, such as duplicate installs of
+/// the same family or a static and a variable version of a font
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum DuplicateFamilyStrategy {
+    /// Prefer the face that was loaded first
+    #[default]
+    FirstLoaded,
+    /// Prefer the face that was loaded most recently
+    LastLoaded,
+    /// Prefer variable fonts over static fonts
+    PreferVariable,
+    /// Prefer faces loaded from the given path
+    #[cfg(feature = "std")]
+    PreferPath(std::path::PathBuf),
+}
+
+impl DuplicateFamilyStrategy {
+    /// Rank a face, lower ranks are preferred. `load_index` is the position of the face in the
+    /// database and `face_count` the number of faces in it
+    fn rank(
+        &self,
+        db: &fontdb::Database,
+        face: &fontdb::FaceInfo,
+        load_index: usize,
+        face_count: usize,
+    ) -> usize {
+        match self {
+            Self::FirstLoaded => 0,
+            Self::LastLoaded => face_count - load_index,
+            Self::PreferVariable => {
+                let variable = db
+                    .with_face_data(face.id, |font_data, face_index| {
+                        ttf_parser::Face::parse(font_data, face_index)
+                            .is_ok_and(|face| face.is_variable())
+                    })
+                    .unwrap_or(false);
+                usize::from(!variable)
+            }
+            #[cfg(feature = "std")]
+            Self::PreferPath(path) => {
+                let matches = match &face.source {
+                    fontdb::Source::Binary(_) => false,
+                    fontdb::Source::File(face_path) | fontdb::Source::SharedFile(face_path, _) => {
+                        face_path == path
+                    }
+                };
+                usize::from(!matches)
+            }
+        }
+    }
+}
+
+/// Source of the advance of whitespace that the primary font of a run cannot shape, see
+/// [`FontSystem::set_space_advance_source`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SpaceAdvanceSource {
+    /// Use the advance from the fallback font that shaped the whitespace
+    #[default]
+    Fallback,
+    /// Use a fixed advance in em units
+    Em(f32),
+    /// Use the whitespace glyph and advance of the given font
+    Font(fontdb::ID),
+}
+
+/// Resolution of runs of characters from the Unicode Private Use Areas, which apps use for
+/// custom icons, see [`FontSystem::set_pua_policy`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PuaPolicy {
+    /// Font to shape private use characters with, such as an icon font, skipping font matching
+    /// and fallback
+    pub font: Option<fontdb::ID>,
+    /// Skip the fallback search for private use characters missing from the font of the run,
+    /// drawing them as missing glyphs instead
+    pub skip_fallback: bool,
+}
+
+/// Whether combining marks missing from the font of their base character may be shaped with a
+/// fallback font, see [`FontSystem::set_mark_fallback`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MarkFallback {
+    /// Shape the base and its marks with a fallback font covering them all, drawing the marks as
+    /// missing glyphs if there is none
+    #[default]
+    Allow,
+    /// Shape the base and its marks with a fallback font covering them all, dropping the marks if
+    /// there is none
+    DropIfNoBaseCoverage,
+    /// Never use a fallback font for marks missing from the font of their base, dropping them
+    Never,
+}
+
+/// Error creating a [`FontSystem`] or restricting the fonts it uses, see
+/// [`FontSystem::try_new_with_fonts`] and [`crate::Buffer::set_font_whitelist`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FontSystemError {
+    /// The font database or font whitelist has no font faces, so all text would be drawn as
+    /// missing glyphs
+    NoFonts,
+}
+
+impl fmt::Display for FontSystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFonts => write!(f, "no fonts loaded"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FontSystemError {}
+
+/// A range of text with its ISO 15924 script tag, such as `*b"grek"`, and optionally its
+/// language, as returned by a [`ScriptDetector`]
+pub type ScriptDetection = (Range<usize>, [u8; 4], Option<rustybuzz::Language>);
+
+/// Custom script and language detection, see [`FontSystem::set_script_detector`]
+pub type ScriptDetector = Box<dyn Fn(&str) -> Vec<ScriptDetection> + Send + Sync>;
+
+/// Known and supported bits of a block of consecutive codepoints
+#[derive(Default)]
+struct CodepointBlock {
+    known: [u64; 4],
+    supported: [u64; 4],
+}
+
 struct FontCachedCodepointSupportInfo {
-    supported: Vec<u32>,
-    not_supported: Vec<u32>,
+    /// Blocks of 256 codepoints, by codepoint divided by 256
+    blocks: HashMap<u32, CodepointBlock>,
 }
 
 impl FontCachedCodepointSupportInfo {
-    const SUPPORTED_MAX_SZ: usize = 512;
-    const NOT_SUPPORTED_MAX_SZ: usize = 1024;
+    const BLOCK_SHIFT: u32 = 8;
 
     fn new() -> Self {
         Self {
-            supported: Vec::with_capacity(Self::SUPPORTED_MAX_SZ),
-            not_supported: Vec::with_capacity(Self::NOT_SUPPORTED_MAX_SZ),
+            blocks: HashMap::default(),
         }
     }
 
     #[inline(always)]
-    fn unknown_has_codepoint(
-        &mut self,
-        font_codepoints: &[u32],
-        codepoint: u32,
-        supported_insert_pos: usize,
-        not_supported_insert_pos: usize,
-    ) -> bool {
-        let ret = font_codepoints.contains(&codepoint);
-        if ret {
-            // don't bother inserting if we are going to truncate the entry away
-            if supported_insert_pos != Self::SUPPORTED_MAX_SZ {
-                self.supported.insert(supported_insert_pos, codepoint);
-                self.supported.truncate(Self::SUPPORTED_MAX_SZ);
-            }
-        } else {
-            // don't bother inserting if we are going to truncate the entry away
-            if not_supported_insert_pos != Self::NOT_SUPPORTED_MAX_SZ {
-                self.not_supported
-                    .insert(not_supported_insert_pos, codepoint);
-                self.not_supported.truncate(Self::NOT_SUPPORTED_MAX_SZ);
+    fn has_codepoint(&mut self, font_codepoints: &[u32], codepoint: u32) -> bool {
+        let block = self
+            .blocks
+            .entry(codepoint >> Self::BLOCK_SHIFT)
+            .or_default();
+        let word = (codepoint as usize & 0xFF) / 64;
+        let bit = 1 << (codepoint % 64);
+        if block.known[word] & bit == 0 {
+            block.known[word] |= bit;
+            if font_codepoints.contains(&codepoint) {
+                block.supported[word] |= bit;
             }
         }
-        ret
+        block.supported[word] & bit != 0
     }
 
-    #[inline(always)]
-    fn has_codepoint(&mut self, font_codepoints: &[u32], codepoint: u32) -> bool {
-        match self.supported.binary_search(&codepoint) {
-            Ok(_) => true,
-            Err(supported_insert_pos) => match self.not_supported.binary_search(&codepoint) {
-                Ok(_) => false,
-                Err(not_supported_insert_pos) => self.unknown_has_codepoint(
-                    font_codepoints,
-                    codepoint,
-                    supported_insert_pos,
-                    not_supported_insert_pos,
-                ),
-            },
+    fn memory_usage(&self) -> usize {
+        self.blocks.capacity() * mem::size_of::<(u32, CodepointBlock)>()
+    }
+}
+
+/// Get the stylistic set features in a `GSUB` table with the name ids from their feature params
+fn stylistic_set_name_ids(gsub: &[u8]) -> Option<Vec<(FeatureTag, Option<u16>)>> {
+    let read_u16 = |offset: usize| {
+        gsub.get(offset..offset + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+
+    let feature_list = usize::from(read_u16(6)?);
+    let feature_count = usize::from(read_u16(feature_list)?);
+    let mut sets = Vec::new();
+    for i in 0..feature_count {
+        let record = feature_list + 2 + i * 6;
+        let tag = FeatureTag::new(gsub.get(record..record + 4)?.try_into().ok()?);
+        if tag.stylistic_set_number().is_none() {
+            continue;
+        }
+        let feature = feature_list + usize::from(read_u16(record + 4)?);
+        // The feature params are a version followed by the name id of the user interface name
+        let name_id = match read_u16(feature)? {
+            0 => None,
+            params => read_u16(feature + usize::from(params) + 2),
+        };
+        sets.push((tag, name_id));
+    }
+    Some(sets)
+}
+
+/// Get the name with id `name_id` of a font, preferring English
+fn font_name(face: &ttf_parser::Face, name_id: u16) -> Option<String> {
+    let names = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == name_id && name.is_unicode());
+    let mut name_opt = None;
+    for name in names {
+        let english = name.language() == ttf_parser::Language::English_UnitedStates;
+        if name_opt.is_none() || english {
+            name_opt = char::decode_utf16(
+                name.name
+                    .chunks_exact(2)
+                    .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]])),
+            )
+            .collect::<Result<String, _>>()
+            .ok();
+        }
+        if english {
+            break;
+        }
+    }
+    name_opt
+}
+
+/// Get the horizontal kerning of the glyphs `left` and `right` from the pair adjustments of the
+/// `kern` feature in `GPOS`, or `None` if the font has no `GPOS` kerning
+fn gpos_kerning(
+    face: &ttf_parser::Face,
+    left: ttf_parser::GlyphId,
+    right: ttf_parser::GlyphId,
+) -> Option<i16> {
+    use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+
+    let gpos = face.tables().gpos?;
+    let mut lookup_indices: Vec<u16> = gpos
+        .features
+        .into_iter()
+        .filter(|feature| feature.tag == ttf_parser::Tag::from_bytes(b"kern"))
+        .flat_map(|feature| feature.lookup_indices)
+        .collect();
+    if lookup_indices.is_empty() {
+        return None;
+    }
+    lookup_indices.sort_unstable();
+    lookup_indices.dedup();
+
+    let mut kerning = 0i16;
+    for lookup in lookup_indices
+        .into_iter()
+        .filter_map(|index| gpos.lookups.get(index))
+    {
+        // Only the first subtable covering the pair applies
+        for subtable in lookup.subtables.into_iter::<PositioningSubtable>() {
+            let PositioningSubtable::Pair(pair) = subtable else {
+                continue;
+            };
+            let Some(coverage_index) = pair.coverage().get(left) else {
+                continue;
+            };
+            let records = match pair {
+                PairAdjustment::Format1 { sets, .. } => {
+                    sets.get(coverage_index).and_then(|set| set.get(right))
+                }
+                PairAdjustment::Format2 {
+                    classes, matrix, ..
+                } => matrix.get((classes.0.get(left), classes.1.get(right))),
+            };
+            if let Some((first, _)) = records {
+                kerning = kerning.saturating_add(first.x_advance);
+                break;
+            }
         }
     }
+    Some(kerning)
+}
+
+/// Get the ids of the monospace fonts in `db`, and of those fonts per supported script
+fn monospace_font_ids(
+    db: &fontdb::Database,
+) -> (Vec<fontdb::ID>, HashMap<[u8; 4], Vec<fontdb::ID>>) {
+    let mut monospace_font_ids = db
+        .faces()
+        .filter(|face_info| face_info.monospaced && !face_info.post_script_name.contains("Emoji"))
+        .map(|face_info| face_info.id)
+        .collect::<Vec<_>>();
+    monospace_font_ids.sort();
+
+    let mut per_script_monospace_font_ids: HashMap<[u8; 4], BTreeSet<fontdb::ID>> =
+        HashMap::default();
+
+    if cfg!(feature = "monospace_fallback") {
+        monospace_font_ids.iter().for_each(|&id| {
+            db.with_face_data(id, |font_data, face_index| {
+                let _ = ttf_parser::Face::parse(font_data, face_index).map(|face| {
+                    face.tables()
+                        .gpos
+                        .into_iter()
+                        .chain(face.tables().gsub)
+                        .flat_map(|table| table.scripts)
+                        .inspect(|script| {
+                            per_script_monospace_font_ids
+                                .entry(script.tag.to_bytes())
+                                .or_default()
+                                .insert(id);
+                        })
+                });
+            });
+        });
+    }
+
+    let per_script_monospace_font_ids = per_script_monospace_font_ids
+        .into_iter()
+        .map(|(k, v)| (k, Vec::from_iter(v)))
+        .collect();
+
+    (monospace_font_ids, per_script_monospace_font_ids)
+}
+
+/// Sorted unique script tags
+type ScriptTags = Vec<[u8; 4]>;
+
+/// Get the estimated size in bytes of an entry of the font cache
+fn font_entry_size(font_opt: &Option<Arc<Font>>) -> usize {
+    mem::size_of::<(fontdb::ID, (u64, Option<Arc<Font>>))>()
+        + font_opt.as_ref().map_or(0, |font| font.memory_usage())
+}
+
+/// Get the estimated size in bytes of an entry of the codepoint support cache
+fn codepoint_support_entry_size(info: &FontCachedCodepointSupportInfo) -> usize {
+    mem::size_of::<(fontdb::ID, (u64, FontCachedCodepointSupportInfo))>() + info.memory_usage()
+}
+
+/// Get the estimated size in bytes of an entry of the font matches cache
+fn font_matches_entry_size(keys: &[FontMatchKey]) -> usize {
+    mem::size_of::<(FontMatchAttrs, (u64, Arc<Vec<FontMatchKey>>))>() + mem::size_of_val(keys)
+}
+
+/// Get the estimated size in bytes of an entry of the monospace ids cache
+fn monospace_ids_entry_size(scripts: &ScriptTags, ids: &[fontdb::ID]) -> usize {
+    mem::size_of::<(ScriptTags, (u64, Arc<Vec<fontdb::ID>>))>()
+        + scripts.len() * mem::size_of::<[u8; 4]>()
+        + mem::size_of_val(ids)
 }
 
 /// Access to the system fonts.
+///
+/// # Shaping settings
+///
+/// Settings that change how text is shaped, such as [`FontSystem::set_pua_policy`] or
+/// [`FontSystem::add_range_fallback`], invalidate the cached shaping of this [`FontSystem`] when
+/// changed, but do not reach into [`crate::Buffer`]s. Buffers already shaped keep their glyphs
+/// until they are reshaped, for example with [`crate::BufferLine::reset_shaping`] on their lines,
+/// or by setting their text again.
 pub struct FontSystem {
     /// The locale of the system.
     locale: String,
 
-    /// The underlying font database.
-    db: fontdb::Database,
+    /// The underlying font database, which may be shared with other font systems.
+    db: Arc<fontdb::Database>,
 
-    /// Cache for loaded fonts from the database.
-    font_cache: HashMap<fontdb::ID, Option<Arc<Font>>>,
+    /// Cache for loaded fonts from the database, with the tick they were last used at.
+    font_cache: HashMap<fontdb::ID, (u64, Option<Arc<Font>>)>,
 
     /// Sorted unique ID's of all Monospace fonts in DB
     monospace_font_ids: Vec<fontdb::ID>,
@@ -98,11 +389,26 @@ pub struct FontSystem {
     /// may appear in multiple map value vecs.
     per_script_monospace_font_ids: HashMap<[u8; 4], Vec<fontdb::ID>>,
 
-    /// Cache for font codepoint support info
-    font_codepoint_support_info_cache: HashMap<fontdb::ID, FontCachedCodepointSupportInfo>,
+    /// Cache for monospace font ids of sorted sets of scripts, with the tick they were last used
+    /// at
+    monospace_ids_for_scripts_cache: HashMap<ScriptTags, (u64, Arc<Vec<fontdb::ID>>)>,
+
+    /// Cache for font codepoint support info, with the tick they were last used at
+    font_codepoint_support_info_cache: HashMap<fontdb::ID, (u64, FontCachedCodepointSupportInfo)>,
+
+    /// Cache for font matches, with the tick they were last used at.
+    font_matches_cache: HashMap<FontMatchAttrs, (u64, Arc<Vec<FontMatchKey>>)>,
+
+    /// Counter ordering the uses of cache entries, for evicting the least recently used ones
+    cache_tick: u64,
 
-    /// Cache for font matches.
-    font_matches_cache: HashMap<FontMatchAttrs, Arc<Vec<FontMatchKey>>>,
+    /// Estimated size in bytes of the font, codepoint support, font matches and monospace ids
+    /// caches, updated as entries are added and removed
+    cache_memory_usage: usize,
+
+    /// Upper bound on the estimated size of all caches in bytes, see
+    /// [`FontSystem::set_memory_budget`]
+    memory_budget: Option<usize>,
 
     /// Scratch buffer for shaping and laying out.
     pub(crate) shape_buffer: ShapeBuffer,
@@ -119,6 +425,80 @@ pub struct FontSystem {
 
     /// List of fallbacks
     pub(crate) fallbacks: Fallbacks,
+
+    /// Number of lines shaped with this [`FontSystem`]
+    pub(crate) shaped_line_count: u64,
+
+    /// Strategy for choosing between faces that match equally well
+    duplicate_family_strategy: DuplicateFamilyStrategy,
+
+    /// Prefer fallback fonts sharing scripts with the primary font
+    fallback_style_affinity: bool,
+
+    /// Scale fallback glyphs in clusters of the primary font to its vertical extent
+    fallback_metric_matching: bool,
+
+    /// Use the faces of fallback families closest to the requested weight and style
+    fallback_face_matching: bool,
+
+    /// Source of the advance of whitespace missing from the primary font
+    space_advance_source: SpaceAdvanceSource,
+
+    /// Resolution of private use characters, see [`FontSystem::set_pua_policy`]
+    pua_policy: PuaPolicy,
+
+    /// Fallback of combining marks, see [`FontSystem::set_mark_fallback`]
+    mark_fallback: MarkFallback,
+
+    /// Length in bytes above which runs are shaped in chunks, see
+    /// [`FontSystem::set_max_run_length`]
+    max_run_length: usize,
+
+    /// Number of fallback fonts tried for glyphs missing from the primary font of a run
+    pub(crate) fallback_font_count: u64,
+
+    /// Cache for sorted script tags supported by fonts
+    font_scripts_cache: HashMap<fontdb::ID, Vec<[u8; 4]>>,
+
+    /// Replacement ids of fonts replaced with [`FontSystem::replace_font_source`]
+    font_id_replacements: HashMap<fontdb::ID, fontdb::ID>,
+
+    /// Locale used instead of `locale` while shaping, see [`FontSystem::with_locale`]
+    locale_override: Option<String>,
+
+    /// Fonts shaping is restricted to, see [`FontSystem::with_font_whitelist`]
+    font_whitelist: Option<Vec<fontdb::ID>>,
+
+    /// Fallbacks for locales other than the system locale
+    locale_fallbacks: HashMap<String, Fallbacks>,
+
+    /// Fallbacks for unicode ranges, tried before script fallbacks
+    pub(crate) range_fallbacks: Vec<RangeFallback>,
+
+    /// Faces of the families of range fallbacks with a weight and style, resolved when first
+    /// used and cleared when the database changes
+    range_fallback_ids: HashMap<(String, Weight, Style), Option<fontdb::ID>>,
+
+    /// Color palettes selected for rendering color glyphs, see [`FontSystem::set_color_palette`]
+    color_palettes: HashMap<fontdb::ID, ColorPaletteSelection>,
+
+    /// Custom script and language detection, see [`FontSystem::set_script_detector`]
+    pub(crate) script_detector: Option<ScriptDetector>,
+
+    /// Ids of removed and replaced fonts, in order of removal, for purging caches outside of
+    /// this system such as the [`crate::SwashCache`]
+    removed_font_ids: Vec<fontdb::ID>,
+
+    /// Ids of fonts whose color palette changed, in order of change, for purging images rendered
+    /// with the previous palette outside of this system such as in the [`crate::SwashCache`]
+    palette_changed_font_ids: Vec<fontdb::ID>,
+}
+
+/// The `CPAL` palette and overridden palette entries used to render the `COLR` glyphs of a font
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ColorPaletteSelection {
+    pub(crate) index: usize,
+    pub(crate) overrides: HashMap<u16, Color>,
 }
 
 impl fmt::Debug for FontSystem {
@@ -132,6 +512,7 @@ impl fmt::Debug for FontSystem {
 
 impl FontSystem {
     const FONT_MATCHES_CACHE_SIZE_LIMIT: usize = 256;
+    const MONOSPACE_IDS_CACHE_SIZE_LIMIT: usize = 64;
     /// Create a new [`FontSystem`], that allows access to any installed system fonts
     ///
     /// # Timing
@@ -160,48 +541,60 @@ impl FontSystem {
         Self::new_with_locale_and_db_and_fallback(locale, db, PlatformFallback)
     }
 
+    /// Create a new [`FontSystem`] with a pre-specified set of fonts, like
+    /// [`FontSystem::new_with_fonts`], but fail if no fonts could be loaded. This catches
+    /// misconfiguration early on platforms without system fonts, where
+    /// [`FontSystem::new_with_fonts`] would allow the empty database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FontSystemError::NoFonts`] if the database has no font faces
+    pub fn try_new_with_fonts(
+        fonts: impl IntoIterator<Item = fontdb::Source>,
+    ) -> Result<Self, FontSystemError> {
+        Self::try_new(Self::new_with_fonts(fonts))
+    }
+
+    /// Create a new [`FontSystem`] with a pre-specified locale and font database, like
+    /// [`FontSystem::new_with_locale_and_db`], but fail if the database is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FontSystemError::NoFonts`] if the database has no font faces
+    pub fn try_new_with_locale_and_db(
+        locale: String,
+        db: fontdb::Database,
+    ) -> Result<Self, FontSystemError> {
+        Self::try_new(Self::new_with_locale_and_db(locale, db))
+    }
+
+    fn try_new(font_system: Self) -> Result<Self, FontSystemError> {
+        if font_system.db.is_empty() {
+            Err(FontSystemError::NoFonts)
+        } else {
+            Ok(font_system)
+        }
+    }
+
     /// Create a new [`FontSystem`] with a pre-specified locale, font database and font fallback list.
     pub fn new_with_locale_and_db_and_fallback(
         locale: String,
         db: fontdb::Database,
         impl_fallback: impl Fallback + 'static,
     ) -> Self {
-        let mut monospace_font_ids = db
-            .faces()
-            .filter(|face_info| {
-                face_info.monospaced && !face_info.post_script_name.contains("Emoji")
-            })
-            .map(|face_info| face_info.id)
-            .collect::<Vec<_>>();
-        monospace_font_ids.sort();
-
-        let mut per_script_monospace_font_ids: HashMap<[u8; 4], BTreeSet<fontdb::ID>> =
-            HashMap::default();
-
-        if cfg!(feature = "monospace_fallback") {
-            monospace_font_ids.iter().for_each(|&id| {
-                db.with_face_data(id, |font_data, face_index| {
-                    let _ = ttf_parser::Face::parse(font_data, face_index).map(|face| {
-                        face.tables()
-                            .gpos
-                            .into_iter()
-                            .chain(face.tables().gsub)
-                            .flat_map(|table| table.scripts)
-                            .inspect(|script| {
-                                per_script_monospace_font_ids
-                                    .entry(script.tag.to_bytes())
-                                    .or_default()
-                                    .insert(id);
-                            })
-                    });
-                });
-            });
-        }
+        Self::new_from_shared_db(locale, Arc::new(db), impl_fallback)
+    }
 
-        let per_script_monospace_font_ids = per_script_monospace_font_ids
-            .into_iter()
-            .map(|(k, v)| (k, Vec::from_iter(v)))
-            .collect();
+    /// Create a new [`FontSystem`] with a pre-specified locale and font fallback list, using a
+    /// font database shared with other font systems. Only the caches are owned by this system.
+    ///
+    /// The database is not copied unless it is modified, see [`FontSystem::db_mut`].
+    pub fn new_from_shared_db(
+        locale: String,
+        db: Arc<fontdb::Database>,
+        impl_fallback: impl Fallback + 'static,
+    ) -> Self {
+        let (monospace_font_ids, per_script_monospace_font_ids) = monospace_font_ids(&db);
 
         let fallbacks = Fallbacks::new(&impl_fallback, &[], &locale);
 
@@ -210,15 +603,40 @@ impl FontSystem {
             db,
             monospace_font_ids,
             per_script_monospace_font_ids,
+            monospace_ids_for_scripts_cache: Default::default(),
             font_cache: Default::default(),
             font_matches_cache: Default::default(),
             font_codepoint_support_info_cache: Default::default(),
+            cache_tick: 0,
+            cache_memory_usage: 0,
+            memory_budget: None,
             monospace_fallbacks_buffer: BTreeSet::default(),
             #[cfg(feature = "shape-run-cache")]
             shape_run_cache: crate::ShapeRunCache::default(),
             shape_buffer: ShapeBuffer::default(),
             dyn_fallback: Box::new(impl_fallback),
             fallbacks,
+            shaped_line_count: 0,
+            duplicate_family_strategy: DuplicateFamilyStrategy::default(),
+            fallback_style_affinity: false,
+            fallback_metric_matching: false,
+            fallback_face_matching: false,
+            space_advance_source: SpaceAdvanceSource::default(),
+            pua_policy: PuaPolicy::default(),
+            mark_fallback: MarkFallback::default(),
+            max_run_length: usize::MAX,
+            fallback_font_count: 0,
+            font_scripts_cache: Default::default(),
+            font_id_replacements: Default::default(),
+            locale_override: None,
+            font_whitelist: None,
+            locale_fallbacks: Default::default(),
+            range_fallbacks: Vec::new(),
+            range_fallback_ids: Default::default(),
+            color_palettes: Default::default(),
+            script_detector: None,
+            removed_font_ids: Vec::new(),
+            palette_changed_font_ids: Vec::new(),
         }
     }
 
@@ -232,43 +650,652 @@ impl FontSystem {
         &self.locale
     }
 
+    /// Get the locale used for fallback resolution, which may be overridden while shaping a
+    /// [`crate::Buffer`] with its own locale
+    pub(crate) fn shaping_locale(&self) -> &str {
+        self.locale_override.as_deref().unwrap_or(&self.locale)
+    }
+
+    /// Get the locale override active while shaping a [`crate::Buffer`] with its own locale
+    pub(crate) fn locale_override(&self) -> Option<&str> {
+        self.locale_override.as_deref()
+    }
+
+    /// Run `f` with fallbacks and language resolved for `locale_opt` instead of the system
+    /// locale
+    pub(crate) fn with_locale<R>(
+        &mut self,
+        locale_opt: Option<&str>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let Some(locale) = locale_opt else {
+            return f(self);
+        };
+
+        // The system fallbacks are reused if the locale matches
+        let system_fallbacks_opt = (locale != self.locale).then(|| {
+            let fallbacks = match self.locale_fallbacks.remove(locale) {
+                Some(fallbacks) => fallbacks,
+                None => Fallbacks::new(self.dyn_fallback.as_ref(), &[], locale),
+            };
+            core::mem::replace(&mut self.fallbacks, fallbacks)
+        });
+        self.locale_override = Some(locale.into());
+
+        let result = f(self);
+
+        let locale_opt = self.locale_override.take();
+        if let (Some(system_fallbacks), Some(locale)) = (system_fallbacks_opt, locale_opt) {
+            let fallbacks = core::mem::replace(&mut self.fallbacks, system_fallbacks);
+            self.locale_fallbacks.insert(locale, fallbacks);
+        }
+        result
+    }
+
+    /// Get the fonts shaping is restricted to while shaping a [`crate::Buffer`] with a font
+    /// whitelist
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn font_whitelist(&self) -> Option<&[fontdb::ID]> {
+        self.font_whitelist.as_deref()
+    }
+
+    /// True if the font `id` may be used for shaping
+    pub(crate) fn font_allowed(&self, id: fontdb::ID) -> bool {
+        self.font_whitelist
+            .as_ref()
+            .map_or(true, |font_whitelist| font_whitelist.contains(&id))
+    }
+
+    /// Get the first whitelisted font that loads, used to draw missing glyphs when no whitelisted
+    /// font matches the requested attributes
+    pub(crate) fn first_allowed_font(&mut self) -> Option<Arc<Font>> {
+        let len = self.font_whitelist.as_ref()?.len();
+        (0..len).find_map(|i| {
+            let id = self.font_whitelist.as_ref()?[i];
+            self.get_font(id)
+        })
+    }
+
+    /// Run `f` with font matching and fallback restricted to the fonts in `font_whitelist_opt`
+    pub(crate) fn with_font_whitelist<R>(
+        &mut self,
+        font_whitelist_opt: Option<&[fontdb::ID]>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let Some(font_whitelist) = font_whitelist_opt else {
+            return f(self);
+        };
+        let previous = self.font_whitelist.replace(font_whitelist.to_vec());
+        let result = f(self);
+        self.font_whitelist = previous;
+        result
+    }
+
     /// Get the database.
     pub fn db(&self) -> &fontdb::Database {
         &self.db
     }
 
+    /// Get the database as shared with other font systems, see
+    /// [`FontSystem::new_from_shared_db`].
+    pub fn shared_db(&self) -> &Arc<fontdb::Database> {
+        &self.db
+    }
+
     /// Get a mutable reference to the database.
+    ///
+    /// If the database is shared with other font systems, it is copied first so they are not
+    /// affected. Changing the fonts affects shaping like a
+    /// [shaping setting](FontSystem#shaping-settings).
     pub fn db_mut(&mut self) -> &mut fontdb::Database {
-        self.font_matches_cache.clear();
-        &mut self.db
+        self.clear_font_matches_cache();
+        self.range_fallback_ids.clear();
+        self.invalidate_shaping();
+        Arc::make_mut(&mut self.db)
+    }
+
+    /// Load the faces in `source` into the database, returning their ids.
+    ///
+    /// Unlike loading them through [`FontSystem::db_mut`], only the cached font matches for
+    /// attributes the new faces match are invalidated, and loaded fonts stay cached.
+    pub fn load_font_source(&mut self, source: fontdb::Source) -> Vec<fontdb::ID> {
+        let ids: Vec<fontdb::ID> = Arc::make_mut(&mut self.db)
+            .load_font_source(source)
+            .into_iter()
+            .collect();
+        let faces: Vec<&fontdb::FaceInfo> = ids.iter().filter_map(|&id| self.db.face(id)).collect();
+        let cache_memory_usage = &mut self.cache_memory_usage;
+        self.font_matches_cache.retain(|attrs, (_, keys)| {
+            let keep = !faces.iter().any(|face| attrs.matches_any_stretch(face));
+            if !keep {
+                *cache_memory_usage -= font_matches_entry_size(keys);
+            }
+            keep
+        });
+        self.range_fallback_ids.clear();
+        ids
+    }
+
+    /// Decode the WOFF or WOFF2 font `data` and load its faces into the database, returning
+    /// their ids.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`crate::WoffError`] if `data` is not a valid WOFF or WOFF2 font
+    #[cfg(feature = "woff")]
+    pub fn load_woff(&mut self, data: &[u8]) -> Result<Vec<fontdb::ID>, crate::WoffError> {
+        let sfnt = crate::decode_woff(data)?;
+        Ok(self.load_font_source(fontdb::Source::Binary(Arc::new(sfnt))))
+    }
+
+    /// Decode the WOFF2 font or collection `data` and load its faces into the database,
+    /// returning their ids.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`crate::WoffError`] if `data` is not a valid WOFF2 font
+    #[cfg(feature = "woff")]
+    pub fn load_woff2(&mut self, data: &[u8]) -> Result<Vec<fontdb::ID>, crate::WoffError> {
+        let sfnt = crate::decode_woff2(data)?;
+        Ok(self.load_font_source(fontdb::Source::Binary(Arc::new(sfnt))))
+    }
+
+    /// Replace the font `old_id` with the faces in `source`, returning the id of the first new face.
+    ///
+    /// The old face is removed from the database and every cache referencing it is invalidated.
+    /// Ids are never reused, so use [`FontSystem::replacement_font_id`] to map ids held
+    /// elsewhere, and reset shaping of any [`crate::Buffer`] that used the old font.
+    pub fn replace_font_source(
+        &mut self,
+        old_id: fontdb::ID,
+        source: fontdb::Source,
+    ) -> Option<fontdb::ID> {
+        let db = Arc::make_mut(&mut self.db);
+        let new_id = db.load_font_source(source).first().copied()?;
+        db.remove_face(old_id);
+        self.invalidate_font(old_id);
+        self.font_id_replacements.insert(old_id, new_id);
+        Some(new_id)
+    }
+
+    /// Remove the font `id` from the database and invalidate every cache referencing it.
+    ///
+    /// Prefer this to removing faces through [`FontSystem::db_mut`], which leaves cached fonts,
+    /// codepoint coverage and monospace indices of the removed face behind.
+    pub fn remove_font(&mut self, id: fontdb::ID) {
+        Arc::make_mut(&mut self.db).remove_face(id);
+        self.invalidate_font(id);
+    }
+
+    /// Get the id of the font that replaced `id` with [`FontSystem::replace_font_source`],
+    /// following repeated replacements. Returns `None` if `id` was not replaced
+    pub fn replacement_font_id(&self, id: fontdb::ID) -> Option<fontdb::ID> {
+        let mut replacement = *self.font_id_replacements.get(&id)?;
+        while let Some(&next) = self.font_id_replacements.get(&replacement) {
+            replacement = next;
+        }
+        Some(replacement)
+    }
+
+    /// Get the ids of the fonts removed with [`FontSystem::remove_font`] or replaced with
+    /// [`FontSystem::replace_font_source`], in order of removal
+    pub fn removed_font_ids(&self) -> &[fontdb::ID] {
+        &self.removed_font_ids
+    }
+
+    /// Get the ids of the fonts whose color palette was changed with
+    /// [`FontSystem::set_color_palette`], [`FontSystem::set_color_palette_override`] or
+    /// [`FontSystem::reset_color_palette`], in order of change
+    pub fn palette_changed_font_ids(&self) -> &[fontdb::ID] {
+        &self.palette_changed_font_ids
+    }
+
+    /// Remove all cached data for the font `id`
+    fn invalidate_font(&mut self, id: fontdb::ID) {
+        self.removed_font_ids.push(id);
+        if let Some((_, font_opt)) = self.font_cache.remove(&id) {
+            self.cache_memory_usage -= font_entry_size(&font_opt);
+        }
+        if let Some((_, info)) = self.font_codepoint_support_info_cache.remove(&id) {
+            self.cache_memory_usage -= codepoint_support_entry_size(&info);
+        }
+        self.font_scripts_cache.remove(&id);
+        self.color_palettes.remove(&id);
+        self.monospace_font_ids
+            .retain(|&monospace_id| monospace_id != id);
+        for ids in self.per_script_monospace_font_ids.values_mut() {
+            ids.retain(|&monospace_id| monospace_id != id);
+        }
+        self.clear_monospace_ids_cache();
+        self.clear_font_matches_cache();
+        self.range_fallback_ids.clear();
+        #[cfg(feature = "shape-run-cache")]
+        self.shape_run_cache.remove_font(id);
     }
 
     /// Consume this [`FontSystem`] and return the locale and database.
+    ///
+    /// If the database is shared with other font systems, a copy is returned.
     pub fn into_locale_and_db(self) -> (String, fontdb::Database) {
-        (self.locale, self.db)
+        let db = Arc::try_unwrap(self.db).unwrap_or_else(|db| (*db).clone());
+        (self.locale, db)
     }
 
     /// Get a font by its ID.
     pub fn get_font(&mut self, id: fontdb::ID) -> Option<Arc<Font>> {
-        self.font_cache
-            .entry(id)
-            .or_insert_with(|| {
-                #[cfg(feature = "std")]
+        let tick = self.next_cache_tick();
+        let mut inserted = false;
+        let (used, font_opt) = self.font_cache.entry(id).or_insert_with(|| {
+            inserted = true;
+            // A shared database is left untouched, instead of copying it to memory map the face
+            #[cfg(feature = "std")]
+            if let Some(db) = Arc::get_mut(&mut self.db) {
                 unsafe {
-                    self.db.make_shared_face_data(id);
+                    db.make_shared_face_data(id);
                 }
-                match Font::new(&self.db, id) {
-                    Some(font) => Some(Arc::new(font)),
-                    None => {
-                        log::warn!(
-                            "failed to load font '{}'",
-                            self.db.face(id)?.post_script_name
-                        );
-                        None
-                    }
+            }
+            let font_opt = Font::new(&self.db, id).map(Arc::new);
+            if font_opt.is_none() {
+                if let Some(face) = self.db.face(id) {
+                    log::warn!("failed to load font '{}'", face.post_script_name);
                 }
+            }
+            (tick, font_opt)
+        });
+        *used = tick;
+        let font_opt = font_opt.clone();
+        if inserted {
+            self.cache_memory_usage += font_entry_size(&font_opt);
+            self.enforce_memory_budget();
+        }
+        font_opt
+    }
+
+    /// Get the current [`DuplicateFamilyStrategy`]
+    pub fn duplicate_family_strategy(&self) -> &DuplicateFamilyStrategy {
+        &self.duplicate_family_strategy
+    }
+
+    /// Set the [`DuplicateFamilyStrategy`] used to choose between faces that match equally well
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_duplicate_family_strategy(&mut self, strategy: DuplicateFamilyStrategy) {
+        if strategy != self.duplicate_family_strategy {
+            self.duplicate_family_strategy = strategy;
+            self.clear_font_matches_cache();
+            self.invalidate_shaping();
+        }
+    }
+
+    /// True if fallback fonts sharing scripts with the primary font are preferred
+    pub fn fallback_style_affinity(&self) -> bool {
+        self.fallback_style_affinity
+    }
+
+    /// Prefer fallback fonts whose supported scripts overlap the most with the primary font of a
+    /// run, before trying unrelated fonts. This avoids glyphs appearing in a mismatched typeface
+    /// when no configured fallback covers them.
+    ///
+    /// Only the fonts tried last, after the script and common fallback families, are reordered.
+    /// Configured fallbacks are still tried first, in their own order.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_fallback_style_affinity(&mut self, fallback_style_affinity: bool) {
+        if fallback_style_affinity != self.fallback_style_affinity {
+            self.fallback_style_affinity = fallback_style_affinity;
+            self.invalidate_shaping();
+        }
+    }
+
+    /// True if fallback glyphs in clusters of the primary font are scaled to match its metrics
+    pub fn fallback_metric_matching(&self) -> bool {
+        self.fallback_metric_matching
+    }
+
+    /// Scale glyphs from fallback fonts in clusters the primary font of the run only partly
+    /// supports, such as a base character with a combining mark it lacks, so that the ascender
+    /// to descender extent of their font matches the primary font. The ascent and descent of
+    /// the cluster are those of the primary font, so the line metrics do not change. This keeps
+    /// marks from a fallback font with very different metrics proportionate to their base.
+    /// Clusters shaped entirely with a fallback font keep its size.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_fallback_metric_matching(&mut self, fallback_metric_matching: bool) {
+        if fallback_metric_matching != self.fallback_metric_matching {
+            self.fallback_metric_matching = fallback_metric_matching;
+            self.invalidate_shaping();
+        }
+    }
+
+    /// True if the faces of fallback families closest to the requested weight and style are used
+    pub fn fallback_face_matching(&self) -> bool {
+        self.fallback_face_matching
+    }
+
+    /// Use the face of each script, common and range fallback family closest to the weight and
+    /// style of the [`Attrs`] being shaped, as [`FontSystem::add_range_fallback_with_style`] does.
+    /// Otherwise only faces with exactly the requested weight and style are used, so a bold or
+    /// italic run can fall back to unrelated fonts, or to none at all, when a fallback family lacks
+    /// that face.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_fallback_face_matching(&mut self, fallback_face_matching: bool) {
+        self.fallback_face_matching = fallback_face_matching;
+        self.invalidate_shaping();
+    }
+
+    /// Get the current [`PuaPolicy`]
+    pub fn pua_policy(&self) -> PuaPolicy {
+        self.pua_policy
+    }
+
+    /// Set how runs made up only of characters from the Unicode Private Use Areas are resolved.
+    /// Icon fonts often use these, and searching the other fonts for them is slow and almost
+    /// never finds the intended glyph. A font set on the [`Attrs`] of the run with
+    /// [`Attrs::font_id`] still takes priority.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_pua_policy(&mut self, pua_policy: PuaPolicy) {
+        self.pua_policy = pua_policy;
+        self.invalidate_shaping();
+    }
+
+    /// Get the current [`MarkFallback`]
+    pub fn mark_fallback(&self) -> MarkFallback {
+        self.mark_fallback
+    }
+
+    /// Set whether combining marks missing from the font of their base character may be shaped
+    /// with a fallback font. Drawing a mark in a mismatched font often looks worse than leaving
+    /// it out, so marks can be dropped instead of drawn as missing glyphs.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_mark_fallback(&mut self, mark_fallback: MarkFallback) {
+        self.mark_fallback = mark_fallback;
+        self.invalidate_shaping();
+    }
+
+    /// Get the length in bytes above which runs are shaped in chunks
+    pub fn max_run_length(&self) -> usize {
+        self.max_run_length
+    }
+
+    /// Shape runs longer than `max_run_length` bytes in chunks of about that length, to bound
+    /// the memory and time used to shape very long runs, such as pathological input without
+    /// spaces. Chunks end at clusters the shaper marks as safe to break before, and grow until
+    /// they have one, so the glyphs are the same as shaping the whole run. Runs are not chunked
+    /// by default.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_max_run_length(&mut self, max_run_length: usize) {
+        self.max_run_length = max_run_length;
+        self.invalidate_shaping();
+    }
+
+    /// Get the current [`SpaceAdvanceSource`]
+    pub fn space_advance_source(&self) -> SpaceAdvanceSource {
+        self.space_advance_source
+    }
+
+    /// Set where the advance of whitespace comes from when the primary font of a run lacks it,
+    /// such as with icon fonts, instead of using whatever fallback font shapes it. This keeps
+    /// spaces from a fallback with unusually wide spaces from disrupting justification.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_space_advance_source(&mut self, space_advance_source: SpaceAdvanceSource) {
+        self.space_advance_source = space_advance_source;
+        self.invalidate_shaping();
+    }
+
+    /// Replace the detection of the script and language that text is shaped with, for example to
+    /// shape romanized text as another language. `None` restores the default detection, where
+    /// the script is guessed from the characters and the language follows the locale of the
+    /// [`crate::Buffer`].
+    ///
+    /// The detector is called with the text of each shaping run, and must return ranges that
+    /// cover the whole text in order, without gaps or overlaps. Each range is shaped separately
+    /// with its script, and its language if set. A script set with [`Attrs::script`] still
+    /// takes priority. Font fallback is not affected.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn set_script_detector(&mut self, script_detector: Option<ScriptDetector>) {
+        self.script_detector = script_detector;
+        self.invalidate_shaping();
+    }
+
+    /// Invalidate cached shaping after a [shaping setting](FontSystem#shaping-settings) changed
+    fn invalidate_shaping(&mut self) {
+        #[cfg(feature = "shape-run-cache")]
+        self.shape_run_cache.clear();
+    }
+
+    /// Try the font family `family` for characters in `range`, before any script or common
+    /// fallbacks.
+    ///
+    /// If `primary_family` is set, the fallback is only used for runs whose requested family has
+    /// that name, for example to use "Roboto Cyrillic" only when "Roboto" is the primary family.
+    /// Fallbacks are tried in the order they were added.
+    ///
+    /// This is a [shaping setting](FontSystem#shaping-settings).
+    pub fn add_range_fallback(
+        &mut self,
+        range: RangeInclusive<char>,
+        family: impl Into<String>,
+        primary_family: Option<&str>,
+    ) {
+        self.range_fallbacks.push(RangeFallback {
+            range,
+            family: family.into(),
+            primary_family: primary_family.map(Into::into),
+            style: None,
+            font_id: None,
+        });
+        self.invalidate_shaping();
+    }
+
+    /// Try the face of the font family `family` closest to `weight` and `style` for characters in
+    /// `range`, like [`FontSystem::add_range_fallback`], whatever the attributes of the run.
+    ///
+    /// The face is looked up by name when first needed and again after the database changes, so
+    /// the family can be registered before its fonts are loaded.
+    pub fn add_range_fallback_with_style(
+        &mut self,
+        range: RangeInclusive<char>,
+        family: impl Into<String>,
+        primary_family: Option<&str>,
+        weight: Weight,
+        style: Style,
+    ) {
+        self.range_fallbacks.push(RangeFallback {
+            range,
+            family: family.into(),
+            primary_family: primary_family.map(Into::into),
+            style: Some((weight, style)),
+            font_id: None,
+        });
+        self.invalidate_shaping();
+    }
+
+    /// Try the fonts `ids` in order for characters in `range`, like
+    /// [`FontSystem::add_range_fallback`].
+    ///
+    /// Fonts without any of the characters of a run in `range` are skipped, and characters none
+    /// of the fonts have go on to the script and common fallbacks. For example, a font covering
+    /// kana can be followed by a second font for rare kanji it lacks.
+    pub fn add_range_fallback_fonts(
+        &mut self,
+        range: RangeInclusive<char>,
+        ids: &[fontdb::ID],
+        primary_family: Option<&str>,
+    ) {
+        for &id in ids {
+            let family = self
+                .db
+                .face(id)
+                .and_then(|face| face.families.first())
+                .map_or_else(String::new, |(family, _)| family.clone());
+            self.range_fallbacks.push(RangeFallback {
+                range: range.clone(),
+                family,
+                primary_family: primary_family.map(Into::into),
+                style: None,
+                font_id: Some(id),
+            });
+        }
+        self.invalidate_shaping();
+    }
+
+    /// Get the face of `family` closest to `weight` and `style` for a fallback
+    pub(crate) fn range_fallback_id(
+        &mut self,
+        family: &str,
+        weight: Weight,
+        style: Style,
+    ) -> Option<fontdb::ID> {
+        let key = (family.into(), weight, style);
+        if let Some(id_opt) = self.range_fallback_ids.get(&key) {
+            return *id_opt;
+        }
+        let id_opt = self.db.query(&fontdb::Query {
+            families: &[fontdb::Family::Name(family)],
+            weight,
+            stretch: fontdb::Stretch::Normal,
+            style,
+        });
+        self.range_fallback_ids.insert(key, id_opt);
+        id_opt
+    }
+
+    /// Remove the fallbacks added with [`FontSystem::add_range_fallback`] for exactly the range
+    /// from `start` to `end` inclusive, whether or not they are limited to a primary family.
+    /// Fallbacks for other ranges are kept, even if they overlap it.
+    ///
+    /// Returns `true` if any fallback was removed
+    pub fn remove_unicode_range_fallback(&mut self, start: char, end: char) -> bool {
+        let range = start..=end;
+        let len = self.range_fallbacks.len();
+        self.range_fallbacks
+            .retain(|range_fallback| range_fallback.range != range);
+        let removed = self.range_fallbacks.len() != len;
+        if removed {
+            self.invalidate_shaping();
+        }
+        removed
+    }
+
+    /// Remove all fallbacks added with [`FontSystem::add_range_fallback`]
+    pub fn clear_unicode_range_fallbacks(&mut self) {
+        if !self.range_fallbacks.is_empty() {
+            self.range_fallbacks.clear();
+            self.invalidate_shaping();
+        }
+    }
+
+    /// Render the `COLR` glyphs of the font `id` with its `CPAL` palette `palette_index`, for
+    /// example to pick a dark palette in a dark theme. Palette 0 is used by default.
+    ///
+    /// Images of the font cached by a [`crate::SwashCache`] are rendered again on its next use.
+    pub fn set_color_palette(&mut self, id: fontdb::ID, palette_index: usize) {
+        let palette = self.color_palettes.entry(id).or_default();
+        if palette.index != palette_index {
+            palette.index = palette_index;
+            self.palette_changed_font_ids.push(id);
+        }
+    }
+
+    /// Render the `CPAL` palette entry `entry` of the font `id` with `color`, whichever palette
+    /// is selected with [`FontSystem::set_color_palette`]
+    pub fn set_color_palette_override(&mut self, id: fontdb::ID, entry: u16, color: Color) {
+        let overrides = &mut self.color_palettes.entry(id).or_default().overrides;
+        if overrides.insert(entry, color) != Some(color) {
+            self.palette_changed_font_ids.push(id);
+        }
+    }
+
+    /// Use the default palette of the font `id` again, removing any palette entry overrides
+    pub fn reset_color_palette(&mut self, id: fontdb::ID) {
+        if self.color_palettes.remove(&id).is_some() {
+            self.palette_changed_font_ids.push(id);
+        }
+    }
+
+    /// Get the color palette selected for the font `id`
+    #[cfg(feature = "swash")]
+    pub(crate) fn color_palette(&self, id: fontdb::ID) -> Option<&ColorPaletteSelection> {
+        self.color_palettes.get(&id)
+    }
+
+    /// Get the sorted OpenType script tags from the `GSUB` and `GPOS` tables of a font
+    pub(crate) fn font_scripts(&mut self, id: fontdb::ID) -> &[[u8; 4]] {
+        let db = &self.db;
+        self.font_scripts_cache.entry(id).or_insert_with(|| {
+            let mut scripts = db
+                .with_face_data(id, |font_data, face_index| {
+                    let face = ttf_parser::Face::parse(font_data, face_index).ok()?;
+                    Some(
+                        face.tables()
+                            .gpos
+                            .into_iter()
+                            .chain(face.tables().gsub)
+                            .flat_map(|table| table.scripts)
+                            .map(|script| script.tag.to_bytes())
+                            .filter(|tag| tag != b"DFLT")
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .flatten()
+                .unwrap_or_default();
+            scripts.sort();
+            scripts.dedup();
+            scripts
+        })
+    }
+
+    /// Get the stylistic sets (`ss01` to `ss20`) in the `GSUB` table of a font, sorted by tag,
+    /// with the names the font gives them, for example to list them in a font feature menu.
+    ///
+    /// Enable a stylistic set with [`Attrs::stylistic_set`].
+    pub fn stylistic_sets(&self, id: fontdb::ID) -> Vec<(FeatureTag, Option<String>)> {
+        self.db
+            .with_face_data(id, |font_data, face_index| {
+                let face = ttf_parser::Face::parse(font_data, face_index).ok()?;
+                let gsub = face
+                    .raw_face()
+                    .table(ttf_parser::Tag::from_bytes(b"GSUB"))?;
+                let mut sets = stylistic_set_name_ids(gsub)?
+                    .into_iter()
+                    .map(|(tag, name_id)| (tag, name_id.and_then(|id| font_name(&face, id))))
+                    .collect::<Vec<_>>();
+                sets.sort_by_key(|(tag, _)| *tag.as_bytes());
+                sets.dedup_by(|(b_tag, b_name), (a_tag, a_name)| {
+                    if a_tag != b_tag {
+                        return false;
+                    }
+                    if a_name.is_none() {
+                        *a_name = b_name.take();
+                    }
+                    true
+                });
+                Some(sets)
             })
-            .clone()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// Get the number of lines that have been shaped with this [`FontSystem`].
+    ///
+    /// This is useful for checking that an operation reused cached shaping instead of shaping
+    /// lines again.
+    pub fn shaped_line_count(&self) -> u64 {
+        self.shaped_line_count
+    }
+
+    /// Get the number of fallback fonts that have been tried for glyphs missing from the primary
+    /// font of a run.
+    ///
+    /// This is useful for checking that text resolved its fonts without a fallback search.
+    pub fn fallback_font_count(&self) -> u64 {
+        self.fallback_font_count
     }
 
     pub fn is_monospace(&self, id: fontdb::ID) -> bool {
@@ -288,6 +1315,44 @@ impl FontSystem {
         ret
     }
 
+    /// Get [`Self::get_monospace_ids_for_scripts`] from a cache per set of scripts, evicting the
+    /// least recently used set when the cache is full
+    pub(crate) fn cached_monospace_ids_for_scripts(
+        &mut self,
+        scripts: impl Iterator<Item = [u8; 4]>,
+    ) -> Arc<Vec<fontdb::ID>> {
+        let mut scripts = scripts.collect::<Vec<_>>();
+        scripts.sort();
+        scripts.dedup();
+
+        let tick = self.next_cache_tick();
+        if let Some((used, ids)) = self.monospace_ids_for_scripts_cache.get_mut(&scripts) {
+            *used = tick;
+            return ids.clone();
+        }
+
+        let ids = Arc::new(self.get_monospace_ids_for_scripts(scripts.iter().copied()));
+
+        if self.monospace_ids_for_scripts_cache.len() >= Self::MONOSPACE_IDS_CACHE_SIZE_LIMIT {
+            let oldest_opt = self
+                .monospace_ids_for_scripts_cache
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(scripts, _)| scripts.clone());
+            if let Some(oldest) = oldest_opt {
+                if let Some((_, oldest_ids)) = self.monospace_ids_for_scripts_cache.remove(&oldest)
+                {
+                    self.cache_memory_usage -= monospace_ids_entry_size(&oldest, &oldest_ids);
+                }
+            }
+        }
+        self.cache_memory_usage += monospace_ids_entry_size(&scripts, &ids);
+        self.monospace_ids_for_scripts_cache
+            .insert(scripts, (tick, ids.clone()));
+        self.enforce_memory_budget();
+        ids
+    }
+
     #[inline(always)]
     pub fn get_font_supported_codepoints_in_word(
         &mut self,
@@ -296,42 +1361,242 @@ impl FontSystem {
     ) -> Option<usize> {
         self.get_font(id).map(|font| {
             let code_points = font.unicode_codepoints();
-            let cache = self
+            let tick = self.next_cache_tick();
+            let mut inserted = false;
+            let (used, cache) = self
                 .font_codepoint_support_info_cache
                 .entry(id)
-                .or_insert_with(FontCachedCodepointSupportInfo::new);
-            word.chars()
+                .or_insert_with(|| {
+                    inserted = true;
+                    (tick, FontCachedCodepointSupportInfo::new())
+                });
+            *used = tick;
+            let size_before = if inserted {
+                0
+            } else {
+                codepoint_support_entry_size(cache)
+            };
+            let count = word
+                .chars()
                 .filter(|ch| cache.has_codepoint(code_points, u32::from(*ch)))
-                .count()
+                .count();
+            let size_after = codepoint_support_entry_size(cache);
+            self.cache_memory_usage += size_after - size_before;
+            if inserted {
+                self.enforce_memory_budget();
+            }
+            count
         })
     }
 
+    /// Get the glyph id that the cmap of the font `id` maps `c` to, or `None` if the font does not
+    /// cover `c` or cannot be loaded.
+    ///
+    /// This does no shaping, so it is mostly useful for icon fonts and custom shaping.
+    pub fn glyph_index(&mut self, id: fontdb::ID, c: char) -> Option<u16> {
+        let font = self.get_font(id)?;
+        let glyph_id = font.rustybuzz().glyph_index(c)?;
+        (glyph_id.0 != 0).then_some(glyph_id.0)
+    }
+
+    /// Get the font that shaping `c` with `attrs` would choose, without shaping it.
+    ///
+    /// This follows the same selection as shaping: [`Attrs::font_id`], the [`PuaPolicy`], the
+    /// matching fonts, Unicode range fallbacks and the platform fallback chain, in that order. If
+    /// no font covers `c`, the primary font is returned, as its missing glyph is what is drawn.
+    /// Returns `None` only if no font can be loaded at all.
+    pub fn font_for_char(&mut self, c: char, attrs: &Attrs<'_>) -> Option<fontdb::ID> {
+        let private_use = crate::shape::is_private_use(c);
+        let font_id_opt = attrs
+            .font_id_opt
+            .or(self.pua_policy.font.filter(|_| private_use))
+            .filter(|&id| self.font_allowed(id));
+        if let Some(id) = font_id_opt.filter(|&id| self.get_font(id).is_some()) {
+            return Some(id);
+        }
+        let skip_fallback = private_use && self.pua_policy.skip_fallback;
+
+        let scripts: Vec<Script> = match c.script() {
+            Script::Common | Script::Inherited | Script::Latin | Script::Unknown => Vec::new(),
+            script => alloc::vec![script],
+        };
+        let fonts = self.get_font_matches(attrs);
+        let mut word = [0; 4];
+        let word = c.encode_utf8(&mut word);
+        let default_families = [&attrs.family];
+        let mut font_iter = FontFallbackIter::new(self, &fonts, &default_families, &scripts, word)
+            .with_style(attrs.weight, attrs.style);
+
+        let primary = font_iter.next()?;
+        if skip_fallback
+            || primary
+                .rustybuzz()
+                .glyph_index(c)
+                .is_some_and(|id| id.0 != 0)
+        {
+            return Some(primary.id());
+        }
+        let id_opt = font_iter
+            .find(|font| font.rustybuzz().glyph_index(c).is_some_and(|id| id.0 != 0))
+            .map(|font| font.id());
+        Some(id_opt.unwrap_or_else(|| primary.id()))
+    }
+
+    /// Get the kerning between the glyphs `left` and `right` of the font `id` at `font_size`,
+    /// which is added to the advance of `left` when it is followed by `right`. Negative values
+    /// move the glyphs closer together.
+    ///
+    /// The pair adjustments of the `kern` feature in `GPOS` are used, or the legacy `kern` table
+    /// if the font has no `GPOS` kerning. Returns `0.0` if the pair is not kerned or the font
+    /// cannot be loaded.
+    pub fn kerning(&mut self, id: fontdb::ID, left: u16, right: u16, font_size: f32) -> f32 {
+        let Some(font) = self.get_font(id) else {
+            return 0.0;
+        };
+        let face = font.rustybuzz();
+        let (left, right) = (ttf_parser::GlyphId(left), ttf_parser::GlyphId(right));
+        let units = gpos_kerning(face, left, right).unwrap_or_else(|| {
+            face.tables()
+                .kern
+                .into_iter()
+                .flat_map(|kern| kern.subtables)
+                .filter(|subtable| subtable.horizontal && !subtable.variable)
+                .filter_map(|subtable| subtable.glyphs_kerning(left, right))
+                .fold(0, i16::saturating_add)
+        });
+        f32::from(units) * font_size / face.units_per_em() as f32
+    }
+
+    /// Get the codepoints covered by the cmap of the font `id` as sorted, coalesced ranges.
+    /// Returns an empty list if the font cannot be loaded
+    pub fn font_coverage_ranges(&mut self, id: fontdb::ID) -> Vec<RangeInclusive<char>> {
+        let Some(font) = self.get_font(id) else {
+            return Vec::new();
+        };
+
+        let mut codepoints = Vec::new();
+        if let Some(cmap) = font.rustybuzz().tables().cmap {
+            for subtable in cmap.subtables {
+                if subtable.is_unicode() {
+                    subtable.codepoints(|codepoint| {
+                        if subtable.glyph_index(codepoint).is_some() {
+                            codepoints.extend(char::from_u32(codepoint));
+                        }
+                    });
+                }
+            }
+        }
+        codepoints.sort_unstable();
+        codepoints.dedup();
+
+        let mut ranges: Vec<RangeInclusive<char>> = Vec::new();
+        for c in codepoints {
+            match ranges.last_mut() {
+                Some(range) if u32::from(*range.end()) + 1 == u32::from(c) => {
+                    *range = *range.start()..=c;
+                }
+                _ => ranges.push(c..=c),
+            }
+        }
+        ranges
+    }
+
+    /// Get the fraction of graphemes in `text` that the font `id` can render without fallback,
+    /// from `0.0` (none) to `1.0` (all).
+    ///
+    /// This is useful for ranking candidate fonts for a document. Returns `0.0` if the font
+    /// cannot be loaded and `1.0` if `text` is empty.
+    pub fn coverage_fraction(&mut self, id: fontdb::ID, text: &str) -> f32 {
+        let Some(font) = self.get_font(id) else {
+            return 0.0;
+        };
+
+        let mut total = 0;
+        let mut covered = 0;
+        for grapheme in text.graphemes(true) {
+            total += 1;
+            // Codepoints are only collected when the `monospace_fallback` feature is enabled
+            let supported = if font.unicode_codepoints().is_empty() {
+                grapheme
+                    .chars()
+                    .all(|c| font.rustybuzz().glyph_index(c).is_some())
+            } else {
+                self.get_font_supported_codepoints_in_word(id, grapheme)
+                    == Some(grapheme.chars().count())
+            };
+            if supported {
+                covered += 1;
+            }
+        }
+
+        if total == 0 {
+            1.0
+        } else {
+            covered as f32 / total as f32
+        }
+    }
+
+    /// Get the faces matching `attrs`, from the same cache as [`FontSystem::get_font_matches`].
+    ///
+    /// The faces are sorted like the keys, which is the priority order they are tried in when
+    /// shaping, before any fallback fonts.
+    pub fn matches_for_attrs(&mut self, attrs: &Attrs<'_>) -> Vec<&fontdb::FaceInfo> {
+        let keys = self.get_font_matches(attrs);
+        keys.iter().filter_map(|key| self.db.face(key.id)).collect()
+    }
+
     pub fn get_font_matches(&mut self, attrs: &Attrs<'_>) -> Arc<Vec<FontMatchKey>> {
         // Clear the cache first if it reached the size limit
         if self.font_matches_cache.len() >= Self::FONT_MATCHES_CACHE_SIZE_LIMIT {
             log::trace!("clear font mache cache");
-            self.font_matches_cache.clear();
+            self.clear_font_matches_cache();
         }
 
-        self.font_matches_cache
+        let tick = self.next_cache_tick();
+        let mut inserted = false;
+        let (used, font_match_keys) = self
+            .font_matches_cache
             //TODO: do not create AttrsOwned unless entry does not already exist
             .entry(attrs.into())
             .or_insert_with(|| {
+                inserted = true;
+
                 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
                 let now = std::time::Instant::now();
 
-                let mut font_match_keys = self
+                // Faces of another stretch are only used for families without a face of the
+                // requested stretch
+                let exact_families: BTreeSet<&str> = self
                     .db
                     .faces()
                     .filter(|face| attrs.matches(face))
-                    .map(|face| FontMatchKey {
+                    .filter_map(|face| face.families.first())
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                let face_count = self.db.len();
+                let mut font_match_keys = self
+                    .db
+                    .faces()
+                    .enumerate()
+                    .filter(|(_, face)| {
+                        attrs.matches(face)
+                            || (attrs.matches_any_stretch(face)
+                                && face.families.first().map_or(true, |(name, _)| {
+                                    !exact_families.contains(name.as_str())
+                                }))
+                    })
+                    .map(|(load_index, face)| FontMatchKey {
                         font_weight_diff: attrs.weight.0.abs_diff(face.weight.0),
                         font_weight: face.weight.0,
+                        stretch_diff: attrs.stretch.to_number().abs_diff(face.stretch.to_number()),
+                        duplicate_rank: self
+                            .duplicate_family_strategy
+                            .rank(&self.db, face, load_index, face_count),
                         id: face.id,
                     })
                     .collect::<Vec<_>>();
 
-                // Sort so we get the keys with weight_offset=0 first
+                // Sort so we get the keys with stretch_diff=0 and weight_offset=0 first
                 font_match_keys.sort();
 
                 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
@@ -340,9 +1605,206 @@ impl FontSystem {
                     log::debug!("font matches for {:?} in {:?}", attrs, elapsed);
                 }
 
-                Arc::new(font_match_keys)
-            })
-            .clone()
+                (tick, Arc::new(font_match_keys))
+            });
+        *used = tick;
+        let font_match_keys = font_match_keys.clone();
+        if inserted {
+            self.cache_memory_usage += font_matches_entry_size(&font_match_keys);
+            self.enforce_memory_budget();
+        }
+        font_match_keys
+    }
+
+    /// Check if the family of `attrs` is available in the database, with any weight, rather than
+    /// shaping falling back to other fonts. Generic families are resolved to the family configured
+    /// in the database.
+    ///
+    /// This is useful to warn when a requested font is not installed.
+    pub fn family_resolved_exactly(&mut self, attrs: &Attrs<'_>) -> bool {
+        let font_match_keys = self.get_font_matches(attrs);
+        let family_name = self.db.family_name(&attrs.family);
+        font_match_keys.iter().any(|m_key| {
+            self.db
+                .face(m_key.id)
+                .is_some_and(|face| face.families.iter().any(|(name, _)| name == family_name))
+        })
+    }
+
+    /// Shape `text` with `attrs` and keep its runs in [`Self::shape_run_cache`] until they are
+    /// unpinned with [`Self::unpin_shaped`], so that shaping it again never misses the cache,
+    /// for example for the labels of a user interface.
+    ///
+    /// Pinned runs are never trimmed or evicted and do not count towards the memory budget. They
+    /// are only invalidated by changes such as removing a font, after which they are cached and
+    /// kept again the next time they are shaped. Runs are only cached with [`Shaping::Advanced`]
+    /// and the current locale.
+    #[cfg(feature = "shape-run-cache")]
+    pub fn pin_shaped(&mut self, text: &str, attrs: &Attrs<'_>, shaping: Shaping) {
+        for key in self.shaped_run_keys(text, attrs, shaping) {
+            self.shape_run_cache.pin(key);
+        }
+    }
+
+    /// Undo a [`Self::pin_shaped`] with the same arguments, allowing the runs to be evicted
+    /// again once they are not pinned by any other call
+    #[cfg(feature = "shape-run-cache")]
+    pub fn unpin_shaped(&mut self, text: &str, attrs: &Attrs<'_>, shaping: Shaping) {
+        for key in self.shaped_run_keys(text, attrs, shaping) {
+            self.shape_run_cache.unpin(&key);
+        }
+    }
+
+    /// Shape `text` like a [`crate::Buffer`] would, and get the keys of the cached runs used
+    #[cfg(feature = "shape-run-cache")]
+    fn shaped_run_keys(
+        &mut self,
+        text: &str,
+        attrs: &Attrs<'_>,
+        shaping: Shaping,
+    ) -> Vec<crate::ShapeRunKey> {
+        let attrs_list = AttrsList::new(attrs);
+        self.shape_run_cache.record(true);
+        for (range, _ending) in crate::LineIter::new(text) {
+            ShapeLine::new(self, &text[range], &attrs_list, shaping, 8);
+        }
+        self.shape_run_cache.record(false)
+    }
+
+    /// Get the budget in bytes for the combined size of the caches of this [`FontSystem`]
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    /// Bound the combined size of the caches of this [`FontSystem`], such as the font, codepoint
+    /// support, font match and shape run caches, to about `budget_opt` bytes, evicting the least
+    /// recently used entries across all of them when it is exceeded. `None` removes the bound.
+    ///
+    /// Sizes are rough estimates, see [`FontSystem::cache_memory_usage`]
+    pub fn set_memory_budget(&mut self, budget_opt: Option<usize>) {
+        self.memory_budget = budget_opt;
+        self.enforce_memory_budget();
+    }
+
+    /// Get the estimated size in bytes of the caches of this [`FontSystem`]. Font data owned by
+    /// the database is not included, as evicting fonts does not free it, but loaded fonts count
+    /// their parsed face and supported codepoints. Tables the shaper parses lazily are not
+    /// counted, so the actual size can be somewhat larger.
+    pub fn cache_memory_usage(&self) -> usize {
+        #[cfg(feature = "shape-run-cache")]
+        let shape_run_usage = self.shape_run_cache.memory_usage();
+        #[cfg(not(feature = "shape-run-cache"))]
+        let shape_run_usage = 0;
+        self.cache_memory_usage + shape_run_usage
+    }
+
+    /// Get the tick and estimated size of every cache entry
+    fn cache_entry_sizes(&self) -> Vec<(u64, usize)> {
+        let mut entries = Vec::new();
+        entries.extend(
+            self.font_cache
+                .values()
+                .map(|(tick, font_opt)| (*tick, font_entry_size(font_opt))),
+        );
+        entries.extend(
+            self.font_codepoint_support_info_cache
+                .values()
+                .map(|(tick, info)| (*tick, codepoint_support_entry_size(info))),
+        );
+        entries.extend(
+            self.font_matches_cache
+                .values()
+                .map(|(tick, keys)| (*tick, font_matches_entry_size(keys))),
+        );
+        entries.extend(
+            self.monospace_ids_for_scripts_cache
+                .iter()
+                .map(|(scripts, (tick, ids))| (*tick, monospace_ids_entry_size(scripts, ids))),
+        );
+        #[cfg(feature = "shape-run-cache")]
+        entries.extend(self.shape_run_cache.entry_sizes());
+        entries
+    }
+
+    fn clear_font_matches_cache(&mut self) {
+        for (_, keys) in self.font_matches_cache.values() {
+            self.cache_memory_usage -= font_matches_entry_size(keys);
+        }
+        self.font_matches_cache.clear();
+    }
+
+    fn clear_monospace_ids_cache(&mut self) {
+        for (scripts, (_, ids)) in self.monospace_ids_for_scripts_cache.iter() {
+            self.cache_memory_usage -= monospace_ids_entry_size(scripts, ids);
+        }
+        self.monospace_ids_for_scripts_cache.clear();
+    }
+
+    /// Advance the tick recorded by cache entries when they are used
+    pub(crate) fn next_cache_tick(&mut self) -> u64 {
+        self.cache_tick += 1;
+        #[cfg(feature = "shape-run-cache")]
+        {
+            self.shape_run_cache.tick = self.cache_tick;
+        }
+        self.cache_tick
+    }
+
+    /// Evict the least recently used cache entries until the caches fit in the memory budget
+    pub(crate) fn enforce_memory_budget(&mut self) {
+        let Some(budget) = self.memory_budget else {
+            return;
+        };
+        let mut usage = self.cache_memory_usage();
+        if usage <= budget {
+            return;
+        }
+
+        let mut entries = self.cache_entry_sizes();
+        entries.sort_unstable_by_key(|(tick, _)| *tick);
+        let mut evict_tick = 0;
+        for (tick, size) in entries {
+            if usage <= budget {
+                break;
+            }
+            usage -= size;
+            evict_tick = tick;
+        }
+        log::trace!("evict cache entries used at or before tick {}", evict_tick);
+
+        let cache_memory_usage = &mut self.cache_memory_usage;
+        self.font_cache.retain(|_, (tick, font_opt)| {
+            let keep = *tick > evict_tick;
+            if !keep {
+                *cache_memory_usage -= font_entry_size(font_opt);
+            }
+            keep
+        });
+        self.font_codepoint_support_info_cache
+            .retain(|_, (tick, info)| {
+                let keep = *tick > evict_tick;
+                if !keep {
+                    *cache_memory_usage -= codepoint_support_entry_size(info);
+                }
+                keep
+            });
+        self.font_matches_cache.retain(|_, (tick, keys)| {
+            let keep = *tick > evict_tick;
+            if !keep {
+                *cache_memory_usage -= font_matches_entry_size(keys);
+            }
+            keep
+        });
+        self.monospace_ids_for_scripts_cache
+            .retain(|scripts, (tick, ids)| {
+                let keep = *tick > evict_tick;
+                if !keep {
+                    *cache_memory_usage -= monospace_ids_entry_size(scripts, ids);
+                }
+                keep
+            });
+        #[cfg(feature = "shape-run-cache")]
+        self.shape_run_cache.evict_used_before(evict_tick + 1);
     }
 
     #[cfg(feature = "std")]
@@ -383,6 +1845,66 @@ impl FontSystem {
             db.load_font_source(source);
         }
     }
+
+    /// Start loading the system fonts on a background thread, which can take up to a second.
+    ///
+    /// Create a [`FontSystem`] with only the fonts needed right away, for example with
+    /// [`FontSystem::new_with_locale_and_db`], and add the system fonts once they are loaded
+    /// with [`FontSystem::merge_system_fonts`].
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn load_system_fonts_async() -> SystemFontsLoader {
+        SystemFontsLoader {
+            handle: std::thread::spawn(|| {
+                let mut db = fontdb::Database::new();
+                Self::load_fonts(&mut db, core::iter::empty());
+                db
+            }),
+        }
+    }
+
+    /// Add the system fonts loaded by `loader` to the database, waiting for them if they are not
+    /// loaded yet, see [`SystemFontsLoader::is_finished`].
+    ///
+    /// Font matches and cached shaping are invalidated, as they may use the new fonts. Reset the
+    /// shaping of any [`crate::Buffer`] that should use them, such as one with text that was
+    /// missing a fallback font.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn merge_system_fonts(&mut self, loader: SystemFontsLoader) {
+        let system_db = match loader.handle.join() {
+            Ok(db) => db,
+            Err(_) => {
+                log::warn!("failed to load system fonts");
+                return;
+            }
+        };
+
+        let db = Arc::make_mut(&mut self.db);
+        for face in system_db.faces() {
+            db.push_face_info(face.clone());
+        }
+
+        (self.monospace_font_ids, self.per_script_monospace_font_ids) = monospace_font_ids(db);
+        self.clear_monospace_ids_cache();
+        self.clear_font_matches_cache();
+        self.range_fallback_ids.clear();
+        #[cfg(feature = "shape-run-cache")]
+        self.shape_run_cache.clear();
+    }
+}
+
+/// System fonts loading on a background thread, see [`FontSystem::load_system_fonts_async`]
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct SystemFontsLoader {
+    handle: std::thread::JoinHandle<fontdb::Database>,
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl SystemFontsLoader {
+    /// True if the system fonts are loaded, so [`FontSystem::merge_system_fonts`] does not block
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
 }
 
 /// A value borrowed together with an [`FontSystem`]
@@ -405,3 +1927,100 @@ impl<T> DerefMut for BorrowedWithFontSystem<'_, T> {
         self.inner
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remove_font_invalidates_caches() {
+        let mut db = fontdb::Database::new();
+        db.load_font_data(std::fs::read("fonts/FiraMono-Medium.ttf").expect("failed to read font"));
+        let id = db.faces().next().expect("failed to load font").id;
+        let family = db.face(id).expect("missing face").families[0].0.clone();
+        let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), db);
+        let attrs = Attrs::new().family(crate::Family::Name(&family));
+
+        assert!(font_system.get_font(id).is_some());
+        assert!(font_system
+            .get_font_supported_codepoints_in_word(id, "abc")
+            .is_some());
+        font_system.font_scripts(id);
+        assert!(font_system.is_monospace(id));
+        assert_eq!(font_system.get_font_matches(&attrs)[0].id, id);
+        font_system.cached_monospace_ids_for_scripts([*b"latn"].into_iter());
+
+        font_system.remove_font(id);
+        assert!(font_system.db().face(id).is_none());
+        assert!(!font_system.font_cache.contains_key(&id));
+        assert!(!font_system
+            .font_codepoint_support_info_cache
+            .contains_key(&id));
+        assert!(!font_system.font_scripts_cache.contains_key(&id));
+        assert!(!font_system.is_monospace(id));
+        assert!(font_system.monospace_ids_for_scripts_cache.is_empty());
+        assert!(font_system
+            .per_script_monospace_font_ids
+            .values()
+            .all(|ids| !ids.contains(&id)));
+
+        // A different font under the same family resolves freshly
+        let face_info = fontdb::FaceInfo {
+            id: fontdb::ID::dummy(),
+            source: fontdb::Source::Binary(Arc::new(
+                std::fs::read("fonts/NotoSans-Regular.ttf").expect("failed to read font"),
+            )),
+            index: 0,
+            families: alloc::vec![(family.clone(), fontdb::Language::English_UnitedStates)],
+            post_script_name: "NotoSans-Regular".into(),
+            style: fontdb::Style::Normal,
+            weight: fontdb::Weight::NORMAL,
+            stretch: fontdb::Stretch::Normal,
+            monospaced: false,
+        };
+        let new_id = font_system.db_mut().push_face_info(face_info);
+        assert_eq!(font_system.get_font_matches(&attrs)[0].id, new_id);
+        assert!(font_system
+            .get_font_supported_codepoints_in_word(new_id, "abc")
+            .is_some());
+    }
+
+    #[test]
+    fn cache_memory_usage_tracks_entries() {
+        let mut db = fontdb::Database::new();
+        for path in ["fonts/FiraMono-Medium.ttf", "fonts/NotoSans-Regular.ttf"] {
+            db.load_font_data(std::fs::read(path).expect("failed to read font"));
+        }
+        let ids: Vec<fontdb::ID> = db.faces().map(|face| face.id).collect();
+        let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), db);
+        let check = |font_system: &FontSystem| {
+            let total: usize = font_system
+                .cache_entry_sizes()
+                .iter()
+                .map(|(_, size)| size)
+                .sum();
+            assert_eq!(font_system.cache_memory_usage(), total);
+        };
+
+        let mut buffer = crate::Buffer::new(&mut font_system, crate::Metrics::new(14.0, 20.0));
+        for weight in (100..=900).step_by(100) {
+            let attrs = Attrs::new().weight(crate::Weight(weight));
+            buffer.set_text(
+                &mut font_system,
+                &alloc::format!("Line {weight} ŝ"),
+                &attrs,
+                crate::Shaping::Advanced,
+            );
+            font_system.cached_monospace_ids_for_scripts([*b"latn"].into_iter());
+            check(&font_system);
+        }
+        assert!(font_system.cache_memory_usage() > 0);
+
+        font_system.set_memory_budget(Some(font_system.cache_memory_usage() / 2));
+        check(&font_system);
+        font_system.remove_font(ids[0]);
+        check(&font_system);
+        font_system.db_mut();
+        check(&font_system);
+    }
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::woff2::{decode_woff2, WOFF2_SIGNATURE};
+
+const WOFF_SIGNATURE: u32 = 0x774F_4646;
+const WOFF_HEADER_SIZE: usize = 44;
+const WOFF_TABLE_ENTRY_SIZE: usize = 20;
+pub(super) const SFNT_HEADER_SIZE: usize = 12;
+pub(super) const SFNT_TABLE_RECORD_SIZE: usize = 16;
+
+/// Error decoding a WOFF or WOFF2 font, see [`decode_woff`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WoffError {
+    /// The data is not a WOFF or WOFF2 font
+    NotWoff,
+    /// The font is truncated or has an invalid table directory or table
+    InvalidData,
+    /// A compressed table could not be decompressed to its original size
+    Decompression,
+}
+
+impl fmt::Display for WoffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotWoff => write!(f, "not a WOFF font"),
+            Self::InvalidData => write!(f, "invalid WOFF font data"),
+            Self::Decompression => write!(f, "failed to decompress WOFF table"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WoffError {}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, WoffError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or(WoffError::InvalidData)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, WoffError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(WoffError::InvalidData)
+}
+
+/// The `sfnt` header of a font with `flavor` and `num_tables` tables
+pub(super) fn sfnt_header(flavor: u32, num_tables: u16) -> [u8; SFNT_HEADER_SIZE] {
+    // The sfnt header fields for binary search of the table records
+    let entry_selector = num_tables.checked_ilog2().unwrap_or(0);
+    let search_range = (1u32 << entry_selector) * SFNT_TABLE_RECORD_SIZE as u32;
+    let range_shift =
+        (u32::from(num_tables) * SFNT_TABLE_RECORD_SIZE as u32).saturating_sub(search_range);
+
+    let mut header = [0; SFNT_HEADER_SIZE];
+    header[..4].copy_from_slice(&flavor.to_be_bytes());
+    for (i, value) in [
+        u32::from(num_tables),
+        search_range,
+        entry_selector,
+        range_shift,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        header[4 + i * 2..6 + i * 2].copy_from_slice(&(value as u16).to_be_bytes());
+    }
+    header
+}
+
+/// Decode the WOFF or WOFF2 font `data` to the `sfnt` font data it wraps, for loading into a
+/// [`fontdb::Database`]. Compressed tables are inflated and metadata is dropped. WOFF2 fonts
+/// are decoded with [`decode_woff2`]
+///
+/// # Errors
+///
+/// Returns a [`WoffError`] if `data` is not a valid WOFF or WOFF2 font
+pub fn decode_woff(data: &[u8]) -> Result<Vec<u8>, WoffError> {
+    match read_u32(data, 0) {
+        Ok(WOFF_SIGNATURE) => {}
+        Ok(WOFF2_SIGNATURE) => return decode_woff2(data),
+        _ => return Err(WoffError::NotWoff),
+    }
+    let flavor = read_u32(data, 4)?;
+    let num_tables = read_u16(data, 12)?;
+
+    let mut sfnt = Vec::new();
+    sfnt.extend_from_slice(&sfnt_header(flavor, num_tables));
+    let mut table_offset = SFNT_HEADER_SIZE + SFNT_TABLE_RECORD_SIZE * usize::from(num_tables);
+    sfnt.resize(table_offset, 0);
+
+    for table_i in 0..usize::from(num_tables) {
+        let entry = WOFF_HEADER_SIZE + WOFF_TABLE_ENTRY_SIZE * table_i;
+        let tag = read_u32(data, entry)?;
+        let offset = read_u32(data, entry + 4)? as usize;
+        let comp_length = read_u32(data, entry + 8)? as usize;
+        let orig_length = read_u32(data, entry + 12)? as usize;
+        let checksum = read_u32(data, entry + 16)?;
+
+        let table_data = offset
+            .checked_add(comp_length)
+            .and_then(|end| data.get(offset..end))
+            .ok_or(WoffError::InvalidData)?;
+        if comp_length < orig_length {
+            let table =
+                miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(table_data, orig_length)
+                    .map_err(|_| WoffError::Decompression)?;
+            if table.len() != orig_length {
+                return Err(WoffError::Decompression);
+            }
+            sfnt.extend_from_slice(&table);
+        } else if comp_length == orig_length {
+            sfnt.extend_from_slice(table_data);
+        } else {
+            return Err(WoffError::InvalidData);
+        }
+        // Tables are aligned to four bytes
+        sfnt.resize(sfnt.len().next_multiple_of(4), 0);
+
+        let record = SFNT_HEADER_SIZE + SFNT_TABLE_RECORD_SIZE * table_i;
+        for (i, value) in [tag, checksum, table_offset as u32, orig_length as u32]
+            .into_iter()
+            .enumerate()
+        {
+            sfnt[record + i * 4..record + i * 4 + 4].copy_from_slice(&value.to_be_bytes());
+        }
+        table_offset = sfnt.len();
+    }
+
+    Ok(sfnt)
+}
@@ -0,0 +1,742 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::marker::PhantomData;
+
+use brotli_decompressor::{
+    Allocator, BrotliDecompressStream, BrotliResult, BrotliState, SliceWrapper, SliceWrapperMut,
+};
+
+use super::woff::{sfnt_header, WoffError, SFNT_HEADER_SIZE, SFNT_TABLE_RECORD_SIZE};
+
+pub(super) const WOFF2_SIGNATURE: u32 = 0x774F_4632;
+const TTC_SIGNATURE: u32 = 0x7474_6366;
+/// Largest decoded font accepted, so a crafted header cannot request a huge allocation
+const MAX_SFNT_SIZE: usize = 1 << 28;
+
+/// Tags of the table directory entries that do not store their tag, by index
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+// Flags of simple glyph points in the `glyf` table
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+// Flags of composite glyph components in the `glyf` table
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+/// A cursor reading big-endian values from WOFF2 data
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], WoffError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(WoffError::InvalidData)?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Split off the next `len` bytes as their own reader
+    fn sub(&mut self, len: usize) -> Result<Self, WoffError> {
+        self.bytes(len).map(Self::new)
+    }
+
+    fn u8(&mut self) -> Result<u8, WoffError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, WoffError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, WoffError> {
+        self.u16().map(|value| value as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, WoffError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Read a `255UInt16` variable-length integer
+    fn u255_16(&mut self) -> Result<u16, WoffError> {
+        const LOWEST_U_CODE: u16 = 253;
+        match self.u8()? {
+            253 => self.u16(),
+            254 => Ok(LOWEST_U_CODE * 2 + u16::from(self.u8()?)),
+            255 => Ok(LOWEST_U_CODE + u16::from(self.u8()?)),
+            code => Ok(code.into()),
+        }
+    }
+
+    /// Read a `UIntBase128` variable-length integer
+    fn base128(&mut self) -> Result<u32, WoffError> {
+        let mut value = 0u32;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            // No leading zeros, and no overflow
+            if (i == 0 && byte == 0x80) || value & 0xFE00_0000 != 0 {
+                return Err(WoffError::InvalidData);
+            }
+            value = (value << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(WoffError::InvalidData)
+    }
+}
+
+/// Memory for the Brotli decoder, allocated on the heap so no `std` is needed
+struct VecAllocator<T>(PhantomData<T>);
+
+struct VecSlice<T>(Box<[T]>);
+
+impl<T> Default for VecSlice<T> {
+    fn default() -> Self {
+        Self(Box::default())
+    }
+}
+
+impl<T> SliceWrapper<T> for VecSlice<T> {
+    fn slice(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T> SliceWrapperMut<T> for VecSlice<T> {
+    fn slice_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T: Clone + Default> Allocator<T> for VecAllocator<T> {
+    type AllocatedMemory = VecSlice<T>;
+
+    fn alloc_cell(&mut self, len: usize) -> VecSlice<T> {
+        VecSlice(vec![T::default(); len].into_boxed_slice())
+    }
+
+    fn free_cell(&mut self, _data: VecSlice<T>) {}
+}
+
+/// Decompress the Brotli stream `data` of exactly `len` bytes
+fn decompress(data: &[u8], len: usize) -> Result<Vec<u8>, WoffError> {
+    let mut output = vec![0; len];
+    let mut state = BrotliState::new(
+        VecAllocator(PhantomData),
+        VecAllocator(PhantomData),
+        VecAllocator(PhantomData),
+    );
+    let mut available_in = data.len();
+    let mut input_offset = 0;
+    let mut available_out = len;
+    let mut output_offset = 0;
+    let mut written = 0;
+    match BrotliDecompressStream(
+        &mut available_in,
+        &mut input_offset,
+        data,
+        &mut available_out,
+        &mut output_offset,
+        &mut output,
+        &mut written,
+        &mut state,
+    ) {
+        BrotliResult::ResultSuccess if output_offset == len => Ok(output),
+        _ => Err(WoffError::Decompression),
+    }
+}
+
+/// A table of the WOFF2 table directory
+struct TableEntry {
+    tag: [u8; 4],
+    /// True if the table is stored with the transform of its transform version, rather than as
+    /// is
+    transformed: bool,
+    /// Offset and length of the stored table in the decompressed data
+    offset: usize,
+    length: usize,
+}
+
+/// The tables of a font in a WOFF2 font or collection
+struct FontEntry {
+    flavor: u32,
+    tables: Vec<usize>,
+}
+
+impl FontEntry {
+    fn table(&self, entries: &[TableEntry], tag: &[u8; 4]) -> Option<usize> {
+        self.tables
+            .iter()
+            .copied()
+            .find(|&table_i| &entries[table_i].tag == tag)
+    }
+}
+
+/// The `glyf` and `loca` tables reconstructed from a transformed `glyf` table
+struct Glyf {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    /// Minimum x of each glyph, for the `hmtx` transform
+    x_mins: Vec<i16>,
+}
+
+/// Read the delta of a point of a simple glyph with `flag` from the glyph stream
+fn read_triplet(flag: u8, glyph_stream: &mut Reader) -> Result<(i32, i32), WoffError> {
+    fn with_sign(flag: u8, value: i32) -> i32 {
+        if flag & 1 != 0 {
+            value
+        } else {
+            -value
+        }
+    }
+
+    let f = i32::from(flag);
+    Ok(match flag {
+        0..=9 => {
+            let b0 = i32::from(glyph_stream.u8()?);
+            (0, with_sign(flag, ((f & 14) << 7) + b0))
+        }
+        10..=19 => {
+            let b0 = i32::from(glyph_stream.u8()?);
+            (with_sign(flag, (((f - 10) & 14) << 7) + b0), 0)
+        }
+        20..=83 => {
+            let b0 = f - 20;
+            let b1 = i32::from(glyph_stream.u8()?);
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+            )
+        }
+        84..=119 => {
+            let b0 = f - 84;
+            let bytes = glyph_stream.bytes(2)?;
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + i32::from(bytes[0])),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + i32::from(bytes[1])),
+            )
+        }
+        120..=123 => {
+            let bytes = glyph_stream.bytes(3)?;
+            let (b0, b1, b2) = (
+                i32::from(bytes[0]),
+                i32::from(bytes[1]),
+                i32::from(bytes[2]),
+            );
+            (
+                with_sign(flag, (b0 << 4) + (b1 >> 4)),
+                with_sign(flag >> 1, ((b1 & 0x0F) << 8) + b2),
+            )
+        }
+        _ => {
+            let bytes = glyph_stream.bytes(4)?;
+            (
+                with_sign(flag, i32::from(u16::from_be_bytes([bytes[0], bytes[1]]))),
+                with_sign(
+                    flag >> 1,
+                    i32::from(u16::from_be_bytes([bytes[2], bytes[3]])),
+                ),
+            )
+        }
+    })
+}
+
+/// Write the flags and coordinates of the points of a simple glyph
+fn write_points(
+    glyf: &mut Vec<u8>,
+    points: &[(i32, i32, bool)],
+    overlap: bool,
+) -> Result<(), WoffError> {
+    let mut flags = Vec::with_capacity(points.len());
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let (mut last_x, mut last_y) = (0, 0);
+    for (point_i, &(x, y, on_curve)) in points.iter().enumerate() {
+        let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+        if overlap && point_i == 0 {
+            flag |= OVERLAP_SIMPLE;
+        }
+        for (delta, coordinates, short, same_or_positive) in [
+            (x - last_x, &mut xs, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE),
+            (y - last_y, &mut ys, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE),
+        ] {
+            if delta == 0 {
+                flag |= same_or_positive;
+            } else if delta.unsigned_abs() <= 0xFF {
+                flag |= short;
+                if delta > 0 {
+                    flag |= same_or_positive;
+                }
+                coordinates.push(delta.unsigned_abs() as u8);
+            } else {
+                let delta = i16::try_from(delta).map_err(|_| WoffError::InvalidData)?;
+                coordinates.extend_from_slice(&delta.to_be_bytes());
+            }
+        }
+        flags.push(flag);
+        (last_x, last_y) = (x, y);
+    }
+    // Runs of the same flag are written once with a repeat count
+    let mut flag_i = 0;
+    while let Some(&flag) = flags.get(flag_i) {
+        let repeats = flags[flag_i + 1..]
+            .iter()
+            .take(0xFF)
+            .take_while(|&&next| next == flag)
+            .count();
+        if repeats > 0 {
+            glyf.extend_from_slice(&[flag | REPEAT_FLAG, repeats as u8]);
+        } else {
+            glyf.push(flag);
+        }
+        flag_i += 1 + repeats;
+    }
+    glyf.extend_from_slice(&xs);
+    glyf.extend_from_slice(&ys);
+    Ok(())
+}
+
+/// Reconstruct the `glyf` and `loca` tables from the transformed `glyf` table `data`
+fn reconstruct_glyf(data: &[u8]) -> Result<Glyf, WoffError> {
+    let mut header = Reader::new(data);
+    header.u16()?; // Reserved
+    let option_flags = header.u16()?;
+    let num_glyphs = usize::from(header.u16()?);
+    let index_format = header.u16()?;
+    let mut stream_lens = [0; 7];
+    for len in &mut stream_lens {
+        *len = header.u32()? as usize;
+    }
+    let [n_contour_len, n_points_len, flag_len, glyph_len, composite_len, bbox_len, instruction_len] =
+        stream_lens;
+    let mut n_contour_stream = header.sub(n_contour_len)?;
+    let mut n_points_stream = header.sub(n_points_len)?;
+    let mut flag_stream = header.sub(flag_len)?;
+    let mut glyph_stream = header.sub(glyph_len)?;
+    let mut composite_stream = header.sub(composite_len)?;
+    let mut bbox_stream = header.sub(bbox_len)?;
+    let mut instruction_stream = header.sub(instruction_len)?;
+    let bitmap_len = num_glyphs.div_ceil(32) * 4;
+    let bbox_bitmap = bbox_stream.bytes(bitmap_len)?;
+    let overlap_bitmap = if option_flags & 1 != 0 {
+        Some(header.bytes(num_glyphs.div_ceil(8))?)
+    } else {
+        None
+    };
+    let bit = |bitmap: &[u8], glyph_i: usize| bitmap[glyph_i >> 3] & (0x80 >> (glyph_i & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+    let mut points = Vec::new();
+    for glyph_i in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let has_bbox = bit(bbox_bitmap, glyph_i);
+        let n_contours = n_contour_stream.i16()?;
+        if n_contours == 0 {
+            // Empty glyph
+            if has_bbox {
+                return Err(WoffError::InvalidData);
+            }
+            x_mins.push(0);
+            continue;
+        }
+
+        if n_contours == -1 {
+            // Composite glyph, copying its components
+            if !has_bbox {
+                return Err(WoffError::InvalidData);
+            }
+            let bbox = bbox_stream.bytes(8)?;
+            let components_start = composite_stream.offset;
+            let mut have_instructions = false;
+            loop {
+                let flags = composite_stream.u16()?;
+                have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+                let args_len = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                let scale_len = if flags & WE_HAVE_A_SCALE != 0 {
+                    2
+                } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    4
+                } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    8
+                } else {
+                    0
+                };
+                // Glyph index, arguments and scale
+                composite_stream.bytes(2 + args_len + scale_len)?;
+                if flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            glyf.extend_from_slice(&n_contours.to_be_bytes());
+            glyf.extend_from_slice(bbox);
+            glyf.extend_from_slice(
+                &composite_stream.data[components_start..composite_stream.offset],
+            );
+            if have_instructions {
+                let len = glyph_stream.u255_16()?;
+                glyf.extend_from_slice(&len.to_be_bytes());
+                glyf.extend_from_slice(instruction_stream.bytes(len.into())?);
+            }
+            x_mins.push(i16::from_be_bytes([bbox[0], bbox[1]]));
+        } else if n_contours > 0 {
+            // Simple glyph, decoding its points
+            let mut end_points = Vec::with_capacity(n_contours as usize);
+            let mut num_points = 0u32;
+            for _ in 0..n_contours {
+                num_points += u32::from(n_points_stream.u255_16()?);
+                let end_point = num_points
+                    .checked_sub(1)
+                    .and_then(|end_point| u16::try_from(end_point).ok())
+                    .ok_or(WoffError::InvalidData)?;
+                end_points.push(end_point);
+            }
+            points.clear();
+            let (mut x, mut y) = (0i32, 0i32);
+            for _ in 0..num_points {
+                let flag = flag_stream.u8()?;
+                let (dx, dy) = read_triplet(flag & 0x7F, &mut glyph_stream)?;
+                x += dx;
+                y += dy;
+                points.push((x, y, flag & 0x80 == 0));
+            }
+            let instructions_len = glyph_stream.u255_16()?;
+            let instructions = instruction_stream.bytes(instructions_len.into())?;
+
+            let bbox = if has_bbox {
+                let bbox = bbox_stream.bytes(8)?;
+                [0, 2, 4, 6].map(|i| i16::from_be_bytes([bbox[i], bbox[i + 1]]))
+            } else {
+                let (mut x_min, mut y_min) = (i32::MAX, i32::MAX);
+                let (mut x_max, mut y_max) = (i32::MIN, i32::MIN);
+                for &(x, y, _) in &points {
+                    (x_min, y_min) = (x_min.min(x), y_min.min(y));
+                    (x_max, y_max) = (x_max.max(x), y_max.max(y));
+                }
+                let bbox = [x_min, y_min, x_max, y_max].map(i16::try_from);
+                if bbox.iter().any(Result::is_err) {
+                    return Err(WoffError::InvalidData);
+                }
+                bbox.map(|value| value.unwrap_or_default())
+            };
+
+            glyf.extend_from_slice(&n_contours.to_be_bytes());
+            for value in bbox {
+                glyf.extend_from_slice(&value.to_be_bytes());
+            }
+            for end_point in end_points {
+                glyf.extend_from_slice(&end_point.to_be_bytes());
+            }
+            glyf.extend_from_slice(&instructions_len.to_be_bytes());
+            glyf.extend_from_slice(instructions);
+            let overlap = overlap_bitmap.is_some_and(|bitmap| bit(bitmap, glyph_i));
+            write_points(&mut glyf, &points, overlap)?;
+            x_mins.push(bbox[0]);
+        } else {
+            return Err(WoffError::InvalidData);
+        }
+
+        // Glyphs are aligned to four bytes
+        glyf.resize(glyf.len().next_multiple_of(4), 0);
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::new();
+    for offset in offsets {
+        if index_format == 0 {
+            let offset = u16::try_from(offset / 2).map_err(|_| WoffError::InvalidData)?;
+            loca.extend_from_slice(&offset.to_be_bytes());
+        } else {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+    }
+
+    Ok(Glyf { glyf, loca, x_mins })
+}
+
+/// Reconstruct the `hmtx` table from the transformed `hmtx` table `data`, with the left side
+/// bearings left out of it set to the minimum x of their glyph
+fn reconstruct_hmtx(
+    data: &[u8],
+    num_h_metrics: usize,
+    x_mins: &[i16],
+) -> Result<Vec<u8>, WoffError> {
+    if num_h_metrics == 0 || num_h_metrics > x_mins.len() {
+        return Err(WoffError::InvalidData);
+    }
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let mut advances = Vec::with_capacity(num_h_metrics);
+    for _ in 0..num_h_metrics {
+        advances.push(reader.u16()?);
+    }
+    let mut lsbs = Vec::with_capacity(x_mins.len());
+    for (glyph_i, &x_min) in x_mins.iter().enumerate() {
+        // Proportional and monospaced left side bearings are left out separately
+        let absent = if glyph_i < num_h_metrics {
+            flags & 1 != 0
+        } else {
+            flags & 2 != 0
+        };
+        lsbs.push(if absent { x_min } else { reader.i16()? });
+    }
+
+    let mut hmtx = Vec::with_capacity(num_h_metrics * 2 + x_mins.len() * 2);
+    for (glyph_i, lsb) in lsbs.into_iter().enumerate() {
+        if let Some(advance) = advances.get(glyph_i) {
+            hmtx.extend_from_slice(&advance.to_be_bytes());
+        }
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+    Ok(hmtx)
+}
+
+/// Sum of the big-endian `u32` words of `data`, zero padded
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Decode the WOFF2 font or collection `data` to the `sfnt` font data it wraps, for loading
+/// into a [`fontdb::Database`]. The tables are decompressed and the transformed `glyf`, `loca`
+/// and `hmtx` tables are reconstructed. Metadata is dropped
+///
+/// # Errors
+///
+/// Returns a [`WoffError`] if `data` is not a valid WOFF2 font
+pub fn decode_woff2(data: &[u8]) -> Result<Vec<u8>, WoffError> {
+    let mut header = Reader::new(data);
+    if header.u32() != Ok(WOFF2_SIGNATURE) {
+        return Err(WoffError::NotWoff);
+    }
+    let flavor = header.u32()?;
+    header.u32()?; // Length
+    let num_tables = usize::from(header.u16()?);
+    header.u16()?; // Reserved
+    let total_sfnt_size = header.u32()? as usize;
+    let compressed_len = header.u32()? as usize;
+    // Versions, and offsets and lengths of metadata and private data
+    header.bytes(24)?;
+
+    let mut entries = Vec::with_capacity(num_tables);
+    let mut stored_len = 0usize;
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match KNOWN_TAGS.get(usize::from(flags & 0x3F)) {
+            Some(tag) => **tag,
+            None => header.u32()?.to_be_bytes(),
+        };
+        let version = flags >> 6;
+        let orig_length = header.base128()?;
+        // The null transform is version 3 for glyf and loca, and version 0 for other tables
+        let transformed = match &tag {
+            b"glyf" | b"loca" => version == 0,
+            _ => version != 0,
+        };
+        let length = if transformed {
+            if version != 0 && !(&tag == b"hmtx" && version == 1) {
+                return Err(WoffError::InvalidData);
+            }
+            header.base128()?
+        } else {
+            orig_length
+        } as usize;
+        entries.push(TableEntry {
+            tag,
+            transformed,
+            offset: stored_len,
+            length,
+        });
+        stored_len = stored_len
+            .checked_add(length)
+            .ok_or(WoffError::InvalidData)?;
+    }
+
+    // The stored tables are at most as large as the decoded font, which is allocated up front
+    if stored_len > total_sfnt_size || stored_len > MAX_SFNT_SIZE {
+        return Err(WoffError::InvalidData);
+    }
+
+    let (collection_version, fonts) = if flavor == TTC_SIGNATURE {
+        let version = header.u32()?;
+        let num_fonts = header.u255_16()?;
+        let mut fonts = Vec::with_capacity(num_fonts.into());
+        for _ in 0..num_fonts {
+            let num_font_tables = header.u255_16()?;
+            let flavor = header.u32()?;
+            let mut tables = Vec::with_capacity(num_font_tables.into());
+            for _ in 0..num_font_tables {
+                let table_i = usize::from(header.u255_16()?);
+                if table_i >= entries.len() {
+                    return Err(WoffError::InvalidData);
+                }
+                tables.push(table_i);
+            }
+            fonts.push(FontEntry { flavor, tables });
+        }
+        (Some(version), fonts)
+    } else {
+        let tables = (0..entries.len()).collect();
+        (None, vec![FontEntry { flavor, tables }])
+    };
+
+    let stored = decompress(header.bytes(compressed_len)?, stored_len)?;
+    let mut tables: Vec<Vec<u8>> = entries
+        .iter()
+        .map(|entry| stored[entry.offset..entry.offset + entry.length].to_vec())
+        .collect();
+
+    // Reconstruct the transformed tables of each font, which may be shared in a collection
+    let mut reconstructed = vec![false; entries.len()];
+    for font in &fonts {
+        let glyf_i = font.table(&entries, b"glyf");
+        let loca_i = font.table(&entries, b"loca");
+        let mut x_mins = None;
+        if let (Some(glyf_i), Some(loca_i)) = (glyf_i, loca_i) {
+            if entries[glyf_i].transformed != entries[loca_i].transformed {
+                return Err(WoffError::InvalidData);
+            }
+            if entries[glyf_i].transformed {
+                let stored = &stored[entries[glyf_i].offset..][..entries[glyf_i].length];
+                let glyf = reconstruct_glyf(stored)?;
+                if !reconstructed[glyf_i] {
+                    tables[glyf_i] = glyf.glyf;
+                    tables[loca_i] = glyf.loca;
+                    reconstructed[glyf_i] = true;
+                    reconstructed[loca_i] = true;
+                }
+                x_mins = Some(glyf.x_mins);
+            }
+        } else if glyf_i
+            .or(loca_i)
+            .is_some_and(|table_i| entries[table_i].transformed)
+        {
+            return Err(WoffError::InvalidData);
+        }
+
+        if let Some(hmtx_i) = font.table(&entries, b"hmtx") {
+            if entries[hmtx_i].transformed && !reconstructed[hmtx_i] {
+                // The transform needs the glyph bounding boxes
+                let x_mins = x_mins.as_deref().ok_or(WoffError::InvalidData)?;
+                let hhea_i = font
+                    .table(&entries, b"hhea")
+                    .ok_or(WoffError::InvalidData)?;
+                let num_h_metrics = Reader::new(tables[hhea_i].get(34..).unwrap_or_default())
+                    .u16()?
+                    .into();
+                tables[hmtx_i] = reconstruct_hmtx(&tables[hmtx_i], num_h_metrics, x_mins)?;
+                reconstructed[hmtx_i] = true;
+            }
+        }
+    }
+
+    // The head table checksum is computed without the checksum adjustment of the whole font
+    for (entry, table) in entries.iter().zip(&mut tables) {
+        if &entry.tag == b"head" {
+            if let Some(adjustment) = table.get_mut(8..12) {
+                adjustment.fill(0);
+            }
+        }
+    }
+    let head_i = entries.iter().position(|entry| &entry.tag == b"head");
+
+    // The collection header, then the table directory of each font, then the tables
+    let mut directory_offsets = Vec::with_capacity(fonts.len());
+    let mut offset = match collection_version {
+        Some(version) => 12 + 4 * fonts.len() + if version >= 0x0002_0000 { 12 } else { 0 },
+        None => 0,
+    };
+    for font in &fonts {
+        directory_offsets.push(offset);
+        offset += SFNT_HEADER_SIZE + SFNT_TABLE_RECORD_SIZE * font.tables.len();
+    }
+    let mut sfnt = vec![0; offset];
+    let mut table_offsets = Vec::with_capacity(tables.len());
+    for table in &tables {
+        table_offsets.push(sfnt.len() as u32);
+        sfnt.extend_from_slice(table);
+        // Tables are aligned to four bytes
+        sfnt.resize(sfnt.len().next_multiple_of(4), 0);
+    }
+
+    if let Some(version) = collection_version {
+        let mut collection_header = Vec::with_capacity(directory_offsets[0]);
+        for value in [TTC_SIGNATURE, version, fonts.len() as u32] {
+            collection_header.extend_from_slice(&value.to_be_bytes());
+        }
+        for &directory_offset in &directory_offsets {
+            collection_header.extend_from_slice(&(directory_offset as u32).to_be_bytes());
+        }
+        sfnt[..collection_header.len()].copy_from_slice(&collection_header);
+    }
+    for (font, &directory_offset) in fonts.iter().zip(&directory_offsets) {
+        let num_tables = u16::try_from(font.tables.len()).map_err(|_| WoffError::InvalidData)?;
+        sfnt[directory_offset..directory_offset + SFNT_HEADER_SIZE]
+            .copy_from_slice(&sfnt_header(font.flavor, num_tables));
+
+        // Table records are sorted by tag
+        let mut font_tables = font.tables.clone();
+        font_tables.sort_by_key(|&table_i| entries[table_i].tag);
+        for (record_i, &table_i) in font_tables.iter().enumerate() {
+            let record = directory_offset + SFNT_HEADER_SIZE + SFNT_TABLE_RECORD_SIZE * record_i;
+            let table = &tables[table_i];
+            let mut values = [0; SFNT_TABLE_RECORD_SIZE];
+            values[..4].copy_from_slice(&entries[table_i].tag);
+            values[4..8].copy_from_slice(&checksum(table).to_be_bytes());
+            values[8..12].copy_from_slice(&table_offsets[table_i].to_be_bytes());
+            values[12..].copy_from_slice(&(table.len() as u32).to_be_bytes());
+            sfnt[record..record + SFNT_TABLE_RECORD_SIZE].copy_from_slice(&values);
+        }
+    }
+
+    // The checksum adjustment makes the checksum of a single font a fixed value
+    if let (None, Some(head_i)) = (collection_version, head_i) {
+        let adjustment_offset = table_offsets[head_i] as usize + 8;
+        if tables[head_i].len() >= 12 {
+            let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&sfnt));
+            sfnt[adjustment_offset..adjustment_offset + 4]
+                .copy_from_slice(&adjustment.to_be_bytes());
+        }
+    }
+
+    Ok(sfnt)
+}
@@ -5,7 +5,7 @@ use core::fmt::Display;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-use crate::{math, CacheKey, CacheKeyFlags, Color};
+use crate::{math, CacheKey, CacheKeyFlags, Color, SubpixelBin};
 
 /// A laid out glyph
 #[derive(Clone, Debug)]
@@ -50,12 +50,24 @@ pub struct LayoutGlyph {
     pub y_offset: f32,
     /// Optional color override
     pub color_opt: Option<Color>,
+    /// Opacity from `Attrs`, applied to the color by [`Self::color`]
+    pub opacity: f32,
     /// Metadata from `Attrs`
     pub metadata: usize,
     /// [`CacheKeyFlags`]
     pub cache_key_flags: CacheKeyFlags,
+    /// True if the text cannot be split at the start of this glyph's cluster without shaping
+    /// both sides again, see [`crate::ShapeGlyph::unsafe_to_break`]
+    pub unsafe_to_break: bool,
+    /// ISO 15924 tag of the script the glyph was shaped as, see [`crate::ShapeGlyph::script`]
+    pub script: [u8; 4],
+    /// Zero-advance glyphs, such as combining marks, folded into this glyph when
+    /// [`crate::Buffer::set_emit_zero_advance_glyphs`] is disabled. They keep their own position
+    /// and are drawn together with this glyph
+    pub marks: Vec<LayoutGlyph>,
 }
 
+/// A glyph positioned on the pixel grid, see [`LayoutGlyph::physical`]
 #[derive(Clone, Debug)]
 pub struct PhysicalGlyph {
     /// Cache key, see [`CacheKey`]
@@ -64,9 +76,15 @@ pub struct PhysicalGlyph {
     pub x: i32,
     /// Integer component of Y offset in line
     pub y: i32,
+    /// Binning of the fractional component of the X offset, the same as the
+    /// [`CacheKey::x_bin`] the glyph is rasterized with. The Y offset is hinted to whole pixels
+    pub subpixel: SubpixelBin,
 }
 
 impl LayoutGlyph {
+    /// Convert to a [`PhysicalGlyph`] on the pixel grid, with the layout moved by `offset` and
+    /// scaled by `scale`. The integer position and subpixel bin are the ones the glyph's
+    /// [`CacheKey`] is rasterized with
     pub fn physical(&self, offset: (f32, f32), scale: f32) -> PhysicalGlyph {
         let x_offset = self.font_size * self.x_offset;
         let y_offset = self.font_size * self.y_offset;
@@ -82,7 +100,34 @@ impl LayoutGlyph {
             self.cache_key_flags,
         );
 
-        PhysicalGlyph { cache_key, x, y }
+        PhysicalGlyph {
+            cache_key,
+            x,
+            y,
+            subpixel: cache_key.x_bin,
+        }
+    }
+
+    /// Get the color of the span this glyph was shaped from, or `default` if it has none. Unlike
+    /// [`Self::color`], [`Self::opacity`] is not applied
+    pub fn color_or(&self, default: Color) -> Color {
+        self.color_opt.unwrap_or(default)
+    }
+
+    /// Get the color to draw this glyph with, using `default` if there is no color override,
+    /// with its alpha multiplied by [`Self::opacity`]
+    pub fn color(&self, default: Color) -> Color {
+        let color = self.color_or(default);
+        if self.opacity >= 1.0 {
+            return color;
+        }
+        let a = math::roundf(f32::from(color.a()) * self.opacity.max(0.0)) as u8;
+        Color::rgba(color.r(), color.g(), color.b(), a)
+    }
+
+    /// Iterate over this glyph followed by its folded [`Self::marks`], in drawing order
+    pub fn with_marks(&self) -> impl Iterator<Item = &LayoutGlyph> {
+        core::iter::once(self).chain(self.marks.iter())
     }
 }
 
@@ -112,6 +157,9 @@ pub enum Wrap {
     Word,
     /// Wraps at the word level, or fallback to glyph level if a word can't fit on a line by itself
     WordOrGlyph,
+    /// Wraps at the word level to the same number of lines as [`Wrap::Word`], at the narrowest
+    /// width that fits them, so that lines have similar lengths. Useful for headings and captions
+    Balanced,
 }
 
 impl Display for Wrap {
@@ -121,6 +169,42 @@ impl Display for Wrap {
             Self::Word => write!(f, "Word Wrap"),
             Self::WordOrGlyph => write!(f, "Word Wrap or Character"),
             Self::Glyph => write!(f, "Character"),
+            Self::Balanced => write!(f, "Balanced"),
+        }
+    }
+}
+
+/// Handling of words wider than the wrap width with [`Wrap::Word`], mirroring CSS
+/// `overflow-wrap`
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum OverflowWrap {
+    /// Words are never broken and overflow the wrap width
+    #[default]
+    Normal,
+    /// Words are broken at the glyph level only if they do not fit on a line by themselves, like
+    /// [`Wrap::WordOrGlyph`]
+    BreakWord,
+    /// Words are broken at any glyph, filling each line greedily, like [`Wrap::Glyph`]
+    Anywhere,
+}
+
+impl OverflowWrap {
+    /// Get the [`Wrap`] used for laying out lines with `wrap`. Only [`Wrap::Word`] is affected
+    pub fn apply(&self, wrap: Wrap) -> Wrap {
+        match (wrap, self) {
+            (Wrap::Word, Self::BreakWord) => Wrap::WordOrGlyph,
+            (Wrap::Word, Self::Anywhere) => Wrap::Glyph,
+            _ => wrap,
+        }
+    }
+}
+
+impl Display for OverflowWrap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Normal => write!(f, "Normal"),
+            Self::BreakWord => write!(f, "Break Word"),
+            Self::Anywhere => write!(f, "Anywhere"),
         }
     }
 }
@@ -132,6 +216,9 @@ pub enum Align {
     Right,
     Center,
     Justified,
+    /// Align to the start edge of the line direction: left for LTR and right for RTL lines
+    Start,
+    /// Align to the end edge of the line direction: right for LTR and left for RTL lines
     End,
 }
 
@@ -142,7 +229,200 @@ impl Display for Align {
             Self::Right => write!(f, "Right"),
             Self::Center => write!(f, "Center"),
             Self::Justified => write!(f, "Justified"),
+            Self::Start => write!(f, "Start"),
             Self::End => write!(f, "End"),
         }
     }
 }
+
+/// Vertical alignment of a block of text within a box
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Center,
+    Bottom,
+}
+
+impl Display for VerticalAlign {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Top => write!(f, "Top"),
+            Self::Center => write!(f, "Center"),
+            Self::Bottom => write!(f, "Bottom"),
+        }
+    }
+}
+
+/// Dominant baseline that glyphs from different fonts are aligned on
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum Baseline {
+    /// Align on the alphabetic baseline, used by Latin, Greek, Cyrillic and most other scripts
+    #[default]
+    Alphabetic,
+    /// Align on the bottom of the ideographic em box, used by CJK scripts
+    Ideographic,
+    /// Align on the hanging baseline, used by Devanagari, Bengali, Tibetan and similar scripts
+    Hanging,
+    /// Align on the center of the ideographic em box
+    Central,
+}
+
+impl Display for Baseline {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Alphabetic => write!(f, "Alphabetic"),
+            Self::Ideographic => write!(f, "Ideographic"),
+            Self::Hanging => write!(f, "Hanging"),
+            Self::Central => write!(f, "Central"),
+        }
+    }
+}
+
+/// Options for shaping and laying out a line, kept by a [`crate::Buffer`] for all of its lines.
+/// See [`crate::BufferLine::layout_with_options`]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LayoutOptions {
+    /// Split shaping runs at every attribute span boundary, see
+    /// [`crate::Buffer::set_preserve_span_boundaries`]
+    pub preserve_span_boundaries: bool,
+    /// Dominant baseline that glyphs from different fonts are aligned on, see
+    /// [`crate::Buffer::set_baseline`]
+    pub baseline: Baseline,
+    /// Emit zero-advance glyphs as separate glyphs, see
+    /// [`crate::Buffer::set_emit_zero_advance_glyphs`]
+    pub emit_zero_advance_glyphs: bool,
+    /// Snap glyph X positions to a multiple of this step, see
+    /// [`crate::Buffer::set_position_quantization`]
+    pub position_quantization: Option<f32>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            preserve_span_boundaries: false,
+            baseline: Baseline::default(),
+            emit_zero_advance_glyphs: true,
+            position_quantization: None,
+        }
+    }
+}
+
+/// Source of the line height of empty lines, see [`crate::Buffer::set_empty_line_metrics`]
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum EmptyLineMetrics {
+    /// Use the metrics of the attributes of the empty line, or the buffer [`crate::Metrics`] if
+    /// they have none
+    #[default]
+    Attrs,
+    /// Use the buffer [`crate::Metrics`]
+    Buffer,
+    /// Use the line height of the last visual line of the previous line, which follows its
+    /// largest font
+    PreviousLine,
+}
+
+impl Display for EmptyLineMetrics {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Attrs => write!(f, "Attrs"),
+            Self::Buffer => write!(f, "Buffer"),
+            Self::PreviousLine => write!(f, "Previous Line"),
+        }
+    }
+}
+
+/// Where the leading of a line, the line height not taken by its glyphs, is placed, see
+/// [`crate::Buffer::set_leading_distribution`]
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum LeadingDistribution {
+    /// Place half of the leading above the glyphs and half below, centering them in the line
+    #[default]
+    Proportional,
+    /// Place all of the leading below the glyphs, so the first baseline is at the ascent of the
+    /// first line, for example to align text to a baseline grid
+    BelowOnly,
+    /// Place all of the leading above the glyphs
+    AboveOnly,
+}
+
+impl LeadingDistribution {
+    /// Get the part of `leading` placed above the glyphs
+    pub(crate) fn above(self, leading: f32) -> f32 {
+        match self {
+            Self::Proportional => leading / 2.0,
+            Self::BelowOnly => 0.0,
+            Self::AboveOnly => leading,
+        }
+    }
+}
+
+impl Display for LeadingDistribution {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Proportional => write!(f, "Proportional"),
+            Self::BelowOnly => write!(f, "Below Only"),
+            Self::AboveOnly => write!(f, "Above Only"),
+        }
+    }
+}
+
+/// A text decoration line drawn along the baseline, see [`crate::LayoutRun::decorations`]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum DecorationLine {
+    Underline,
+    Strikethrough,
+}
+
+impl Display for DecorationLine {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Underline => write!(f, "Underline"),
+            Self::Strikethrough => write!(f, "Strikethrough"),
+        }
+    }
+}
+
+/// How decoration lines are positioned across glyphs from different fonts, see
+/// [`crate::LayoutRun::decorations`]
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum DecorationMode {
+    /// Use the position and thickness of the font of each glyph
+    #[default]
+    PerFont,
+    /// Use the lowest position and largest thickness of all fonts, for a continuous line
+    Unified,
+}
+
+impl Display for DecorationMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::PerFont => write!(f, "Per Font"),
+            Self::Unified => write!(f, "Unified"),
+        }
+    }
+}
+
+/// Extent of glyphs used for the bounds of laid out text, see [`crate::Buffer::bounds`]
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum BoundsMode {
+    /// Use the advance boxes of glyphs and the height of lines, ignoring whitespace at the end of
+    /// lines
+    #[default]
+    Advance,
+    /// Use the advance boxes of glyphs and the height of lines, including whitespace at the end
+    /// of lines
+    AdvanceWithTrailingWhitespace,
+    /// Use the bounding boxes of glyph outlines
+    Ink,
+}
+
+impl Display for BoundsMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Advance => write!(f, "Advance"),
+            Self::AdvanceWithTrailingWhitespace => write!(f, "Advance With Trailing Whitespace"),
+            Self::Ink => write!(f, "Ink"),
+        }
+    }
+}
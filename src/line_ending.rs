@@ -27,6 +27,45 @@ impl LineEnding {
             Self::None => "",
         }
     }
+
+    /// Get the line ending that `text` starts with, preferring two character endings
+    pub(crate) fn from_prefix(text: &str) -> Self {
+        if text.starts_with("\r\n") {
+            Self::CrLf
+        } else if text.starts_with("\n\r") {
+            Self::LfCr
+        } else if text.starts_with('\n') {
+            Self::Lf
+        } else if text.starts_with('\r') {
+            Self::Cr
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Policy for the line endings of text set on a [`crate::Buffer`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LineEndingPolicy {
+    /// Keep the original line ending of every line, so the text can be read back unchanged
+    #[default]
+    Auto,
+    /// Normalize every line ending to [`LineEnding::Lf`]
+    Lf,
+    /// Normalize every line ending to [`LineEnding::CrLf`]
+    CrLf,
+}
+
+impl LineEndingPolicy {
+    /// Apply the policy to the original `ending` of a line. Lines without an ending keep
+    /// [`LineEnding::None`]
+    pub fn apply(&self, ending: LineEnding) -> LineEnding {
+        match (self, ending) {
+            (_, LineEnding::None) | (Self::Auto, _) => ending,
+            (Self::Lf, _) => LineEnding::Lf,
+            (Self::CrLf, _) => LineEnding::CrLf,
+        }
+    }
 }
 
 /// Iterator over lines terminated by [`LineEnding`]
@@ -56,19 +95,7 @@ impl Iterator for LineIter<'_> {
             Some(i) => {
                 let end = start + i;
                 self.start = end;
-                let after = &self.string[end..];
-                let ending = if after.starts_with("\r\n") {
-                    LineEnding::CrLf
-                } else if after.starts_with("\n\r") {
-                    LineEnding::LfCr
-                } else if after.starts_with("\n") {
-                    LineEnding::Lf
-                } else if after.starts_with("\r") {
-                    LineEnding::Cr
-                } else {
-                    //TODO: this should not be possible
-                    LineEnding::None
-                };
+                let ending = LineEnding::from_prefix(&self.string[end..]);
                 self.start += ending.as_str().len();
                 Some((start..end, ending))
             }
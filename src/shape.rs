@@ -12,11 +12,21 @@ use unicode_script::{Script, UnicodeScript};
 use unicode_segmentation::UnicodeSegmentation;
 
 use crate::fallback::FontFallbackIter;
+#[cfg(feature = "swash")]
+use crate::Ligatures;
 use crate::{
-    math, Align, AttrsList, CacheKeyFlags, Color, Font, FontSystem, LayoutGlyph, LayoutLine,
-    Metrics, Wrap,
+    math, Align, ArabicForm, Attrs, AttrsList, CacheKeyFlags, Color, Feature, FeatureTag, Font,
+    FontSystem, LayoutGlyph, LayoutLine, LayoutOptions, MarkFallback, Metrics, ShapingPlan,
+    SpaceAdvanceSource, Wrap,
 };
 
+/// Width in pixels that [`Wrap::Balanced`] searches the wrap width to
+const BALANCED_WRAP_PRECISION: f32 = 0.5;
+
+/// Features disabled for [`ArabicForm::Isolated`]
+const ISOLATED_FORM_DISABLED_FEATURES: [&[u8; 4]; 6] =
+    [b"init", b"medi", b"fina", b"med2", b"fin2", b"fin3"];
+
 /// The shaping strategy of some text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Shaping {
@@ -35,6 +45,78 @@ pub enum Shaping {
     /// script, the font used needs it, and/or multiple fonts in your system
     /// may be needed to display all of the glyphs.
     Advanced,
+    /// Basic shaping where it displays correctly, and advanced shaping elsewhere, decided for
+    /// each run.
+    ///
+    /// Runs are shaped with [`Shaping::Advanced`] if they are right-to-left, if the font lacks
+    /// any of their characters, if their attributes set font features, ligatures or an Arabic
+    /// form other than the defaults, or if they contain combining marks, zero width joiners or
+    /// characters of these scripts: Arabic, Syriac, Thaana, N'Ko, Hebrew, Mongolian, the
+    /// Brahmic scripts of South and Southeast Asia (such as Devanagari, Bengali, Tamil, Sinhala,
+    /// Thai, Lao, Tibetan, Myanmar and Khmer) and Hangul. Latin and CJK text otherwise uses the
+    /// cheaper [`Shaping::Basic`], without kerning or ligatures.
+    #[cfg(feature = "swash")]
+    Auto,
+}
+
+/// Scripts that [`Shaping::Auto`] always shapes with [`Shaping::Advanced`]. Runs of other scripts
+/// are also shaped with it when their attributes set shaping features, see
+/// [`requests_shaping_features`]
+#[cfg(feature = "swash")]
+const COMPLEX_SCRIPTS: &[Script] = &[
+    Script::Arabic,
+    Script::Syriac,
+    Script::Thaana,
+    Script::Nko,
+    Script::Hebrew,
+    Script::Mongolian,
+    Script::Devanagari,
+    Script::Bengali,
+    Script::Gurmukhi,
+    Script::Gujarati,
+    Script::Oriya,
+    Script::Tamil,
+    Script::Telugu,
+    Script::Kannada,
+    Script::Malayalam,
+    Script::Sinhala,
+    Script::Thai,
+    Script::Lao,
+    Script::Tibetan,
+    Script::Myanmar,
+    Script::Khmer,
+    Script::Hangul,
+];
+
+/// True if `text` needs advanced shaping to display correctly, see [`Shaping::Auto`]
+#[cfg(feature = "swash")]
+fn needs_advanced_shaping(text: &str) -> bool {
+    text.chars().any(|c| {
+        let script = c.script();
+        c == '\u{200D}' || script == Script::Inherited || COMPLEX_SCRIPTS.contains(&script)
+    })
+}
+
+/// True if `attrs` set shaping features, which only [`Shaping::Advanced`] applies. This includes
+/// [`Attrs::font_features`] (and so stylistic sets), [`Attrs::ligatures`] and
+/// [`Attrs::arabic_form`] other than their defaults, see [`Shaping::Auto`]
+#[cfg(feature = "swash")]
+fn sets_shaping_features(attrs: &Attrs) -> bool {
+    !attrs.font_features.features.is_empty()
+        || attrs.ligatures != Ligatures::DEFAULT
+        || attrs.arabic_form != ArabicForm::Auto
+}
+
+/// True if the default attributes or any span overlapping `range` set shaping features, see
+/// [`sets_shaping_features`]
+#[cfg(feature = "swash")]
+fn requests_shaping_features(attrs_list: &AttrsList, range: Range<usize>) -> bool {
+    sets_shaping_features(&attrs_list.defaults())
+        || attrs_list.spans_iter().any(|(span, attrs)| {
+            span.start < range.end
+                && range.start < span.end
+                && sets_shaping_features(&attrs.as_attrs())
+        })
 }
 
 impl Shaping {
@@ -51,6 +133,33 @@ impl Shaping {
         match self {
             #[cfg(feature = "swash")]
             Self::Basic => shape_skip(font_system, glyphs, line, attrs_list, start_run, end_run),
+            #[cfg(feature = "swash")]
+            Self::Auto => {
+                if !span_rtl
+                    && !needs_advanced_shaping(&line[start_run..end_run])
+                    && !requests_shaping_features(attrs_list, start_run..end_run)
+                {
+                    let glyph_start = glyphs.len();
+                    shape_skip(font_system, glyphs, line, attrs_list, start_run, end_run);
+                    if glyphs[glyph_start..]
+                        .iter()
+                        .all(|glyph| glyph.glyph_id != 0)
+                    {
+                        return;
+                    }
+                    // Missing glyphs need font fallback
+                    glyphs.truncate(glyph_start);
+                }
+                Self::Advanced.run(
+                    glyphs,
+                    font_system,
+                    line,
+                    attrs_list,
+                    start_run,
+                    end_run,
+                    span_rtl,
+                );
+            }
             #[cfg(not(feature = "shape-run-cache"))]
             Self::Advanced => shape_run(
                 glyphs,
@@ -96,6 +205,46 @@ pub struct ShapeBuffer {
 
     /// Buffer for sets of layout glyphs.
     glyph_sets: Vec<Vec<LayoutGlyph>>,
+
+    /// Plans of the runs of the word being shaped, see [`ShapeWord::shaping_plans`]
+    shaping_plans: Vec<(Range<usize>, ShapingPlan)>,
+}
+
+impl ShapeBuffer {
+    /// Record the parameters the run `range` shaped into `glyphs` was passed to the shaper with
+    fn push_shaping_plan(
+        &mut self,
+        glyphs: &[ShapeGlyph],
+        attrs: &Attrs,
+        range: Range<usize>,
+        rtl: bool,
+        language_opt: Option<&rustybuzz::Language>,
+    ) {
+        let Some(glyph) = glyphs.first() else {
+            return;
+        };
+        // Font features override ligatures
+        let mut features: Vec<Feature> = attrs
+            .ligatures
+            .features()
+            .chain(attrs.font_features.features.iter().copied())
+            .collect();
+        if attrs.arabic_form == ArabicForm::Isolated {
+            features.extend(ISOLATED_FORM_DISABLED_FEATURES.map(|tag| Feature {
+                tag: FeatureTag::new(tag),
+                value: 0,
+            }));
+        }
+        self.shaping_plans.push((
+            range,
+            ShapingPlan {
+                script: glyph.script.map(|byte| byte.to_ascii_lowercase()),
+                language: language_opt.map(|language| language.as_str().into()),
+                rtl,
+                features,
+            },
+        ));
+    }
 }
 
 impl fmt::Debug for ShapeBuffer {
@@ -104,6 +253,7 @@ impl fmt::Debug for ShapeBuffer {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn shape_fallback(
     scratch: &mut ShapeBuffer,
     glyphs: &mut Vec<ShapeGlyph>,
@@ -113,6 +263,8 @@ fn shape_fallback(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    script_opt: Option<[u8; 4]>,
+    language_opt: Option<&rustybuzz::Language>,
 ) -> Vec<usize> {
     let run = &line[start_run..end_run];
 
@@ -136,15 +288,28 @@ fn shape_fallback(
         buffer.push_str(run);
     }
     buffer.guess_segment_properties();
+    if let Some(language) = language_opt {
+        buffer.set_language(language.clone());
+    }
+
+    let attrs = attrs_list.get_span(start_run);
+    if let Some(script) = attrs.script_opt.or(script_opt).and_then(|tag| {
+        rustybuzz::Script::from_iso15924_tag(rustybuzz::ttf_parser::Tag::from_bytes(&tag))
+    }) {
+        buffer.set_script(script);
+    }
 
     let rtl = matches!(buffer.direction(), rustybuzz::Direction::RightToLeft);
     assert_eq!(rtl, span_rtl);
 
-    let attrs = attrs_list.get_span(start_run);
     let mut rb_font_features = Vec::new();
 
-    // Convert attrs::Feature to rustybuzz::Feature
-    for feature in attrs.font_features.features {
+    // Convert attrs::Feature to rustybuzz::Feature, with font features overriding ligatures
+    for feature in attrs
+        .ligatures
+        .features()
+        .chain(attrs.font_features.features)
+    {
         rb_font_features.push(rustybuzz::Feature::new(
             rustybuzz::ttf_parser::Tag::from_bytes(feature.tag.as_bytes()),
             feature.value,
@@ -152,6 +317,16 @@ fn shape_fallback(
         ));
     }
 
+    if attrs.arabic_form == ArabicForm::Isolated {
+        for tag in ISOLATED_FORM_DISABLED_FEATURES {
+            rb_font_features.push(rustybuzz::Feature::new(
+                rustybuzz::ttf_parser::Tag::from_bytes(tag),
+                0,
+                0..usize::MAX,
+            ));
+        }
+    }
+
     let shape_plan = rustybuzz::ShapePlan::new(
         font.rustybuzz(),
         buffer.direction(),
@@ -159,6 +334,7 @@ fn shape_fallback(
         buffer.language().as_ref(),
         &rb_font_features,
     );
+    let script = buffer.script().tag().to_bytes();
     let glyph_buffer = rustybuzz::shape_with_plan(font.rustybuzz(), &shape_plan, buffer);
     let glyph_infos = glyph_buffer.glyph_infos();
     let glyph_positions = glyph_buffer.glyph_positions();
@@ -194,9 +370,13 @@ fn shape_fallback(
             glyph_id: info.glyph_id.try_into().expect("failed to cast glyph ID"),
             //TODO: color should not be related to shaping
             color_opt: attrs.color_opt,
+            opacity: attrs.opacity_opt.map_or(1.0, |opacity| opacity.0),
             metadata: attrs.metadata,
             cache_key_flags: attrs.cache_key_flags,
             metrics_opt: attrs.metrics_opt.map(|x| x.into()),
+            size_scale: 1.0,
+            unsafe_to_break: info.unsafe_to_break(),
+            script,
         });
     }
 
@@ -239,6 +419,125 @@ fn shape_run(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+) {
+    let max_run_length = font_system.max_run_length().max(1);
+    if end_run - start_run <= max_run_length {
+        shape_chunk(
+            glyphs,
+            font_system,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+        );
+        return;
+    }
+
+    // Shape long runs in chunks, each ending at the last cluster that is safe to break before.
+    // Chunks without such a cluster are grown until they have one
+    let run_glyph_start = glyphs.len();
+    let mut chunk = Vec::new();
+    let mut start = start_run;
+    while start < end_run {
+        let mut chunk_length = max_run_length;
+        let end = loop {
+            let mut end = min(start.saturating_add(chunk_length), end_run);
+            while !line.is_char_boundary(end) {
+                end += 1;
+            }
+            chunk.clear();
+            shape_chunk(
+                &mut chunk,
+                font_system,
+                line,
+                attrs_list,
+                start,
+                end,
+                span_rtl,
+            );
+            if end == end_run {
+                break end;
+            }
+            if let Some(safe_start) = chunk
+                .iter()
+                .filter(|glyph| glyph.start > start && !glyph.unsafe_to_break)
+                .map(|glyph| glyph.start)
+                .max()
+            {
+                chunk.retain(|glyph| glyph.start < safe_start);
+                break safe_start;
+            }
+            chunk_length = chunk_length.saturating_mul(2);
+        };
+        // Glyphs of right-to-left runs are in visual order, so later chunks go first
+        if span_rtl {
+            glyphs.splice(run_glyph_start..run_glyph_start, chunk.drain(..));
+        } else {
+            glyphs.append(&mut chunk);
+        }
+        start = end;
+    }
+}
+
+/// Shape a run, or a chunk of a long run, see [`FontSystem::set_max_run_length`]
+fn shape_chunk(
+    glyphs: &mut Vec<ShapeGlyph>,
+    font_system: &mut FontSystem,
+    line: &str,
+    attrs_list: &AttrsList,
+    start_run: usize,
+    end_run: usize,
+    span_rtl: bool,
+) {
+    let Some(script_detector) = &font_system.script_detector else {
+        shape_detected_run(
+            glyphs,
+            font_system,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            None,
+            None,
+        );
+        return;
+    };
+
+    // Shape each range of the custom detection separately
+    let detections = script_detector(&line[start_run..end_run]);
+    for (range, script, language_opt) in detections {
+        let end = min(range.end, end_run - start_run);
+        if range.start >= end {
+            continue;
+        }
+        shape_detected_run(
+            glyphs,
+            font_system,
+            line,
+            attrs_list,
+            start_run + range.start,
+            start_run + end,
+            span_rtl,
+            Some(script),
+            language_opt,
+        );
+    }
+}
+
+/// Shape a run with the script and language from [`FontSystem::set_script_detector`], if any
+#[allow(clippy::too_many_arguments)]
+fn shape_detected_run(
+    glyphs: &mut Vec<ShapeGlyph>,
+    font_system: &mut FontSystem,
+    line: &str,
+    attrs_list: &AttrsList,
+    start_run: usize,
+    end_run: usize,
+    span_rtl: bool,
+    script_opt: Option<[u8; 4]>,
+    language_opt: Option<rustybuzz::Language>,
 ) {
     // Re-use the previous script buffer if possible.
     let mut scripts = {
@@ -263,6 +562,52 @@ fn shape_run(
 
     let fonts = font_system.get_font_matches(&attrs);
 
+    // Only set the language when detected or when a buffer sets its own locale
+    let language_opt = language_opt.or_else(|| {
+        font_system
+            .locale_override()
+            .and_then(|locale| locale.parse::<rustybuzz::Language>().ok())
+    });
+
+    let fallback_metric_matching = font_system.fallback_metric_matching();
+
+    let pua_policy = font_system.pua_policy();
+    let private_use = line[start_run..end_run].chars().all(is_private_use);
+    let font_id_opt = attrs
+        .font_id_opt
+        .or(pua_policy.font.filter(|_| private_use))
+        .filter(|&id| font_system.font_allowed(id));
+    let skip_fallback = private_use && pua_policy.skip_fallback;
+    let mark_fallback = font_system.mark_fallback();
+
+    if let Some(font) = font_id_opt.and_then(|id| font_system.get_font(id)) {
+        let glyph_start = glyphs.len();
+        let mut missing = shape_fallback(
+            &mut font_system.shape_buffer,
+            glyphs,
+            &font,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            script_opt,
+            language_opt.as_ref(),
+        );
+        font_system.shape_buffer.push_shaping_plan(
+            &glyphs[glyph_start..],
+            &attrs,
+            start_run..end_run,
+            span_rtl,
+            language_opt.as_ref(),
+        );
+        if mark_fallback != MarkFallback::Allow {
+            drop_missing_marks(glyphs, glyph_start, &mut missing);
+        }
+        font_system.shape_buffer.scripts = scripts;
+        return;
+    }
+
     let default_families = [&attrs.family];
     let mut font_iter = FontFallbackIter::new(
         font_system,
@@ -270,29 +615,72 @@ fn shape_run(
         &default_families,
         &scripts,
         &line[start_run..end_run],
-    );
+    )
+    .with_style(attrs.weight, attrs.style);
 
     let font = font_iter.next().expect("no default font found");
+    let base_metrics = em_metrics(&font);
+    let primary_id = font.id();
 
     let glyph_start = glyphs.len();
     let mut missing = {
         let scratch = font_iter.shape_caches();
-        shape_fallback(
-            scratch, glyphs, &font, line, attrs_list, start_run, end_run, span_rtl,
-        )
+        let missing = shape_fallback(
+            scratch,
+            glyphs,
+            &font,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            script_opt,
+            language_opt.as_ref(),
+        );
+        scratch.push_shaping_plan(
+            &glyphs[glyph_start..],
+            &attrs,
+            start_run..end_run,
+            span_rtl,
+            language_opt.as_ref(),
+        );
+        missing
     };
+    if mark_fallback == MarkFallback::Never {
+        drop_missing_marks(glyphs, glyph_start, &mut missing);
+    }
+    // Clusters the primary font only partly shaped, such as a base with a missing mark, which
+    // metric matching keeps at the scale of the primary font
+    let mixed_clusters: Vec<usize> = if fallback_metric_matching {
+        missing
+            .iter()
+            .copied()
+            .filter(|&start| {
+                glyphs[glyph_start..]
+                    .iter()
+                    .any(|glyph| glyph.start == start && glyph.glyph_id != 0)
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let missing_count = missing.len();
+    // Number of missing positions resolved by each fallback font, for the trace below
+    let mut resolved: Vec<(fontdb::ID, usize)> = Vec::new();
 
     //TODO: improve performance!
-    while !missing.is_empty() {
+    while !missing.is_empty() && !skip_fallback {
         let font = match font_iter.next() {
             Some(some) => some,
             None => break,
         };
+        font_iter.count_fallback_font();
 
         log::trace!(
             "Evaluating fallback with font '{}'",
             font_iter.face_name(font.id())
         );
+        let size_scale = base_metrics.0 / em_metrics(&font).0;
         let mut fb_glyphs = Vec::new();
         let scratch = font_iter.shape_caches();
         let fb_missing = shape_fallback(
@@ -304,6 +692,8 @@ fn shape_run(
             start_run,
             end_run,
             span_rtl,
+            script_opt,
+            language_opt.as_ref(),
         );
 
         // Insert all matching glyphs
@@ -318,14 +708,12 @@ fn shape_run(
                 continue;
             }
 
-            let mut missing_i = 0;
-            while missing_i < missing.len() {
-                if missing[missing_i] >= start && missing[missing_i] < end {
-                    // println!("No longer missing {}", missing[missing_i]);
-                    missing.remove(missing_i);
-                } else {
-                    missing_i += 1;
-                }
+            let metric_matching = mixed_clusters.contains(&start);
+            let missing_len = missing.len();
+            missing.retain(|&missing_start| missing_start < start || missing_start >= end);
+            match resolved.iter_mut().find(|(id, _)| *id == font.id()) {
+                Some((_, count)) => *count += missing_len - missing.len(),
+                None => resolved.push((font.id(), missing_len - missing.len())),
             }
 
             // Find prior glyphs
@@ -350,7 +738,14 @@ fn shape_run(
 
             while fb_i < fb_glyphs.len() {
                 if fb_glyphs[fb_i].start >= start && fb_glyphs[fb_i].end <= end {
-                    let fb_glyph = fb_glyphs.remove(fb_i);
+                    let mut fb_glyph = fb_glyphs.remove(fb_i);
+                    if metric_matching {
+                        // Line metrics stay those of the primary font once scaled
+                        let (_, ascent, descent) = base_metrics;
+                        fb_glyph.size_scale = size_scale;
+                        fb_glyph.ascent = ascent / size_scale;
+                        fb_glyph.descent = descent / size_scale;
+                    }
                     // log::trace!("Insert {},{} from font {} at {}", fb_glyph.start, fb_glyph.end, font_i, i);
                     glyphs.insert(i, fb_glyph);
                     i += 1;
@@ -361,9 +756,31 @@ fn shape_run(
         }
     }
 
+    if mark_fallback != MarkFallback::Allow {
+        drop_missing_marks(glyphs, glyph_start, &mut missing);
+    }
+
+    if missing_count > 0 {
+        log::trace!(
+            "      Fallback {:?}: {} missing, resolved by {:?}, {} unresolved",
+            start_run..end_run,
+            missing_count,
+            resolved,
+            missing.len()
+        );
+    }
+
     // Debug missing font fallbacks
     font_iter.check_missing(&line[start_run..end_run]);
 
+    override_space_advances(
+        font_system,
+        &mut glyphs[glyph_start..],
+        line,
+        attrs_list,
+        primary_id,
+    );
+
     /*
     for glyph in glyphs.iter() {
         log::trace!("'{}': {}, {}, {}, {}", &line[glyph.start..glyph.end], glyph.x_advance, glyph.y_advance, glyph.x_offset, glyph.y_offset);
@@ -374,6 +791,64 @@ fn shape_run(
     font_system.shape_buffer.scripts = scripts;
 }
 
+/// Replace the advance of whitespace glyphs that the primary font `primary_id` could not shape,
+/// according to [`FontSystem::space_advance_source`]
+fn override_space_advances(
+    font_system: &mut FontSystem,
+    glyphs: &mut [ShapeGlyph],
+    line: &str,
+    attrs_list: &AttrsList,
+    primary_id: fontdb::ID,
+) {
+    let source = font_system.space_advance_source();
+    if source == SpaceAdvanceSource::Fallback {
+        return;
+    }
+    for glyph in glyphs.iter_mut() {
+        if glyph.font_id == primary_id && glyph.glyph_id != 0 {
+            continue;
+        }
+        let text = &line[glyph.start..glyph.end];
+        if text.is_empty() || !text.chars().all(char::is_whitespace) {
+            continue;
+        }
+        let advance = match source {
+            SpaceAdvanceSource::Fallback => continue,
+            SpaceAdvanceSource::Em(advance) => advance,
+            SpaceAdvanceSource::Font(id) => {
+                let Some(font) = font_system.get_font(id) else {
+                    continue;
+                };
+                // Tabs are shaped as spaces
+                let c = text.chars().next().filter(|&c| c != '\t').unwrap_or(' ');
+                let face = font.rustybuzz();
+                let Some(glyph_id) = face.glyph_index(c) else {
+                    continue;
+                };
+                let font_scale = face.units_per_em() as f32;
+                glyph.font_id = id;
+                glyph.glyph_id = glyph_id.0;
+                glyph.ascent = face.ascender() as f32 / font_scale;
+                glyph.descent = -face.descender() as f32 / font_scale;
+                glyph.font_monospace_em_width = font.monospace_em_width();
+                face.glyph_hor_advance(glyph_id).unwrap_or(0) as f32 / font_scale
+            }
+        };
+        let attrs = attrs_list.get_span(glyph.start);
+        glyph.size_scale = 1.0;
+        glyph.x_advance = advance + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
+    }
+}
+
+/// Height from descender to ascender, ascent and descent of a font in em units
+fn em_metrics(font: &Font) -> (f32, f32, f32) {
+    let face = font.rustybuzz();
+    let font_scale = face.units_per_em() as f32;
+    let ascent = face.ascender() as f32 / font_scale;
+    let descent = -face.descender() as f32 / font_scale;
+    (ascent + descent, ascent, descent)
+}
+
 #[cfg(feature = "shape-run-cache")]
 fn shape_run_cached(
     glyphs: &mut Vec<ShapeGlyph>,
@@ -391,6 +866,8 @@ fn shape_run_cached(
         text: line[run_range.clone()].to_string(),
         default_attrs: AttrsOwned::new(&attrs_list.defaults()),
         attrs_spans: Vec::new(),
+        locale_opt: font_system.locale_override().map(Into::into),
+        font_whitelist_opt: font_system.font_whitelist().map(Into::into),
     };
     for (attrs_range, attrs) in attrs_list.spans.overlapping(&run_range) {
         if attrs == &key.default_attrs {
@@ -404,18 +881,25 @@ fn shape_run_cached(
             key.attrs_spans.push((range, attrs.clone()));
         }
     }
-    if let Some(cache_glyphs) = font_system.shape_run_cache.get(&key) {
+    font_system.next_cache_tick();
+    if let Some((cache_glyphs, cache_plans)) = font_system.shape_run_cache.get_with_plans(&key) {
         for mut glyph in cache_glyphs.iter().cloned() {
             // Adjust glyph start and end to match run position
             glyph.start += start_run;
             glyph.end += start_run;
             glyphs.push(glyph);
         }
+        font_system.shape_buffer.shaping_plans.extend(
+            cache_plans.iter().map(|(range, plan)| {
+                (range.start + start_run..range.end + start_run, plan.clone())
+            }),
+        );
         return;
     }
 
     // Fill in cache if not already set
     let mut cache_glyphs = Vec::new();
+    let plans_start = font_system.shape_buffer.shaping_plans.len();
     shape_run(
         &mut cache_glyphs,
         font_system,
@@ -431,7 +915,15 @@ fn shape_run_cached(
         glyph.start -= start_run;
         glyph.end -= start_run;
     }
-    font_system.shape_run_cache.insert(key, cache_glyphs);
+    let cache_plans = font_system.shape_buffer.shaping_plans[plans_start..]
+        .iter()
+        .map(|(range, plan)| (range.start - start_run..range.end - start_run, plan.clone()))
+        .collect();
+    font_system.next_cache_tick();
+    font_system
+        .shape_run_cache
+        .insert_with_plans(key, cache_glyphs, cache_plans);
+    font_system.enforce_memory_budget();
 }
 
 #[cfg(feature = "swash")]
@@ -444,53 +936,162 @@ fn shape_skip(
     end_run: usize,
 ) {
     let attrs = attrs_list.get_span(start_run);
-    let fonts = font_system.get_font_matches(&attrs);
-
-    let default_families = [&attrs.family];
-    let mut font_iter = FontFallbackIter::new(font_system, &fonts, &default_families, &[], "");
-
-    let font = font_iter.next().expect("no default font found");
+    let font = match attrs.font_id_opt.and_then(|id| font_system.get_font(id)) {
+        Some(font) => font,
+        None => {
+            let fonts = font_system.get_font_matches(&attrs);
+            let default_families = [&attrs.family];
+            let mut font_iter =
+                FontFallbackIter::new(font_system, &fonts, &default_families, &[], "");
+            font_iter.next().expect("no default font found")
+        }
+    };
     let font_id = font.id();
     let font_monospace_em_width = font.monospace_em_width();
-    let font = font.as_swash();
+    let face = font.rustybuzz();
+    let swash = font.as_swash();
 
-    let charmap = font.charmap();
-    let metrics = font.metrics(&[]);
-    let glyph_metrics = font.glyph_metrics(&[]).scale(1.0);
+    let charmap = swash.charmap();
+    let metrics = swash.metrics(&[]);
+    let glyph_metrics = swash.glyph_metrics(&[]).scale(1.0);
 
     let ascent = metrics.ascent / f32::from(metrics.units_per_em);
     let descent = metrics.descent / f32::from(metrics.units_per_em);
 
+    // The first script that is not common or inherited, or unknown, as guessed by rustybuzz
+    let script = line[start_run..end_run]
+        .chars()
+        .map(|c| c.script())
+        .find(|script| !matches!(script, Script::Common | Script::Inherited | Script::Unknown))
+        .unwrap_or(Script::Unknown);
+    let script = script
+        .short_name()
+        .as_bytes()
+        .try_into()
+        .unwrap_or(*b"Zzzz");
+
+    let mut chars = line[start_run..end_run].char_indices().peekable();
     glyphs.extend(
-        line[start_run..end_run]
-            .char_indices()
-            .map(|(chr_idx, codepoint)| {
-                let glyph_id = charmap.map(codepoint);
-                let x_advance = glyph_metrics.advance_width(glyph_id)
-                    + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
-                let attrs = attrs_list.get_span(start_run + chr_idx);
-
-                ShapeGlyph {
-                    start: chr_idx + start_run,
-                    end: chr_idx + start_run + codepoint.len_utf8(),
-                    x_advance,
-                    y_advance: 0.0,
-                    x_offset: 0.0,
-                    y_offset: 0.0,
-                    ascent,
-                    descent,
-                    font_monospace_em_width,
-                    font_id,
-                    glyph_id,
-                    color_opt: attrs.color_opt,
-                    metadata: attrs.metadata,
-                    cache_key_flags: attrs.cache_key_flags,
-                    metrics_opt: attrs.metrics_opt.map(|x| x.into()),
-                }
-            }),
+        core::iter::from_fn(|| {
+            let (chr_idx, codepoint) = chars.next()?;
+            // Keep variation sequences together, using the variant glyph if the font has one
+            let mut end = chr_idx + codepoint.len_utf8();
+            let mut glyph_id = None;
+            while let Some(&(vs_idx, vs)) = chars.peek().filter(|(_, c)| is_variation_selector(*c))
+            {
+                chars.next();
+                end = vs_idx + vs.len_utf8();
+                glyph_id = glyph_id.or_else(|| face.glyph_variation_index(codepoint, vs));
+            }
+            // Default ignorables are hidden behind a zero-advance space, as rustybuzz does
+            let ignorable = is_default_ignorable(codepoint);
+            let glyph_id = if ignorable {
+                charmap.map(' ')
+            } else {
+                glyph_id.map_or_else(|| charmap.map(codepoint), |id| id.0)
+            };
+            Some((chr_idx, end, glyph_id, ignorable))
+        })
+        .map(|(chr_idx, end, glyph_id, ignorable)| {
+            let x_advance = if ignorable {
+                0.0
+            } else {
+                glyph_metrics.advance_width(glyph_id)
+                    + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0)
+            };
+            let attrs = attrs_list.get_span(start_run + chr_idx);
+
+            ShapeGlyph {
+                start: chr_idx + start_run,
+                end: end + start_run,
+                x_advance,
+                y_advance: 0.0,
+                x_offset: 0.0,
+                y_offset: 0.0,
+                ascent,
+                descent,
+                font_monospace_em_width,
+                font_id,
+                glyph_id,
+                color_opt: attrs.color_opt,
+                opacity: attrs.opacity_opt.map_or(1.0, |opacity| opacity.0),
+                metadata: attrs.metadata,
+                cache_key_flags: attrs.cache_key_flags,
+                metrics_opt: attrs.metrics_opt.map(|x| x.into()),
+                size_scale: 1.0,
+                unsafe_to_break: false,
+                script,
+            }
+        }),
     );
 }
 
+/// True for default ignorable code points, such as joiners, bidi controls and variation
+/// selectors, which affect shaping but are not displayed
+pub(crate) fn is_default_ignorable(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{115F}'..='\u{1160}'
+            | '\u{17B4}'..='\u{17B5}'
+            | '\u{180B}'..='\u{180F}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+            | '\u{FFF0}'..='\u{FFF8}'
+            | '\u{1BCA0}'..='\u{1BCA3}'
+            | '\u{1D173}'..='\u{1D17A}'
+            | '\u{E0000}'..='\u{E0FFF}'
+    )
+}
+
+/// Remove the missing glyphs of clusters whose base character was shaped, dropping the combining
+/// marks the font lacks, and remove these clusters from `missing`
+fn drop_missing_marks(glyphs: &mut Vec<ShapeGlyph>, glyph_start: usize, missing: &mut Vec<usize>) {
+    let dropped: Vec<usize> = missing
+        .iter()
+        .copied()
+        .filter(|&start| {
+            glyphs[glyph_start..]
+                .iter()
+                .any(|glyph| glyph.start == start && glyph.glyph_id != 0)
+        })
+        .collect();
+    if dropped.is_empty() {
+        return;
+    }
+
+    let mut i = glyph_start;
+    while i < glyphs.len() {
+        if glyphs[i].glyph_id == 0 && dropped.contains(&glyphs[i].start) {
+            glyphs.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    missing.retain(|start| !dropped.contains(start));
+}
+
+/// True for characters in the Unicode Private Use Areas
+pub(crate) fn is_private_use(c: char) -> bool {
+    matches!(
+        c,
+        '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{FFFFD}' | '\u{100000}'..='\u{10FFFD}'
+    )
+}
+
+/// True for variation selectors, including the ideographic variation selectors
+#[cfg(feature = "swash")]
+fn is_variation_selector(c: char) -> bool {
+    matches!(c, '\u{180B}'..='\u{180D}' | '\u{180F}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
 /// A shaped glyph
 #[derive(Clone, Debug)]
 pub struct ShapeGlyph {
@@ -506,9 +1107,20 @@ pub struct ShapeGlyph {
     pub font_id: fontdb::ID,
     pub glyph_id: u16,
     pub color_opt: Option<Color>,
+    pub opacity: f32,
     pub metadata: usize,
     pub cache_key_flags: CacheKeyFlags,
     pub metrics_opt: Option<Metrics>,
+    /// Factor applied to the font size of this glyph, see
+    /// [`FontSystem::set_fallback_metric_matching`]
+    pub size_scale: f32,
+    /// True if splitting the text at the start of this glyph's cluster and shaping both sides
+    /// separately could give a different result, such as inside joined Arabic letters or
+    /// ligatures. Shaping both sides again is then needed when splitting the text there
+    pub unsafe_to_break: bool,
+    /// ISO 15924 tag of the script of the shaping run this glyph is from, such as `Latn`, or
+    /// `Zzzz` if the run only has common characters such as spaces and digits
+    pub script: [u8; 4],
 }
 
 impl ShapeGlyph {
@@ -535,15 +1147,19 @@ impl ShapeGlyph {
             x_offset: self.x_offset,
             y_offset: self.y_offset,
             color_opt: self.color_opt,
+            opacity: self.opacity,
             metadata: self.metadata,
             cache_key_flags: self.cache_key_flags,
+            unsafe_to_break: self.unsafe_to_break,
+            script: self.script,
+            marks: Vec::new(),
         }
     }
 
     /// Get the width of the [`ShapeGlyph`] in pixels, either using the provided font size
-    /// or the [`ShapeGlyph::metrics_opt`] override.
+    /// or the [`ShapeGlyph::metrics_opt`] override, scaled by [`ShapeGlyph::size_scale`].
     pub fn width(&self, font_size: f32) -> f32 {
-        self.metrics_opt.map_or(font_size, |x| x.font_size) * self.x_advance
+        self.metrics_opt.map_or(font_size, |x| x.font_size) * self.size_scale * self.x_advance
     }
 }
 
@@ -552,6 +1168,11 @@ impl ShapeGlyph {
 pub struct ShapeWord {
     pub blank: bool,
     pub glyphs: Vec<ShapeGlyph>,
+    /// Hyphen shown when a line is broken at the soft hyphen (U+00AD) ending this word
+    pub hyphen: Option<ShapeGlyph>,
+    /// Byte ranges in the line of the runs of this word that were passed to the shaper, with the
+    /// parameters they were passed with, see [`crate::LayoutRun::shaping_plan`]
+    pub shaping_plans: Vec<(Range<usize>, ShapingPlan)>,
 }
 
 impl ShapeWord {
@@ -562,6 +1183,8 @@ impl ShapeWord {
         Self {
             blank: true,
             glyphs: Vec::default(),
+            hyphen: None,
+            shaping_plans: Vec::default(),
         }
     }
 
@@ -575,9 +1198,36 @@ impl ShapeWord {
         level: unicode_bidi::Level,
         blank: bool,
         shaping: Shaping,
+    ) -> Self {
+        Self::new_with_options(
+            font_system,
+            line,
+            attrs_list,
+            word_range,
+            level,
+            blank,
+            shaping,
+            &LayoutOptions::default(),
+        )
+    }
+
+    /// Shape a word into a set of glyphs with the given [`LayoutOptions`].
+    ///
+    /// Shaping runs are split where the font attributes change, or at every attribute span
+    /// boundary if [`LayoutOptions::preserve_span_boundaries`] is true.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_options(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        shaping: Shaping,
+        options: &LayoutOptions,
     ) -> Self {
         let mut empty = Self::empty();
-        empty.build(
+        empty.build_with_options(
             font_system,
             line,
             attrs_list,
@@ -585,6 +1235,7 @@ impl ShapeWord {
             level,
             blank,
             shaping,
+            options,
         );
         empty
     }
@@ -602,6 +1253,33 @@ impl ShapeWord {
         level: unicode_bidi::Level,
         blank: bool,
         shaping: Shaping,
+    ) {
+        self.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            word_range,
+            level,
+            blank,
+            shaping,
+            &LayoutOptions::default(),
+        );
+    }
+
+    /// See [`Self::new_with_options`].
+    ///
+    /// Reuses as much of the pre-existing internal allocations as possible.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        shaping: Shaping,
+        options: &LayoutOptions,
     ) {
         let word = &line[word_range.clone()];
 
@@ -613,6 +1291,10 @@ impl ShapeWord {
 
         let mut glyphs = mem::take(&mut self.glyphs);
         glyphs.clear();
+        // The plans of the runs are collected in the previous plans of this word
+        let mut shaping_plans = mem::take(&mut self.shaping_plans);
+        shaping_plans.clear();
+        font_system.shape_buffer.shaping_plans = shaping_plans;
 
         let span_rtl = level.is_rtl();
 
@@ -621,7 +1303,9 @@ impl ShapeWord {
         for (egc_i, _egc) in word.grapheme_indices(true) {
             let start_egc = word_range.start + egc_i;
             let attrs_egc = attrs_list.get_span(start_egc);
-            if !attrs.compatible(&attrs_egc) {
+            if !attrs.compatible(&attrs_egc)
+                || (options.preserve_span_boundaries && attrs != attrs_egc)
+            {
                 shaping.run(
                     &mut glyphs,
                     font_system,
@@ -648,8 +1332,14 @@ impl ShapeWord {
             );
         }
 
+        self.hyphen = if !blank && word.ends_with('\u{AD}') {
+            soft_hyphen_glyph(font_system, &glyphs, word_range.end - '\u{AD}'.len_utf8())
+        } else {
+            None
+        };
         self.blank = blank;
         self.glyphs = glyphs;
+        self.shaping_plans = mem::take(&mut font_system.shape_buffer.shaping_plans);
     }
 
     /// Get the width of the [`ShapeWord`] in pixels, using the [`ShapeGlyph::width`] function.
@@ -660,6 +1350,43 @@ impl ShapeWord {
         }
         width
     }
+
+    /// Get the width of [`Self::hyphen`] in pixels, or `0.0` if this word does not end with a soft
+    /// hyphen
+    pub fn hyphen_width(&self, font_size: f32) -> f32 {
+        self.hyphen
+            .as_ref()
+            .map_or(0.0, |hyphen| hyphen.width(font_size))
+    }
+}
+
+/// Get the hyphen glyph shown in place of the soft hyphen starting at `start`, in the font of the
+/// soft hyphen glyph
+fn soft_hyphen_glyph(
+    font_system: &mut FontSystem,
+    glyphs: &[ShapeGlyph],
+    start: usize,
+) -> Option<ShapeGlyph> {
+    let glyph = glyphs
+        .iter()
+        .find(|glyph| glyph.start <= start && start < glyph.end)?;
+    let font = font_system.get_font(glyph.font_id)?;
+    let face = font.rustybuzz();
+    let glyph_id = ['\u{2010}', '-']
+        .into_iter()
+        .find_map(|c| face.glyph_index(c).filter(|id| id.0 != 0))?;
+    let advance = face.glyph_hor_advance(glyph_id)?;
+    Some(ShapeGlyph {
+        start,
+        end: glyph.end,
+        x_advance: f32::from(advance) / face.units_per_em() as f32,
+        y_advance: 0.0,
+        x_offset: 0.0,
+        y_offset: 0.0,
+        glyph_id: glyph_id.0,
+        unsafe_to_break: false,
+        ..glyph.clone()
+    })
 }
 
 /// A shaped span (for bidirectional processing)
@@ -689,9 +1416,33 @@ impl ShapeSpan {
         line_rtl: bool,
         level: unicode_bidi::Level,
         shaping: Shaping,
+    ) -> Self {
+        Self::new_with_options(
+            font_system,
+            line,
+            attrs_list,
+            span_range,
+            line_rtl,
+            level,
+            shaping,
+            &LayoutOptions::default(),
+        )
+    }
+
+    /// Shape a span into a set of words with the given [`LayoutOptions`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_options(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
+        options: &LayoutOptions,
     ) -> Self {
         let mut empty = Self::empty();
-        empty.build(
+        empty.build_with_options(
             font_system,
             line,
             attrs_list,
@@ -699,6 +1450,7 @@ impl ShapeSpan {
             line_rtl,
             level,
             shaping,
+            options,
         );
         empty
     }
@@ -715,6 +1467,33 @@ impl ShapeSpan {
         line_rtl: bool,
         level: unicode_bidi::Level,
         shaping: Shaping,
+    ) {
+        self.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            span_range,
+            line_rtl,
+            level,
+            shaping,
+            &LayoutOptions::default(),
+        );
+    }
+
+    /// See [`Self::new_with_options`].
+    ///
+    /// Reuses as much of the pre-existing internal allocations as possible.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
+        options: &LayoutOptions,
     ) {
         let span = &line[span_range.start..span_range.end];
 
@@ -752,7 +1531,7 @@ impl ShapeSpan {
             }
             if start_word < start_lb {
                 let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
-                word.build(
+                word.build_with_options(
                     font_system,
                     line,
                     attrs_list,
@@ -760,6 +1539,7 @@ impl ShapeSpan {
                     level,
                     false,
                     shaping,
+                    options,
                 );
                 words.push(word);
             }
@@ -767,7 +1547,7 @@ impl ShapeSpan {
                 for (i, c) in span[start_lb..end_lb].char_indices() {
                     // assert!(c.is_whitespace());
                     let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
-                    word.build(
+                    word.build_with_options(
                         font_system,
                         line,
                         attrs_list,
@@ -776,6 +1556,7 @@ impl ShapeSpan {
                         level,
                         true,
                         shaping,
+                        options,
                     );
                     words.push(word);
                 }
@@ -844,6 +1625,10 @@ impl ShapeLine {
     /// Shape a line into a set of spans, using a scratch buffer. If [`unicode_bidi::BidiInfo`]
     /// detects multiple paragraphs, they will be joined.
     ///
+    /// Shaping is the only step that computes bidi levels and word and line break segmentation.
+    /// They are kept in the spans and words, so [`Self::layout`] at another width or wrap only
+    /// reorders the stored levels of each visual line.
+    ///
     /// # Panics
     ///
     /// Will panic if `line` contains multiple paragraphs that do not have matching direction
@@ -853,9 +1638,32 @@ impl ShapeLine {
         attrs_list: &AttrsList,
         shaping: Shaping,
         tab_width: u16,
+    ) -> Self {
+        Self::new_with_options(
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            tab_width,
+            &LayoutOptions::default(),
+        )
+    }
+
+    /// Shape a line into a set of spans with the given [`LayoutOptions`], see [`Self::new`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `line` contains multiple paragraphs that do not have matching direction
+    pub fn new_with_options(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+        options: &LayoutOptions,
     ) -> Self {
         let mut empty = Self::empty();
-        empty.build(font_system, line, attrs_list, shaping, tab_width);
+        empty.build_with_options(font_system, line, attrs_list, shaping, tab_width, options);
         empty
     }
 
@@ -874,6 +1682,34 @@ impl ShapeLine {
         shaping: Shaping,
         tab_width: u16,
     ) {
+        self.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            tab_width,
+            &LayoutOptions::default(),
+        );
+    }
+
+    /// See [`Self::new_with_options`].
+    ///
+    /// Reuses as much of the pre-existing internal allocations as possible.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `line` contains multiple paragraphs that do not have matching direction
+    pub fn build_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+        options: &LayoutOptions,
+    ) {
+        font_system.shaped_line_count += 1;
+
         let mut spans = mem::take(&mut self.spans);
 
         // Cache the shape spans in reverse order so they can be popped for reuse in the same order.
@@ -912,7 +1748,7 @@ impl ShapeLine {
                 if new_level != run_level {
                     // End of the previous run, start of a new one.
                     let mut span = cached_spans.pop().unwrap_or_else(ShapeSpan::empty);
-                    span.build(
+                    span.build_with_options(
                         font_system,
                         line,
                         attrs_list,
@@ -920,6 +1756,7 @@ impl ShapeLine {
                         line_rtl,
                         run_level,
                         shaping,
+                        options,
                     );
                     spans.push(span);
                     start = i;
@@ -927,7 +1764,7 @@ impl ShapeLine {
                 }
             }
             let mut span = cached_spans.pop().unwrap_or_else(ShapeSpan::empty);
-            span.build(
+            span.build_with_options(
                 font_system,
                 line,
                 attrs_list,
@@ -935,6 +1772,7 @@ impl ShapeLine {
                 line_rtl,
                 run_level,
                 shaping,
+                options,
             );
             spans.push(span);
         }
@@ -1080,6 +1918,43 @@ impl ShapeLine {
         runs
     }
 
+    /// Get the glyphs of this line in logical order, placed one after another from the left
+    /// without bidi reordering. Right-to-left text keeps the contextual forms it was shaped with,
+    /// so this is useful for placing glyphs in the cells of a terminal grid.
+    pub fn logical_glyphs(&self, font_size: f32) -> Vec<LayoutGlyph> {
+        let mut shape_glyphs = self
+            .spans
+            .iter()
+            .flat_map(|span| {
+                span.words
+                    .iter()
+                    .flat_map(move |word| word.glyphs.iter().map(move |glyph| (glyph, span.level)))
+            })
+            .collect::<Vec<_>>();
+        // Glyphs of the same cluster keep the order they were shaped in
+        shape_glyphs.sort_by_key(|(glyph, _level)| glyph.start);
+
+        let mut x = 0.0;
+        shape_glyphs
+            .into_iter()
+            .map(|(glyph, level)| {
+                let glyph_font_size =
+                    glyph.metrics_opt.map_or(font_size, |x| x.font_size) * glyph.size_scale;
+                let x_advance = glyph_font_size * glyph.x_advance;
+                let layout_glyph = glyph.layout(
+                    glyph_font_size,
+                    glyph.metrics_opt.map(|x| x.line_height),
+                    x,
+                    0.0,
+                    x_advance,
+                    level,
+                );
+                x += x_advance;
+                layout_glyph
+            })
+            .collect()
+    }
+
     pub fn layout(
         &self,
         font_size: f32,
@@ -1101,6 +1976,49 @@ impl ShapeLine {
         lines
     }
 
+    /// Find the narrowest width that word wraps to as few lines as `width_opt`, so that the lines
+    /// of [`Wrap::Balanced`] have similar lengths
+    fn balanced_wrap_width(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_size: f32,
+        width_opt: Option<f32>,
+        match_mono_width: Option<f32>,
+    ) -> Option<f32> {
+        let width = width_opt.filter(|width| width.is_finite())?;
+        let mut lines = Vec::new();
+        let mut count_lines = |scratch: &mut ShapeBuffer, width: f32| {
+            self.layout_to_buffer(
+                scratch,
+                font_size,
+                Some(width),
+                Wrap::Word,
+                None,
+                &mut lines,
+                match_mono_width,
+            );
+            lines.len()
+        };
+
+        let target = count_lines(scratch, width);
+        if target <= 1 {
+            return width_opt;
+        }
+
+        // Binary search the width, which only ever adds lines when narrowed
+        let mut low = 0.0;
+        let mut high = width;
+        while high - low > BALANCED_WRAP_PRECISION {
+            let mid = (low + high) / 2.0;
+            if count_lines(scratch, mid) <= target {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Some(high)
+    }
+
     pub fn layout_to_buffer(
         &self,
         scratch: &mut ShapeBuffer,
@@ -1111,6 +2029,16 @@ impl ShapeLine {
         layout_lines: &mut Vec<LayoutLine>,
         match_mono_width: Option<f32>,
     ) {
+        // Balanced wrapping is word wrapping at a narrower width, while alignment still uses the
+        // full width
+        let (wrap, wrap_width_opt) = if wrap == Wrap::Balanced {
+            let wrap_width_opt =
+                self.balanced_wrap_width(scratch, font_size, width_opt, match_mono_width);
+            (Wrap::Word, wrap_width_opt)
+        } else {
+            (wrap, width_opt)
+        };
+
         // For each visual line a list of  (span index,  and range of words in that span)
         // Note that a BiDi visual line could have multiple spans or parts of them
         // let mut vl_range_of_spans = Vec::with_capacity(1);
@@ -1189,12 +2117,14 @@ impl ShapeLine {
                         // Addition in the same order used to compute the final width, so that
                         // relayouts with that width as the `line_width` will produce the same
                         // wrapping results.
-                        if current_visual_line.w + (word_range_width + word_width)
-                            <= width_opt.unwrap_or(f32::INFINITY)
+                        // Leave room for the hyphen shown if the line is broken after this word
+                        if current_visual_line.w
+                            + (word_range_width + word_width + word.hyphen_width(font_size))
+                            <= wrap_width_opt.unwrap_or(f32::INFINITY)
                             // Include one blank word over the width limit since it won't be
                             // counted in the final width
                             || (word.blank
-                                && (current_visual_line.w + word_range_width) <= width_opt.unwrap_or(f32::INFINITY))
+                                && (current_visual_line.w + word_range_width) <= wrap_width_opt.unwrap_or(f32::INFINITY))
                         {
                             // fits
                             if word.blank {
@@ -1205,12 +2135,12 @@ impl ShapeLine {
                             continue;
                         } else if wrap == Wrap::Glyph
                             // Make sure that the word is able to fit on it's own line, if not, fall back to Glyph wrapping.
-                            || (wrap == Wrap::WordOrGlyph && word_width > width_opt.unwrap_or(f32::INFINITY))
+                            || (wrap == Wrap::WordOrGlyph && word_width > wrap_width_opt.unwrap_or(f32::INFINITY))
                         {
                             // Commit the current line so that the word starts on the next line.
                             if word_range_width > 0.
                                 && wrap == Wrap::WordOrGlyph
-                                && word_width > width_opt.unwrap_or(f32::INFINITY)
+                                && word_width > wrap_width_opt.unwrap_or(f32::INFINITY)
                             {
                                 add_to_visual_line(
                                     &mut current_visual_line,
@@ -1233,7 +2163,7 @@ impl ShapeLine {
                             for (glyph_i, glyph) in word.glyphs.iter().enumerate().rev() {
                                 let glyph_width = glyph.width(font_size);
                                 if current_visual_line.w + (word_range_width + glyph_width)
-                                    <= width_opt.unwrap_or(f32::INFINITY)
+                                    <= wrap_width_opt.unwrap_or(f32::INFINITY)
                                 {
                                     word_range_width += glyph_width;
                                     continue;
@@ -1315,12 +2245,14 @@ impl ShapeLine {
                     let mut fitting_start = (0, 0);
                     for (i, word) in span.words.iter().enumerate() {
                         let word_width = word.width(font_size);
-                        if current_visual_line.w + (word_range_width + word_width)
-                            <= width_opt.unwrap_or(f32::INFINITY)
+                        // Leave room for the hyphen shown if the line is broken after this word
+                        if current_visual_line.w
+                            + (word_range_width + word_width + word.hyphen_width(font_size))
+                            <= wrap_width_opt.unwrap_or(f32::INFINITY)
                             // Include one blank word over the width limit since it won't be
                             // counted in the final width.
                             || (word.blank
-                                && (current_visual_line.w + word_range_width) <= width_opt.unwrap_or(f32::INFINITY))
+                                && (current_visual_line.w + word_range_width) <= wrap_width_opt.unwrap_or(f32::INFINITY))
                         {
                             // fits
                             if word.blank {
@@ -1331,12 +2263,12 @@ impl ShapeLine {
                             continue;
                         } else if wrap == Wrap::Glyph
                             // Make sure that the word is able to fit on it's own line, if not, fall back to Glyph wrapping.
-                            || (wrap == Wrap::WordOrGlyph && word_width > width_opt.unwrap_or(f32::INFINITY))
+                            || (wrap == Wrap::WordOrGlyph && word_width > wrap_width_opt.unwrap_or(f32::INFINITY))
                         {
                             // Commit the current line so that the word starts on the next line.
                             if word_range_width > 0.
                                 && wrap == Wrap::WordOrGlyph
-                                && word_width > width_opt.unwrap_or(f32::INFINITY)
+                                && word_width > wrap_width_opt.unwrap_or(f32::INFINITY)
                             {
                                 add_to_visual_line(
                                     &mut current_visual_line,
@@ -1359,7 +2291,7 @@ impl ShapeLine {
                             for (glyph_i, glyph) in word.glyphs.iter().enumerate() {
                                 let glyph_width = glyph.width(font_size);
                                 if current_visual_line.w + (word_range_width + glyph_width)
-                                    <= width_opt.unwrap_or(f32::INFINITY)
+                                    <= wrap_width_opt.unwrap_or(f32::INFINITY)
                                 {
                                     word_range_width += glyph_width;
                                     continue;
@@ -1445,13 +2377,7 @@ impl ShapeLine {
         }
 
         // Create the LayoutLines using the ranges inside visual lines
-        let align = align.unwrap_or({
-            if self.rtl {
-                Align::Right
-            } else {
-                Align::Left
-            }
-        });
+        let align = align.unwrap_or(Align::Start);
 
         let line_width = match width_opt {
             Some(width) => width,
@@ -1479,13 +2405,33 @@ impl ShapeLine {
             let mut y = 0.;
             let mut max_ascent: f32 = 0.;
             let mut max_descent: f32 = 0.;
+
+            // Show a hyphen at the end of lines broken at a soft hyphen, unless nothing precedes it
+            let hyphen_at = visual_line.ranges.last().and_then(
+                |&(span_index, _, (ending_word, ending_glyph))| {
+                    let span = &self.spans[span_index];
+                    let word_i = ending_word.checked_sub(1).filter(|_| ending_glyph == 0)?;
+                    if index + 1 == number_of_visual_lines
+                        || span.level.is_rtl() != self.rtl
+                        || visual_line.w <= 0.0
+                    {
+                        return None;
+                    }
+                    let hyphen = span.words[word_i].hyphen.as_ref()?;
+                    Some((span_index, word_i, hyphen))
+                },
+            );
+            let visual_line_w =
+                visual_line.w + hyphen_at.map_or(0.0, |(_, _, hyphen)| hyphen.width(font_size));
+
             let alignment_correction = match (align, self.rtl) {
-                (Align::Left, true) => line_width - visual_line.w,
+                (Align::Left, true) => line_width - visual_line_w,
                 (Align::Left, false) => 0.,
                 (Align::Right, true) => 0.,
-                (Align::Right, false) => line_width - visual_line.w,
-                (Align::Center, _) => (line_width - visual_line.w) / 2.0,
-                (Align::End, _) => line_width - visual_line.w,
+                (Align::Right, false) => line_width - visual_line_w,
+                (Align::Center, _) => (line_width - visual_line_w) / 2.0,
+                (Align::Start, _) => 0.,
+                (Align::End, _) => line_width - visual_line_w,
                 (Align::Justified, _) => 0.,
             };
 
@@ -1515,7 +2461,7 @@ impl ShapeLine {
                 // Don't justify the last line in a paragraph.
                 && index != number_of_visual_lines - 1
             {
-                (line_width - visual_line.w) / visual_line.spaces as f32
+                (line_width - visual_line_w) / visual_line.spaces as f32
             } else {
                 0.
             };
@@ -1535,9 +2481,16 @@ impl ShapeLine {
                             (true, true) => &word.glyphs[starting_glyph..ending_glyph],
                         };
 
-                        for glyph in included_glyphs {
+                        let hyphen = hyphen_at
+                            .filter(|&(hyphen_span, hyphen_word, _)| {
+                                (hyphen_span, hyphen_word) == (span_index, i)
+                            })
+                            .map(|(_, _, hyphen)| hyphen);
+
+                        for glyph in included_glyphs.iter().chain(hyphen) {
                             // Use overridden font size
-                            let font_size = glyph.metrics_opt.map_or(font_size, |x| x.font_size);
+                            let font_size = glyph.metrics_opt.map_or(font_size, |x| x.font_size)
+                                * glyph.size_scale;
 
                             let match_mono_em_width = match_mono_width.map(|w| w / font_size);
 
@@ -1611,7 +2564,7 @@ impl ShapeLine {
 
             layout_lines.push(LayoutLine {
                 w: if align != Align::Justified {
-                    visual_line.w
+                    visual_line_w
                 } else if self.rtl {
                     start_x - x
                 } else {
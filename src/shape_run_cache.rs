@@ -2,7 +2,11 @@
 use alloc::{string::String, vec::Vec};
 use core::ops::Range;
 
-use crate::{AttrsOwned, HashMap, ShapeGlyph};
+use crate::{AttrsOwned, HashMap, ShapeGlyph, ShapingPlan};
+
+/// Byte ranges in the run of the parts passed to the shaper, with the parameters they were passed
+/// with, see [`crate::ShapeWord::shaping_plans`]
+pub(crate) type ShapingPlans = Vec<(Range<usize>, ShapingPlan)>;
 
 /// Key for caching shape runs.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -10,36 +14,183 @@ pub struct ShapeRunKey {
     pub text: String,
     pub default_attrs: AttrsOwned,
     pub attrs_spans: Vec<(Range<usize>, AttrsOwned)>,
+    pub locale_opt: Option<String>,
+    pub font_whitelist_opt: Option<Vec<fontdb::ID>>,
 }
 
 /// A helper structure for caching shape runs.
 #[derive(Clone, Default)]
 pub struct ShapeRunCache {
     age: u64,
-    cache: HashMap<ShapeRunKey, (u64, Vec<ShapeGlyph>)>,
+    /// Tick of the last use of a cache of the [`crate::FontSystem`], recorded by items when they
+    /// are used
+    pub(crate) tick: u64,
+    cache: HashMap<ShapeRunKey, (u64, u64, Vec<ShapeGlyph>, ShapingPlans)>,
+    /// Number of times each item was pinned, see [`crate::FontSystem::pin_shaped`]
+    pinned: HashMap<ShapeRunKey, usize>,
+    /// Keys of items used while recording, see [`Self::record`]
+    recording: Option<Vec<ShapeRunKey>>,
+    /// Estimated size in bytes of the items that are not pinned, see [`Self::memory_usage`]
+    memory_usage: usize,
+}
+
+/// Get the estimated size in bytes of a cache item
+fn item_size(key: &ShapeRunKey, glyphs: &[ShapeGlyph], plans: &ShapingPlans) -> usize {
+    core::mem::size_of::<(ShapeRunKey, (u64, u64, Vec<ShapeGlyph>, ShapingPlans))>()
+        + key.text.len()
+        + key.attrs_spans.len() * core::mem::size_of::<(Range<usize>, AttrsOwned)>()
+        + core::mem::size_of_val(glyphs)
+        + core::mem::size_of_val(plans.as_slice())
 }
 
 impl ShapeRunCache {
     /// Get cache item, updating age if found
     pub fn get(&mut self, key: &ShapeRunKey) -> Option<&Vec<ShapeGlyph>> {
-        self.cache.get_mut(key).map(|(age, glyphs)| {
+        self.get_with_plans(key).map(|(glyphs, _)| glyphs)
+    }
+
+    /// Get cache item with the shaping plans of its run, updating age if found
+    pub(crate) fn get_with_plans(
+        &mut self,
+        key: &ShapeRunKey,
+    ) -> Option<(&Vec<ShapeGlyph>, &ShapingPlans)> {
+        let entry_opt = self.cache.get_mut(key);
+        if let (Some(recording), Some(_)) = (self.recording.as_mut(), entry_opt.as_ref()) {
+            recording.push(key.clone());
+        }
+        entry_opt.map(|(age, tick, glyphs, plans)| {
             *age = self.age;
-            &*glyphs
+            *tick = self.tick;
+            (&*glyphs, &*plans)
         })
     }
 
     /// Insert cache item with current age
     pub fn insert(&mut self, key: ShapeRunKey, glyphs: Vec<ShapeGlyph>) {
-        self.cache.insert(key, (self.age, glyphs));
+        self.insert_with_plans(key, glyphs, Vec::new());
+    }
+
+    /// Insert cache item with the shaping plans of its run, with current age
+    pub(crate) fn insert_with_plans(
+        &mut self,
+        key: ShapeRunKey,
+        glyphs: Vec<ShapeGlyph>,
+        plans: ShapingPlans,
+    ) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(key.clone());
+        }
+        let pinned = self.pinned.contains_key(&key);
+        if let Some((_age, _tick, old_glyphs, old_plans)) = self.cache.remove(&key) {
+            if !pinned {
+                self.memory_usage -= item_size(&key, &old_glyphs, &old_plans);
+            }
+        }
+        if !pinned {
+            self.memory_usage += item_size(&key, &glyphs, &plans);
+        }
+        self.cache.insert(key, (self.age, self.tick, glyphs, plans));
     }
 
-    /// Remove anything in the cache with an age older than `keep_ages`
+    /// Check if the item with `key` is pinned, so it is never trimmed or evicted
+    pub fn is_pinned(&self, key: &ShapeRunKey) -> bool {
+        self.pinned.contains_key(key)
+    }
+
+    /// Start or stop recording the keys of items that are used
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn record(&mut self, recording: bool) -> Vec<ShapeRunKey> {
+        let keys = self.recording.take().unwrap_or_default();
+        if recording {
+            self.recording = Some(Vec::new());
+        }
+        keys
+    }
+
+    /// Pin the item with `key`, so it is never trimmed or evicted
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn pin(&mut self, key: ShapeRunKey) {
+        if !self.pinned.contains_key(&key) {
+            if let Some((_age, _tick, glyphs, plans)) = self.cache.get(&key) {
+                self.memory_usage -= item_size(&key, glyphs, plans);
+            }
+        }
+        *self.pinned.entry(key).or_default() += 1;
+    }
+
+    /// Undo one pin of the item with `key`
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn unpin(&mut self, key: &ShapeRunKey) {
+        if let Some(count) = self.pinned.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.pinned.remove(key);
+                if let Some((_age, _tick, glyphs, plans)) = self.cache.get(key) {
+                    self.memory_usage += item_size(key, glyphs, plans);
+                }
+            }
+        }
+    }
+
+    /// Remove anything in the cache with an age older than `keep_ages`, except pinned items
     pub fn trim(&mut self, keep_ages: u64) {
-        self.cache
-            .retain(|_key, (age, _glyphs)| *age + keep_ages >= self.age);
+        let memory_usage = &mut self.memory_usage;
+        self.cache.retain(|key, (age, _tick, glyphs, plans)| {
+            let keep = *age + keep_ages >= self.age || self.pinned.contains_key(key);
+            if !keep {
+                *memory_usage -= item_size(key, glyphs, plans);
+            }
+            keep
+        });
         // Increase age
         self.age += 1;
     }
+
+    /// Remove everything in the cache. Pinned items are shaped again the next time they are used
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.memory_usage = 0;
+    }
+
+    /// Remove anything in the cache shaped with the font `id`
+    pub fn remove_font(&mut self, id: fontdb::ID) {
+        let memory_usage = &mut self.memory_usage;
+        self.cache.retain(|key, (_age, _tick, glyphs, plans)| {
+            let keep = glyphs.iter().all(|glyph| glyph.font_id != id);
+            if !keep && !self.pinned.contains_key(key) {
+                *memory_usage -= item_size(key, glyphs, plans);
+            }
+            keep
+        });
+    }
+
+    /// Get the estimated size in bytes of the items that are not pinned
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// Get the tick of the last use and estimated size in bytes of every item that is not pinned
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn entry_sizes(&self) -> impl Iterator<Item = (u64, usize)> + '_ {
+        self.cache
+            .iter()
+            .filter(|(key, _)| !self.pinned.contains_key(key))
+            .map(|(key, (_age, tick, glyphs, plans))| (*tick, item_size(key, glyphs, plans)))
+    }
+
+    /// Remove anything in the cache last used before `tick`, except pinned items
+    #[cfg(feature = "shape-run-cache")]
+    pub(crate) fn evict_used_before(&mut self, tick: u64) {
+        let memory_usage = &mut self.memory_usage;
+        self.cache.retain(|key, (_age, used, glyphs, plans)| {
+            let keep = *used >= tick || self.pinned.contains_key(key);
+            if !keep {
+                *memory_usage -= item_size(key, glyphs, plans);
+            }
+            keep
+        });
+    }
 }
 
 impl core::fmt::Debug for ShapeRunCache {
@@ -3,10 +3,11 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use core::fmt;
-use swash::scale::{image::Content, ScaleContext};
+use swash::scale::{image::Content, ScaleContext, Scaler};
 use swash::scale::{Render, Source, StrikeWith};
-use swash::zeno::{Format, Vector};
+use swash::zeno::{Format, Mask, Origin, Vector};
 
+use crate::font::ColorPaletteSelection;
 use crate::{CacheKey, CacheKeyFlags, Color, FontSystem, HashMap};
 
 pub use swash::scale::image::{Content as SwashContent, Image as SwashImage};
@@ -36,10 +37,36 @@ fn swash_image(
     // in a real renderer
     let offset = Vector::new(cache_key.x_bin.as_float(), cache_key.y_bin.as_float());
 
+    let transform = if cache_key.flags.contains(CacheKeyFlags::FAKE_ITALIC) {
+        Some(Transform::skew(
+            Angle::from_degrees(14.0),
+            Angle::from_degrees(0.0),
+        ))
+    } else {
+        None
+    };
+
+    // Overridden palette entries need the color layers composited here
+    let palette = font_system.color_palette(cache_key.font_id);
+    if let Some(palette) = palette.filter(|palette| !palette.overrides.is_empty()) {
+        let image = swash_color_outline_image(
+            font.as_swash(),
+            &mut scaler,
+            cache_key.glyph_id,
+            palette,
+            offset,
+            transform,
+        );
+        if image.is_some() {
+            return image;
+        }
+    }
+    let palette_index = palette.map_or(0, |palette| palette_index(palette.index));
+
     // Select our source order
     Render::new(&[
-        // Color outline with the first palette
-        Source::ColorOutline(0),
+        // Color outline with the selected palette
+        Source::ColorOutline(palette_index),
         // Color bitmap with best fit selection mode
         Source::ColorBitmap(StrikeWith::BestFit),
         // Standard scalable outline
@@ -49,18 +76,101 @@ fn swash_image(
     .format(Format::Alpha)
     // Apply the fractional offset
     .offset(offset)
-    .transform(if cache_key.flags.contains(CacheKeyFlags::FAKE_ITALIC) {
-        Some(Transform::skew(
-            Angle::from_degrees(14.0),
-            Angle::from_degrees(0.0),
-        ))
-    } else {
-        None
-    })
+    .transform(transform)
     // Render the image
     .render(&mut scaler, cache_key.glyph_id)
 }
 
+fn palette_index(index: usize) -> u16 {
+    u16::try_from(index).unwrap_or(u16::MAX)
+}
+
+/// Render the color outline of a glyph like [`Source::ColorOutline`], with palette entries
+/// overridden by `palette`
+fn swash_color_outline_image(
+    font: swash::FontRef,
+    scaler: &mut Scaler,
+    glyph_id: swash::GlyphId,
+    palette: &ColorPaletteSelection,
+    offset: Vector,
+    transform: Option<Transform>,
+) -> Option<SwashImage> {
+    if !scaler.has_color_outlines() {
+        return None;
+    }
+    let mut outline = scaler.scale_color_outline(glyph_id)?;
+    if let Some(transform) = &transform {
+        outline.transform(transform);
+    }
+    let colors = font.color_palettes().nth(palette.index);
+
+    let bounds = outline.bounds();
+    let left = (bounds.min.x + offset.x).floor() as i32;
+    let bottom = (bounds.min.y + offset.y).ceil() as i32;
+    let width = bounds.width().ceil() as u32;
+    let height = bounds.height().ceil() as u32;
+
+    let mut image = SwashImage::new();
+    image.source = Source::ColorOutline(palette_index(palette.index));
+    image.content = Content::Color;
+    image.placement = Placement {
+        left,
+        top: height as i32 + bottom,
+        width,
+        height,
+    };
+    image.data.resize((width * height * 4) as usize, 0);
+
+    let mut mask = Vec::new();
+    for i in 0..outline.len() {
+        let layer = outline.get(i)?;
+        mask.clear();
+        let placement = Mask::new(layer.path())
+            .origin(Origin::BottomLeft)
+            .offset(offset)
+            .render_offset(offset)
+            .inspect(|format, width, height| mask.resize(format.buffer_size(width, height), 0))
+            .render_into(&mut mask, None);
+        let color = layer
+            .color_index()
+            .and_then(|entry| {
+                palette
+                    .overrides
+                    .get(&entry)
+                    .map(|color| color.as_rgba())
+                    .or_else(|| colors.map(|colors| colors.get(entry)))
+            })
+            .unwrap_or([128, 128, 128, 255]);
+
+        // Blend the layer over the layers below it
+        for mask_y in 0..placement.height {
+            let y = image.placement.top - placement.top + mask_y as i32;
+            if y < 0 || y >= height as i32 {
+                continue;
+            }
+            for mask_x in 0..placement.width {
+                let x = placement.left - left + mask_x as i32;
+                if x < 0 || x >= width as i32 {
+                    continue;
+                }
+                let coverage = mask[(mask_y * placement.width + mask_x) as usize] as u32;
+                let a = coverage * color[3] as u32 / 255;
+                if a == 0 {
+                    continue;
+                }
+                let pixel = ((y as u32 * width + x as u32) * 4) as usize;
+                let inverse_a = 255 - a;
+                let source = [color[0], color[1], color[2], 255];
+                for (d, s) in image.data[pixel..pixel + 4].iter_mut().zip(source) {
+                    *d = ((inverse_a * *d as u32 + a * s as u32) / 255) as u8;
+                }
+            }
+        }
+    }
+
+    Some(image)
+}
+
 fn swash_outline_commands(
     font_system: &mut FontSystem,
     context: &mut ScaleContext,
@@ -95,11 +205,19 @@ fn swash_outline_commands(
     Some(path.commands().collect())
 }
 
-/// Cache for rasterizing with the swash scaler
+/// Cache for rasterizing with the swash scaler.
+///
+/// Glyphs of fonts removed from the [`FontSystem`] with [`FontSystem::remove_font`] or
+/// [`FontSystem::replace_font_source`], and images of fonts whose color palette changed, are
+/// purged on the next use of the cache
 pub struct SwashCache {
     context: ScaleContext,
     pub image_cache: HashMap<CacheKey, Option<SwashImage>>,
     pub outline_command_cache: HashMap<CacheKey, Option<Box<[swash::zeno::Command]>>>,
+    /// Number of [`FontSystem::removed_font_ids`] already purged
+    removed_fonts_seen: usize,
+    /// Number of [`FontSystem::palette_changed_font_ids`] already purged
+    palette_changes_seen: usize,
 }
 
 impl fmt::Debug for SwashCache {
@@ -115,7 +233,34 @@ impl SwashCache {
             context: ScaleContext::new(),
             image_cache: HashMap::default(),
             outline_command_cache: HashMap::default(),
+            removed_fonts_seen: 0,
+            palette_changes_seen: 0,
+        }
+    }
+
+    /// Remove the images and outlines of the font `id`
+    pub fn remove_font(&mut self, id: fontdb::ID) {
+        self.image_cache
+            .retain(|cache_key, _| cache_key.font_id != id);
+        self.outline_command_cache
+            .retain(|cache_key, _| cache_key.font_id != id);
+    }
+
+    /// Remove the images and outlines of fonts removed from `font_system` since the last use,
+    /// and the images of fonts whose color palette changed since then
+    fn purge_removed_fonts(&mut self, font_system: &FontSystem) {
+        let removed = font_system.removed_font_ids();
+        for &id in removed.get(self.removed_fonts_seen..).unwrap_or_default() {
+            self.remove_font(id);
+        }
+        self.removed_fonts_seen = removed.len();
+
+        let changed = font_system.palette_changed_font_ids();
+        for &id in changed.get(self.palette_changes_seen..).unwrap_or_default() {
+            self.image_cache
+                .retain(|cache_key, _| cache_key.font_id != id);
         }
+        self.palette_changes_seen = changed.len();
     }
 
     /// Create a swash Image from a cache key, without caching results
@@ -133,6 +278,7 @@ impl SwashCache {
         font_system: &mut FontSystem,
         cache_key: CacheKey,
     ) -> &Option<SwashImage> {
+        self.purge_removed_fonts(font_system);
         self.image_cache
             .entry(cache_key)
             .or_insert_with(|| swash_image(font_system, &mut self.context, cache_key))
@@ -144,6 +290,7 @@ impl SwashCache {
         font_system: &mut FontSystem,
         cache_key: CacheKey,
     ) -> Option<&[swash::zeno::Command]> {
+        self.purge_removed_fonts(font_system);
         self.outline_command_cache
             .entry(cache_key)
             .or_insert_with(|| swash_outline_commands(font_system, &mut self.context, cache_key))
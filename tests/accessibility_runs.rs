@@ -0,0 +1,32 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn accessibility_runs_per_line() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello\nworld!",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let runs = buffer.accessibility_runs();
+    assert_eq!(runs.len(), 2);
+
+    for (run, layout_run) in runs.iter().zip(buffer.layout_runs()) {
+        assert_eq!(run.line_i, layout_run.line_i);
+        assert!(!run.rtl);
+        let (x, y, w, h) = run.rect;
+        assert_eq!(x, 0.0);
+        assert_eq!(w, layout_run.line_w);
+        assert_eq!(y, layout_run.line_top);
+        assert_eq!(h, 20.0);
+    }
+    assert_eq!(runs[0].text_range, 0..5);
+    assert_eq!(runs[1].text_range, 0..6);
+    assert_eq!(runs[1].rect.1, 20.0);
+}
@@ -0,0 +1,29 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+fn glyph_count(buffer: &Buffer) -> usize {
+    buffer.layout_runs().map(|run| run.glyphs.len()).sum()
+}
+
+#[test]
+fn append_line_shapes_only_new_line() {
+    let mut font_system = common::font_system();
+
+    let attrs = Attrs::new();
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    let mut text = String::new();
+    for i in 0..1000 {
+        let line = format!("line {i}: the quick brown fox");
+        let shaped = font_system.shaped_line_count();
+        buffer.append_line(&mut font_system, &line, &attrs, Shaping::Advanced);
+        assert_eq!(font_system.shaped_line_count(), shaped + 1);
+        text.push_str(&line);
+        text.push('\n');
+    }
+    assert_eq!(buffer.lines.len(), 1000);
+
+    let mut expected = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    expected.set_text(&mut font_system, &text, &attrs, Shaping::Advanced);
+    assert_eq!(glyph_count(&buffer), glyph_count(&expected));
+}
@@ -0,0 +1,27 @@
+use cosmic_text::{ArabicForm, Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+mod common;
+
+fn glyph_ids(font_system: &mut FontSystem, text: &str, attrs: &Attrs) -> Vec<u16> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+}
+
+#[test]
+fn arabic_form_isolated_disables_joining() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansArabic.ttf"]);
+
+    let isolated = glyph_ids(&mut font_system, "ب", &Attrs::new())[0];
+
+    // Joined, the first and last letters use initial and final forms
+    let joined = glyph_ids(&mut font_system, "ببب", &Attrs::new());
+    assert_eq!(joined.len(), 3);
+    assert!(joined.iter().all(|&glyph_id| glyph_id != isolated));
+
+    let attrs = Attrs::new().arabic_form(ArabicForm::Isolated);
+    let forced = glyph_ids(&mut font_system, "ببب", &attrs);
+    assert_eq!(forced, [isolated; 3]);
+    assert_ne!(forced, joined);
+}
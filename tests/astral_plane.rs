@@ -0,0 +1,54 @@
+use cosmic_text::{Attrs, Buffer, Cursor, Family, FontSystem, Metrics, Motion, Shaping};
+
+mod common;
+
+// U+1D400 MATHEMATICAL BOLD CAPITAL A is four bytes in UTF-8 but a single char and grapheme.
+const TEXT: &str = "a\u{1D400}b";
+
+fn buffer(font_system: &mut FontSystem) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(20.0, 30.0));
+    buffer.set_text(
+        font_system,
+        TEXT,
+        &Attrs::new().family(Family::Name("Inter")),
+        Shaping::Advanced,
+    );
+    buffer
+}
+
+#[test]
+fn cursor_steps_over_astral_char() {
+    let mut font_system = common::font_system();
+    let mut buffer = buffer(&mut font_system);
+
+    let (cursor, _) = buffer
+        .cursor_motion(&mut font_system, Cursor::new(0, 1), None, Motion::Next)
+        .unwrap();
+    assert_eq!(cursor.index, 5);
+
+    let (cursor, _) = buffer
+        .cursor_motion(&mut font_system, cursor, None, Motion::Previous)
+        .unwrap();
+    assert_eq!(cursor.index, 1);
+}
+
+#[test]
+fn hit_never_splits_astral_char() {
+    let mut font_system = common::font_system();
+    let buffer = buffer(&mut font_system);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let glyph = run
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == 1)
+        .expect("no glyph for astral char");
+    assert_eq!(glyph.end, 5);
+
+    for step in 0..=10 {
+        let x = glyph.x + glyph.w * step as f32 / 10.0;
+        let cursor = buffer.hit(x, run.line_top + 1.0).unwrap();
+        assert!(TEXT.is_char_boundary(cursor.index), "{x}: {cursor:?}");
+        assert!(cursor.index == 1 || cursor.index == 5, "{x}: {cursor:?}");
+    }
+}
@@ -0,0 +1,42 @@
+use cosmic_text::{Attrs, AttrsOwned, Buffer, Color, Cursor, Metrics, Shaping, Weight};
+
+mod common;
+
+#[test]
+fn attrs_at_offsets() {
+    let mut font_system = common::font_system();
+
+    let plain = Attrs::new();
+    let bold = plain.clone().weight(Weight::BOLD);
+    let red = plain.clone().color(Color::rgb(0xFF, 0, 0));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("plain ", plain.clone()),
+            ("bold", bold.clone()),
+            (" red", red.clone()),
+            ("\nnext", plain.clone()),
+        ],
+        &plain,
+        Shaping::Advanced,
+        None,
+    );
+
+    let attrs_at = |index| buffer.attrs_at(Cursor::new(0, index)).unwrap();
+    assert_eq!(attrs_at(0), AttrsOwned::new(&plain));
+    assert_eq!(attrs_at(5), AttrsOwned::new(&plain));
+    // Boundaries take the following span
+    assert_eq!(attrs_at(6), AttrsOwned::new(&bold));
+    assert_eq!(attrs_at(9), AttrsOwned::new(&bold));
+    assert_eq!(attrs_at(10), AttrsOwned::new(&red));
+    // End of line takes the last character
+    assert_eq!(attrs_at(14), AttrsOwned::new(&red));
+
+    assert_eq!(
+        buffer.attrs_at(Cursor::new(1, 0)),
+        Some(AttrsOwned::new(&plain))
+    );
+    assert_eq!(buffer.attrs_at(Cursor::new(2, 0)), None);
+}
@@ -0,0 +1,47 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping, Wrap};
+
+mod common;
+
+fn line_texts(buffer: &Buffer) -> Vec<String> {
+    buffer
+        .layout_runs()
+        .map(|run| {
+            let start = run.glyphs.first().unwrap().start;
+            let end = run.glyphs.last().unwrap().end;
+            run.text[start..end].trim().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn balanced_wrap_evens_out_lines() {
+    let mut font_system = common::font_system();
+
+    let text = "alpha bravo delta gamma";
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::None);
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+
+    // Wide enough for the first three words only
+    let run = buffer.layout_runs().next().unwrap();
+    let third_end = run.glyphs.iter().find(|glyph| glyph.start == 17).unwrap();
+    let width = third_end.x + 2.0;
+    buffer.set_size(&mut font_system, Some(width), None);
+
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    assert_eq!(line_texts(&buffer), ["alpha bravo delta", "gamma"]);
+
+    buffer.set_wrap(&mut font_system, Wrap::Balanced);
+    assert_eq!(buffer.wrap(), Wrap::Balanced);
+    assert_eq!(line_texts(&buffer), ["alpha bravo", "delta gamma"]);
+    let widths: Vec<f32> = buffer.layout_runs().map(|run| run.line_w).collect();
+    assert!(widths.iter().all(|&w| w <= width));
+    assert!((widths[0] - widths[1]).abs() < width / 4.0);
+
+    // Relayout gives the same result
+    buffer.set_size(&mut font_system, Some(width + 1.0), None);
+    buffer.set_size(&mut font_system, Some(width), None);
+    let relayout: Vec<f32> = buffer.layout_runs().map(|run| run.line_w).collect();
+    assert_eq!(relayout, widths);
+    assert_eq!(line_texts(&buffer), ["alpha bravo", "delta gamma"]);
+}
@@ -0,0 +1,54 @@
+use cosmic_text::{Attrs, Baseline, Buffer, Family, FontSystem, Metrics, Shaping};
+
+mod common;
+
+// Tests that glyphs from a second font are shifted so that the chosen baseline lines up with the
+// baseline of the font used at the start of the line.
+#[test]
+fn baseline_aligns_mixed_fonts() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/FiraMono-Medium.ttf"]);
+    let latin = Attrs::new().family(Family::Name("Inter"));
+    let cjk = Attrs::new().family(Family::Name("Fira Mono"));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 30.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [("Hello ", latin.clone()), ("世界", cjk)],
+        &latin,
+        Shaping::Advanced,
+        None,
+    );
+
+    let glyph_ys = |buffer: &Buffer| -> Vec<(fontdb::ID, f32)> {
+        buffer
+            .layout_runs()
+            .flat_map(|run| run.glyphs.iter())
+            .map(|glyph| (glyph.font_id, glyph.y))
+            .collect()
+    };
+
+    assert_eq!(buffer.baseline(), Baseline::Alphabetic);
+    assert!(glyph_ys(&buffer).iter().all(|&(_, y)| y == 0.0));
+
+    for baseline in [
+        Baseline::Ideographic,
+        Baseline::Hanging,
+        Baseline::Central,
+        Baseline::Alphabetic,
+    ] {
+        buffer.set_baseline(&mut font_system, baseline);
+        let glyphs = glyph_ys(&buffer);
+        let dominant_id = glyphs[0].0;
+        let offset = |font_system: &mut FontSystem, id| {
+            font_system.get_font(id).unwrap().baseline_offset(baseline)
+        };
+        let dominant = offset(&mut font_system, dominant_id);
+        for (id, y) in glyphs {
+            let expected = (offset(&mut font_system, id) - dominant) * 20.0;
+            assert!(
+                (y - expected).abs() < 0.001,
+                "{baseline}: glyph y {y} != {expected}"
+            );
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use cosmic_text::{Attrs, Buffer, LeadingDistribution, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn baseline_y_is_ascent_without_leading_above() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 40.0));
+    buffer.set_text(
+        &mut font_system,
+        "Hello\nworld",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.set_leading_distribution(LeadingDistribution::BelowOnly);
+    let ascent = buffer.line_layout(&mut font_system, 0).unwrap()[0].max_ascent;
+    assert!(ascent > 0.0);
+
+    for run in buffer.layout_runs() {
+        assert!((run.baseline_y() - ascent).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,100 @@
+use cosmic_text::{Align, Attrs, BoundsMode, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn bounds_are_union_of_line_rects() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(300.0), None);
+    buffer.set_text(
+        &mut font_system,
+        "A short line\nA noticeably longer line of text\nMid line",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    for line in buffer.lines.iter_mut() {
+        line.set_align(Some(Align::Center));
+    }
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let mut expected = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, 0.0f32);
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            expected.0 = expected.0.min(glyph.x);
+            expected.2 = expected.2.max(glyph.x + glyph.w);
+        }
+        expected.1 = expected.1.min(run.line_top);
+        expected.3 = expected.3.max(run.line_top + run.line_height);
+    }
+    assert_eq!(buffer.layout_runs().count(), 3);
+    assert!(expected.0 > 0.0);
+    assert_eq!(
+        buffer.bounds(&mut font_system, BoundsMode::Advance),
+        Some(expected)
+    );
+
+    // Ink bounds lie within the lines
+    let ink = buffer.bounds(&mut font_system, BoundsMode::Ink).unwrap();
+    assert!(ink.0 >= expected.0 - 1.0 && ink.2 <= expected.2 + 1.0);
+    assert!(ink.1 > expected.1 && ink.3 < expected.3);
+}
+
+#[test]
+fn bounds_trailing_whitespace() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "ab", &Attrs::new(), Shaping::Advanced);
+    let trimmed = buffer.bounds(&mut font_system, BoundsMode::Advance);
+
+    buffer.set_text(&mut font_system, "ab   ", &Attrs::new(), Shaping::Advanced);
+    assert_eq!(
+        buffer.bounds(&mut font_system, BoundsMode::Advance),
+        trimmed
+    );
+    let with_whitespace = buffer
+        .bounds(&mut font_system, BoundsMode::AdvanceWithTrailingWhitespace)
+        .unwrap();
+    assert!(with_whitespace.2 > trimmed.unwrap().2);
+
+    buffer.set_text(&mut font_system, "", &Attrs::new(), Shaping::Advanced);
+    assert_eq!(
+        buffer.bounds(&mut font_system, BoundsMode::Advance),
+        Some((0.0, 0.0, 0.0, 20.0))
+    );
+    assert_eq!(buffer.bounds(&mut font_system, BoundsMode::Ink), None);
+}
+
+#[test]
+fn layout_bounds_without_font_system() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(300.0), None);
+    buffer.set_text(&mut font_system, "", &Attrs::new(), Shaping::Advanced);
+    assert_eq!(buffer.layout_bounds(), None);
+
+    // Right-to-left lines are aligned right, with their first glyph rightmost
+    buffer.set_text(
+        &mut font_system,
+        "مرحبا بالعالم\n\nab",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+    let bounds = buffer.layout_bounds().unwrap();
+    assert_eq!(
+        Some(bounds),
+        buffer.bounds(&mut font_system, BoundsMode::AdvanceWithTrailingWhitespace)
+    );
+    assert_eq!(bounds.0, 0.0);
+    assert_eq!((bounds.1, bounds.3), (0.0, 60.0));
+
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.rtl);
+    let first = &run.glyphs[0];
+    assert!(run.glyphs.iter().all(|glyph| glyph.x <= first.x));
+    assert_eq!(bounds.2, first.x + first.w);
+}
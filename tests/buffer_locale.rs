@@ -0,0 +1,66 @@
+use cosmic_text::{Attrs, Buffer, Fallback, Family, FontSystem, Metrics, Shaping};
+use unicode_script::Script;
+
+/// Resolves han characters to a different family depending on the locale
+struct RegionalFallback {
+    ja: &'static str,
+    zh: &'static str,
+}
+
+impl Fallback for RegionalFallback {
+    fn common_fallback(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn forbidden_fallback(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn script_fallback(&self, script: Script, locale: &str) -> &[&'static str] {
+        match (script, locale) {
+            (Script::Han, "ja") => core::slice::from_ref(&self.ja),
+            (Script::Han, _) => core::slice::from_ref(&self.zh),
+            _ => &[],
+        }
+    }
+}
+
+fn family_of(db: &fontdb::Database, id: fontdb::ID) -> &'static str {
+    let name = db.face(id).unwrap().families[0].0.clone();
+    Box::leak(name.into_boxed_str())
+}
+
+#[test]
+fn buffer_locale_overrides_fallback() {
+    let mut db = fontdb::Database::new();
+    let ja_id = db.load_font_source(fontdb::Source::Binary(std::sync::Arc::new(
+        std::fs::read("fonts/Inter-Regular.ttf").unwrap(),
+    )))[0];
+    let zh_id = db.load_font_source(fontdb::Source::Binary(std::sync::Arc::new(
+        std::fs::read("fonts/FiraMono-Medium.ttf").unwrap(),
+    )))[0];
+    let fallback = RegionalFallback {
+        ja: family_of(&db, ja_id),
+        zh: family_of(&db, zh_id),
+    };
+    let mut font_system =
+        FontSystem::new_with_locale_and_db_and_fallback("zh-CN".into(), db, fallback);
+
+    let attrs = Attrs::new().family(Family::Name("Missing"));
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "漢", &attrs, Shaping::Advanced);
+    let han_font = |buffer: &Buffer| buffer.layout_runs().next().unwrap().glyphs[0].font_id;
+
+    assert_eq!(buffer.locale(), None);
+    assert_eq!(han_font(&buffer), zh_id);
+
+    buffer.set_locale(&mut font_system, Some("ja"));
+    assert_eq!(buffer.locale(), Some("ja"));
+    assert_eq!(han_font(&buffer), ja_id);
+
+    buffer.set_locale(&mut font_system, Some("zh-TW"));
+    assert_eq!(han_font(&buffer), zh_id);
+
+    // The system locale itself is unchanged
+    assert_eq!(font_system.locale(), "zh-CN");
+}
@@ -0,0 +1,72 @@
+use cosmic_text::{Attrs, Buffer, ChainFallback, Fallback, Family, FontSystem, Metrics, Shaping};
+use unicode_script::Script;
+
+/// Fallback to fixed families for Han and Arabic characters
+struct FixedFallback {
+    common: &'static [&'static str],
+    han: &'static [&'static str],
+    arabic: &'static [&'static str],
+}
+
+impl Fallback for FixedFallback {
+    fn common_fallback(&self) -> &[&'static str] {
+        self.common
+    }
+
+    fn forbidden_fallback(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn script_fallback(&self, script: Script, _locale: &str) -> &[&'static str] {
+        match script {
+            Script::Han => self.han,
+            Script::Arabic => self.arabic,
+            _ => &[],
+        }
+    }
+}
+
+#[test]
+fn chain_fallback_prefers_earlier_fallbacks() {
+    let mut db = fontdb::Database::new();
+    let mut load = |path: &str| {
+        let font = std::fs::read(path).unwrap();
+        db.load_font_source(fontdb::Source::Binary(std::sync::Arc::new(font)))[0]
+    };
+    let inter_id = load("fonts/Inter-Regular.ttf");
+    let fira_id = load("fonts/FiraMono-Medium.ttf");
+    let arabic_id = load("fonts/NotoSansArabic.ttf");
+    let family = |id| -> &'static str {
+        let name = db.face(id).unwrap().families[0].0.clone();
+        Box::leak(name.into_boxed_str())
+    };
+    let (inter, fira, arabic) = (family(inter_id), family(fira_id), family(arabic_id));
+
+    let chain = ChainFallback::new(vec![
+        Box::new(FixedFallback {
+            common: Box::leak(Box::new([inter])),
+            han: Box::leak(Box::new([inter])),
+            arabic: &[],
+        }),
+        Box::new(FixedFallback {
+            common: Box::leak(Box::new([fira, inter])),
+            han: Box::leak(Box::new([fira])),
+            arabic: Box::leak(Box::new([arabic])),
+        }),
+    ]);
+    assert_eq!(chain.script_fallback(Script::Han, "en-US"), [inter]);
+    assert_eq!(chain.script_fallback(Script::Arabic, "en-US"), [arabic]);
+    assert!(chain.script_fallback(Script::Hebrew, "en-US").is_empty());
+    assert_eq!(chain.common_fallback(), [inter, fira]);
+
+    let mut font_system =
+        FontSystem::new_with_locale_and_db_and_fallback("en-US".into(), db, chain);
+    let attrs = Attrs::new().family(Family::Name("Missing"));
+    let mut first_font = |text: &str| {
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+        buffer.set_text(&mut font_system, text, &attrs, Shaping::Advanced);
+        buffer.layout_runs().next().unwrap().glyphs[0].font_id
+    };
+    assert_eq!(first_font("漢"), inter_id);
+    assert_eq!(first_font("ب"), arabic_id);
+}
@@ -0,0 +1,27 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn cluster_at_x_rtl() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansHebrew.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "אבג", &Attrs::new(), Shaping::Advanced);
+
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.rtl);
+    // The second letter in logical order is the middle glyph, each letter is two bytes
+    let second = run.glyphs.iter().find(|glyph| glyph.start == 2).unwrap();
+    let mid = second.x + second.w / 2.0;
+    assert_eq!(run.cluster_at_x(mid), Some(2..4));
+
+    // The first letter is on the right in RTL
+    let right = run
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    assert_eq!(run.cluster_at_x(right - 0.5), Some(0..2));
+    assert_eq!(run.cluster_at_x(right + 1.0), None);
+}
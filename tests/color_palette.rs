@@ -0,0 +1,90 @@
+#![cfg(feature = "swash")]
+
+use common::{be16, be32, TestFont};
+use cosmic_text::{CacheKey, CacheKeyFlags, Color, FontSystem, SwashCache, SwashContent};
+
+mod common;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+/// Build a font where `A` is a `COLR` glyph with a single square layer using palette entry 0,
+/// which is red in palette 0 and blue in palette 1
+fn two_palette_font() -> Vec<u8> {
+    let mut colr = Vec::new();
+    be16(&mut colr, &[0, 1]);
+    be32(&mut colr, &[14, 20]);
+    be16(&mut colr, &[1, 1, 0, 1, 2, 0]);
+
+    let mut cpal = Vec::new();
+    be16(&mut cpal, &[0, 1, 2, 2]);
+    be32(&mut cpal, &[16]);
+    be16(&mut cpal, &[0, 1]);
+    // Colors are stored as BGRA
+    cpal.extend_from_slice(&[0, 0, 255, 255, 255, 0, 0, 255]);
+
+    TestFont::new("Palette Test")
+        .glyph(None, 1000, false)
+        .glyph(Some('A'), 1000, true)
+        .glyph(None, 1000, true)
+        .table(b"COLR", colr)
+        .table(b"CPAL", cpal)
+        .build()
+}
+
+/// Get the center pixel of `A` cached by `swash_cache`
+fn center_color(
+    swash_cache: &mut SwashCache,
+    font_system: &mut FontSystem,
+    font_id: fontdb::ID,
+) -> [u8; 4] {
+    let (cache_key, _, _) = CacheKey::new(font_id, 1, 20.0, (0.0, 0.0), CacheKeyFlags::empty());
+    let image = swash_cache
+        .get_image(font_system, cache_key)
+        .as_ref()
+        .unwrap();
+    assert_eq!(image.content, SwashContent::Color);
+    let x = image.placement.width / 2;
+    let y = image.placement.height / 2;
+    let i = ((y * image.placement.width + x) * 4) as usize;
+    image.data[i..i + 4].try_into().unwrap()
+}
+
+/// Assert the center pixel of `A` has `color`, up to rounding while swash blends palette colors
+fn assert_center_color(
+    swash_cache: &mut SwashCache,
+    font_system: &mut FontSystem,
+    font_id: fontdb::ID,
+    color: [u8; 4],
+) {
+    let pixel = center_color(swash_cache, font_system, font_id);
+    assert!(
+        pixel.iter().zip(color).all(|(a, b)| a.abs_diff(b) <= 2),
+        "{pixel:?} != {color:?}"
+    );
+}
+
+#[test]
+fn color_palette_selects_cpal_palette() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    font_system.db_mut().load_font_data(two_palette_font());
+    let font_id = font_system.db().faces().next().unwrap().id;
+    // Images cached with the previous palette are rendered again
+    let mut swash_cache = SwashCache::new();
+
+    assert_center_color(&mut swash_cache, &mut font_system, font_id, RED);
+
+    font_system.set_color_palette(font_id, 1);
+    assert_center_color(&mut swash_cache, &mut font_system, font_id, BLUE);
+
+    // Overridden entries are composited without losing opacity
+    font_system.set_color_palette_override(font_id, 0, Color::rgb(0, 255, 0));
+    assert_eq!(
+        center_color(&mut swash_cache, &mut font_system, font_id),
+        [0, 255, 0, 255]
+    );
+
+    font_system.reset_color_palette(font_id);
+    assert_center_color(&mut swash_cache, &mut font_system, font_id, RED);
+}
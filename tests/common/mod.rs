@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use std::path::PathBuf;
 
 use cosmic_text::{
@@ -142,3 +144,238 @@ impl DrawTestCfg {
         }
     }
 }
+
+/// The font used by tests that do not need a particular font
+pub const INTER: &str = "fonts/Inter-Regular.ttf";
+
+/// Create a font system with the `en-US` locale and only the fonts at `paths` loaded
+pub fn font_system_with(paths: &[&str]) -> FontSystem {
+    let mut db = Database::new();
+    for path in paths {
+        db.load_font_data(std::fs::read(path).unwrap());
+    }
+    FontSystem::new_with_locale_and_db("en-US".into(), db)
+}
+
+/// Create a font system with the `en-US` locale and only [`INTER`] loaded
+pub fn font_system() -> FontSystem {
+    font_system_with(&[INTER])
+}
+
+/// Append big endian 16-bit `values` to `data`
+pub fn be16(data: &mut Vec<u8>, values: &[u16]) {
+    for value in values {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Append big endian 32-bit `values` to `data`
+pub fn be32(data: &mut Vec<u8>, values: &[u32]) {
+    for value in values {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// A glyph of a [`TestFont`]
+struct TestGlyph {
+    char_opt: Option<char>,
+    advance: u16,
+    square: bool,
+}
+
+/// Builder of minimal TrueType fonts with 1000 units per em, an ascender of 800 and a descender
+/// of -200, for tests that need specific glyphs or tables.
+pub struct TestFont {
+    family: String,
+    glyphs: Vec<TestGlyph>,
+    names: Vec<(u16, String)>,
+    tables: Vec<([u8; 4], Vec<u8>)>,
+}
+
+impl TestFont {
+    /// Create a font named `family`, with a PostScript name of `family` without spaces. The
+    /// first glyph added is the `.notdef` glyph
+    pub fn new(family: &str) -> Self {
+        Self {
+            family: family.into(),
+            glyphs: Vec::new(),
+            names: Vec::new(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// Add a glyph mapped from `char_opt` that advances `advance` units. It is drawn as a square
+    /// from (100, 100) to (900, 900) if `square` is true, and empty otherwise. Characters must be
+    /// added in increasing order and be in the Basic Multilingual Plane
+    pub fn glyph(mut self, char_opt: Option<char>, advance: u16, square: bool) -> Self {
+        self.glyphs.push(TestGlyph {
+            char_opt,
+            advance,
+            square,
+        });
+        self
+    }
+
+    /// Add a name record with `name_id`, in addition to the family and PostScript names
+    pub fn name(mut self, name_id: u16, string: &str) -> Self {
+        self.names.push((name_id, string.into()));
+        self
+    }
+
+    /// Add a table, such as `GSUB`, built by the test
+    pub fn table(mut self, tag: &[u8; 4], data: Vec<u8>) -> Self {
+        self.tables.push((*tag, data));
+        self
+    }
+
+    /// Build the font data
+    pub fn build(&self) -> Vec<u8> {
+        let num_glyphs = self.glyphs.len() as u16;
+        let any_square = self.glyphs.iter().any(|glyph| glyph.square);
+        let (min, max) = if any_square { (100, 900) } else { (0, 0) };
+        let max_advance = self.glyphs.iter().map(|glyph| glyph.advance).max();
+
+        let mut head = Vec::new();
+        be32(&mut head, &[0x0001_0000, 0x0001_0000, 0, 0x5F0F_3CF5]);
+        be16(&mut head, &[0, 1000]);
+        be32(&mut head, &[0, 0, 0, 0]);
+        be16(&mut head, &[min, min, max, max, 0, 8, 2, 1, 0]);
+
+        let mut hhea = Vec::new();
+        be32(&mut hhea, &[0x0001_0000]);
+        be16(
+            &mut hhea,
+            &[
+                800,
+                (-200i16) as u16,
+                0,
+                max_advance.unwrap_or(0),
+                min,
+                min,
+                max,
+                1,
+            ],
+        );
+        be16(&mut hhea, &[0; 8]);
+        be16(&mut hhea, &[0, num_glyphs]);
+
+        let mut maxp = Vec::new();
+        be32(&mut maxp, &[0x0001_0000]);
+        be16(
+            &mut maxp,
+            &[num_glyphs, 4, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0],
+        );
+
+        // A clockwise square from (100, 100) to (900, 900)
+        let mut square = Vec::new();
+        be16(&mut square, &[1, 100, 100, 900, 900, 3, 0]);
+        square.extend_from_slice(&[1, 1, 1, 1]);
+        be16(&mut square, &[100, 0, 800, 0]);
+        be16(&mut square, &[100, 800, 0, (-800i16) as u16]);
+        square.resize(36, 0);
+
+        let mut hmtx = Vec::new();
+        let mut glyf = Vec::new();
+        let mut loca = Vec::new();
+        for glyph in self.glyphs.iter() {
+            be16(
+                &mut hmtx,
+                &[glyph.advance, if glyph.square { 100 } else { 0 }],
+            );
+            be32(&mut loca, &[glyf.len() as u32]);
+            if glyph.square {
+                glyf.extend_from_slice(&square);
+            }
+        }
+        be32(&mut loca, &[glyf.len() as u32]);
+        if glyf.is_empty() {
+            glyf.resize(4, 0);
+        }
+
+        // One segment per character, and the final segment
+        let chars: Vec<(u16, u16)> = self
+            .glyphs
+            .iter()
+            .enumerate()
+            .filter_map(|(glyph_id, glyph)| Some((glyph.char_opt? as u16, glyph_id as u16)))
+            .collect();
+        let seg_count = chars.len() as u16 + 1;
+        let mut cmap = Vec::new();
+        be16(&mut cmap, &[0, 1, 3, 1]);
+        be32(&mut cmap, &[12]);
+        be16(
+            &mut cmap,
+            &[4, 16 + 8 * seg_count, 0, seg_count * 2, 0, 0, 0],
+        );
+        for (c, _) in chars.iter() {
+            be16(&mut cmap, &[*c]);
+        }
+        be16(&mut cmap, &[0xFFFF, 0]);
+        for (c, _) in chars.iter() {
+            be16(&mut cmap, &[*c]);
+        }
+        be16(&mut cmap, &[0xFFFF]);
+        for (c, glyph_id) in chars.iter() {
+            be16(&mut cmap, &[glyph_id.wrapping_sub(*c)]);
+        }
+        be16(&mut cmap, &[1]);
+        be16(&mut cmap, &vec![0; usize::from(seg_count)]);
+
+        let post_script_name = self.family.replace(' ', "");
+        let mut names = vec![(1, self.family.as_str()), (6, post_script_name.as_str())];
+        names.extend(self.names.iter().map(|(id, string)| (*id, string.as_str())));
+        let mut name = Vec::new();
+        be16(
+            &mut name,
+            &[0, names.len() as u16, 6 + 12 * names.len() as u16],
+        );
+        let mut offset = 0;
+        for (name_id, string) in names.iter() {
+            let len = string.encode_utf16().count() as u16 * 2;
+            be16(&mut name, &[3, 1, 0x409, *name_id, len, offset]);
+            offset += len;
+        }
+        for (_, string) in names.iter() {
+            be16(&mut name, &string.encode_utf16().collect::<Vec<_>>());
+        }
+
+        let mut tables: Vec<([u8; 4], Vec<u8>)> = vec![
+            (*b"cmap", cmap),
+            (*b"glyf", glyf),
+            (*b"head", head),
+            (*b"hhea", hhea),
+            (*b"hmtx", hmtx),
+            (*b"loca", loca),
+            (*b"maxp", maxp),
+            (*b"name", name),
+        ];
+        tables.extend(self.tables.iter().cloned());
+        tables.sort_by_key(|(tag, _)| *tag);
+
+        let num_tables = tables.len() as u16;
+        let entry_selector = num_tables.ilog2() as u16;
+        let search_range = 16 << entry_selector;
+        let mut font = Vec::new();
+        be32(&mut font, &[0x0001_0000]);
+        be16(
+            &mut font,
+            &[
+                num_tables,
+                search_range,
+                entry_selector,
+                num_tables * 16 - search_range,
+            ],
+        );
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, data) in tables.iter() {
+            font.extend_from_slice(tag);
+            be32(&mut font, &[0, offset as u32, data.len() as u32]);
+            offset += data.len().next_multiple_of(4);
+        }
+        for (_, data) in tables.iter() {
+            font.extend_from_slice(data);
+            font.resize(font.len().next_multiple_of(4), 0);
+        }
+        font
+    }
+}
@@ -0,0 +1,18 @@
+mod common;
+
+#[test]
+fn coverage_fraction_latin_font() {
+    let mut font_system = common::font_system();
+    let id = font_system.db().faces().next().unwrap().id;
+
+    let english = font_system.coverage_fraction(id, "The quick brown fox jumps over the lazy dog.");
+    assert!(english > 0.99, "English coverage {english}");
+
+    let cjk = font_system.coverage_fraction(id, "天地玄黄宇宙洪荒");
+    assert!(cjk < 0.01, "CJK coverage {cjk}");
+
+    let mixed = font_system.coverage_fraction(id, "ab天地");
+    assert!((mixed - 0.5).abs() < 0.01, "mixed coverage {mixed}");
+
+    assert_eq!(font_system.coverage_fraction(id, ""), 1.0);
+}
@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, Buffer, Cursor, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn vertical_movement_keeps_desired_x() {
+    let mut font_system = common::font_system_with(&["fonts/FiraMono-Medium.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "a long first line\nab\nanother long line",
+        &Attrs::new().family(Family::Monospace),
+        Shaping::Advanced,
+    );
+
+    let start = Cursor::new(0, 10);
+    let (cursor, x) = buffer.cursor_down(&mut font_system, start, None);
+    assert!(x > 0.0);
+    // The short line ends before the desired x
+    assert_eq!((cursor.line, cursor.index), (1, 2));
+
+    let (cursor, x_after) = buffer.cursor_down(&mut font_system, cursor, Some(x));
+    assert_eq!(x_after, x);
+    assert_eq!((cursor.line, cursor.index), (2, 10));
+
+    // And back up to the same column
+    let (cursor, x_after) = buffer.cursor_up(&mut font_system, cursor, Some(x));
+    assert_eq!((cursor.line, cursor.index), (1, 2));
+    let (cursor, x_after) = buffer.cursor_up(&mut font_system, cursor, Some(x_after));
+    assert_eq!(x_after, x);
+    assert_eq!((cursor.line, cursor.index), (0, 10));
+
+    // Without a desired x, the column of the short line is used
+    let (cursor, _) = buffer.cursor_down(&mut font_system, Cursor::new(1, 2), None);
+    assert_eq!((cursor.line, cursor.index), (2, 2));
+
+    // The cursor stays on the first and last lines
+    let (cursor, _) = buffer.cursor_up(&mut font_system, start, None);
+    assert_eq!(cursor, start);
+    let end = Cursor::new(2, 4);
+    let (cursor, _) = buffer.cursor_down(&mut font_system, end, None);
+    assert_eq!(cursor, end);
+}
@@ -0,0 +1,45 @@
+use cosmic_text::{Attrs, Buffer, DecorationLine, DecorationMode, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn unified_decorations_span_mixed_fonts() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/FiraMono-Medium.ttf"]);
+    let families: Vec<String> = font_system
+        .db()
+        .faces()
+        .map(|face| face.families[0].0.clone())
+        .collect();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 24.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("Hello ", Attrs::new().family(Family::Name(&families[0]))),
+            ("world", Attrs::new().family(Family::Name(&families[1]))),
+        ],
+        &Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+
+    let per_font = run.decorations(
+        &mut font_system,
+        DecorationLine::Underline,
+        DecorationMode::PerFont,
+    );
+    assert_eq!(per_font.len(), 2);
+    assert!(per_font.iter().all(|segment| segment.y > run.line_y));
+
+    let unified = run.decorations(
+        &mut font_system,
+        DecorationLine::Underline,
+        DecorationMode::Unified,
+    );
+    assert_eq!(unified.len(), 1);
+    let lowest = per_font.iter().map(|segment| segment.y).fold(0.0, f32::max);
+    assert_eq!(unified[0].y, lowest);
+    assert_eq!(unified[0].x, per_font[0].x);
+    assert_eq!(unified[0].x + unified[0].w, per_font[1].x + per_font[1].w);
+}
@@ -0,0 +1,50 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Motion, Shaping};
+
+mod common;
+
+const TEXT: &str = "ab\u{2060}cd";
+const JOINER: usize = 2;
+
+fn next(buffer: &mut Buffer, font_system: &mut FontSystem, index: usize, motion: Motion) -> usize {
+    let (cursor, _) = buffer
+        .cursor_motion(font_system, Cursor::new(0, index), None, motion)
+        .unwrap();
+    cursor.index
+}
+
+#[test]
+fn word_joiner_has_zero_advance_and_no_cursor_stop() {
+    let mut font_system = common::font_system();
+
+    for shaping in [Shaping::Advanced, Shaping::Basic] {
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+        buffer.set_text(&mut font_system, TEXT, &Attrs::new(), shaping);
+
+        let run = buffer.layout_runs().next().unwrap();
+        let joiner = run
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start <= JOINER && JOINER < glyph.end)
+            .unwrap();
+        assert_eq!(joiner.w, 0.0, "{shaping:?}");
+        let visible = run.glyphs.iter().filter(|glyph| glyph.w > 0.0).count();
+        assert_eq!(visible, 4, "{shaping:?}");
+
+        // The position before the word joiner is skipped in both directions
+        let after_joiner = JOINER + '\u{2060}'.len_utf8();
+        let mut index = 0;
+        let mut stops = Vec::new();
+        while index < TEXT.len() {
+            index = next(&mut buffer, &mut font_system, index, Motion::Next);
+            stops.push(index);
+        }
+        assert_eq!(stops, [1, after_joiner, 6, 7], "{shaping:?}");
+
+        let mut stops = Vec::new();
+        while index > 0 {
+            index = next(&mut buffer, &mut font_system, index, Motion::Previous);
+            stops.push(index);
+        }
+        assert_eq!(stops, [6, after_joiner, 1, 0], "{shaping:?}");
+    }
+}
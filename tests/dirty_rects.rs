@@ -0,0 +1,28 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn dirty_rects_cover_edited_line() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(200.0), Some(200.0));
+    buffer.set_text(
+        &mut font_system,
+        "first\nsecond\nthird",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert_eq!(buffer.take_dirty_rects().len(), 3);
+    assert!(buffer.take_dirty_rects().is_empty());
+
+    buffer.lines[1].set_text("edited", Default::default(), AttrsList::new(&Attrs::new()));
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().find(|run| run.line_i == 1).unwrap();
+    let expected = (0.0, run.line_top, 200.0, run.line_height);
+    assert_eq!(buffer.take_dirty_rects(), vec![expected]);
+    assert!(buffer.take_dirty_rects().is_empty());
+}
@@ -0,0 +1,43 @@
+use cosmic_text::{Attrs, Buffer, DuplicateFamilyStrategy, Family, FontSystem, Metrics, Shaping};
+
+fn shaped_font_id(font_system: &mut FontSystem) -> fontdb::ID {
+    let mut buffer = Buffer::new(font_system, Metrics::new(20.0, 30.0));
+    buffer.set_text(
+        font_system,
+        "Inter",
+        &Attrs::new().family(Family::Name("Inter")),
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs[0].font_id
+}
+
+// Tests choosing between two faces that both report the "Inter" family.
+#[test]
+fn duplicate_family_strategy() {
+    let path = std::path::Path::new("fonts/Inter-Regular.ttf");
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    font_system
+        .db_mut()
+        .load_font_data(std::fs::read(path).unwrap());
+    font_system.db_mut().load_font_file(path).unwrap();
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    assert_eq!(ids.len(), 2);
+
+    assert_eq!(
+        *font_system.duplicate_family_strategy(),
+        DuplicateFamilyStrategy::FirstLoaded
+    );
+    assert_eq!(shaped_font_id(&mut font_system), ids[0]);
+
+    font_system.set_duplicate_family_strategy(DuplicateFamilyStrategy::LastLoaded);
+    assert_eq!(shaped_font_id(&mut font_system), ids[1]);
+
+    font_system.set_duplicate_family_strategy(DuplicateFamilyStrategy::FirstLoaded);
+    assert_eq!(shaped_font_id(&mut font_system), ids[0]);
+
+    font_system
+        .set_duplicate_family_strategy(DuplicateFamilyStrategy::PreferPath(path.to_path_buf()));
+    assert_eq!(shaped_font_id(&mut font_system), ids[1]);
+}
@@ -0,0 +1,145 @@
+use cosmic_text::{
+    Action, Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Motion, Selection, Shaping,
+};
+
+mod common;
+
+fn text(editor: &Editor) -> String {
+    editor.with_buffer(|buffer| {
+        buffer
+            .lines
+            .iter()
+            .map(|line| line.text())
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+fn type_text(editor: &mut Editor, font_system: &mut FontSystem, text: &str) {
+    for c in text.chars() {
+        editor.action(font_system, Action::Insert(c));
+    }
+}
+
+#[test]
+fn undo_coalesces_typing() {
+    let mut font_system = common::font_system();
+    let mut editor = Editor::new(Buffer::new(&mut font_system, Metrics::new(14.0, 20.0)));
+    assert!(!editor.can_undo());
+    assert!(!editor.can_redo());
+
+    type_text(&mut editor, &mut font_system, "hello");
+    assert!(editor.can_undo());
+
+    // All characters are undone together
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "");
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+    assert!(!editor.can_undo());
+    assert!(editor.can_redo());
+
+    editor.redo(&mut font_system);
+    assert_eq!(text(&editor), "hello");
+    assert_eq!(editor.cursor(), Cursor::new(0, 5));
+    assert!(!editor.can_redo());
+
+    // A new change clears the undone changes
+    editor.undo(&mut font_system);
+    type_text(&mut editor, &mut font_system, "x");
+    assert!(!editor.can_redo());
+    assert_eq!(text(&editor), "x");
+}
+
+#[test]
+fn undo_steps_at_newlines_and_cursor_jumps() {
+    let mut font_system = common::font_system();
+    let mut editor = Editor::new(Buffer::new(&mut font_system, Metrics::new(14.0, 20.0)));
+
+    // A newline is its own step, and starts a new one
+    type_text(&mut editor, &mut font_system, "ab\ncd");
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "ab\n");
+    assert_eq!(editor.cursor(), Cursor::new(1, 0));
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "ab");
+    assert_eq!(editor.cursor(), Cursor::new(0, 2));
+
+    // Moving the cursor starts a new step, even if it moves back
+    editor.action(&mut font_system, Action::Motion(Motion::Left));
+    let before_xy = editor.cursor();
+    type_text(&mut editor, &mut font_system, "xy");
+    editor.action(&mut font_system, Action::Motion(Motion::Left));
+    editor.action(&mut font_system, Action::Motion(Motion::Right));
+    type_text(&mut editor, &mut font_system, "z");
+    assert_eq!(text(&editor), "axyzb");
+
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "axyb");
+    assert_eq!(editor.cursor(), Cursor::new(0, 3));
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "ab");
+    assert_eq!(editor.cursor(), before_xy);
+
+    // Deletions restore the exact cursor
+    editor.action(&mut font_system, Action::Motion(Motion::End));
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "a");
+    editor.action(&mut font_system, Action::Motion(Motion::Home));
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "ab");
+    assert_eq!(editor.cursor(), Cursor::new(0, 2));
+    editor.redo(&mut font_system);
+    assert_eq!(text(&editor), "a");
+    assert_eq!(editor.cursor(), Cursor::new(0, 1));
+}
+
+#[test]
+fn undo_replacing_selection_is_own_step() {
+    let mut font_system = common::font_system();
+    let mut editor = Editor::new(Buffer::new(&mut font_system, Metrics::new(14.0, 20.0)));
+
+    // A typed character replacing a selection is not added to the previous typing
+    type_text(&mut editor, &mut font_system, "abc");
+    editor.set_selection(Selection::Normal(Cursor::new(0, 0)));
+    type_text(&mut editor, &mut font_system, "x");
+    assert_eq!(text(&editor), "x");
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "abc");
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "");
+}
+
+#[test]
+fn direct_edits_clear_history() {
+    let mut font_system = common::font_system();
+    let mut editor = Editor::new(Buffer::new(&mut font_system, Metrics::new(14.0, 20.0)));
+
+    // Redrawing and shaping keep the history
+    type_text(&mut editor, &mut font_system, "ab");
+    editor.set_redraw(false);
+    editor.shape_as_needed(&mut font_system, false);
+    assert!(editor.can_undo());
+
+    editor.insert_string("c", None);
+    assert_eq!(text(&editor), "abc");
+    assert!(!editor.can_undo());
+
+    type_text(&mut editor, &mut font_system, "d");
+    editor.undo(&mut font_system);
+    assert!(editor.can_redo());
+    editor.delete_range(Cursor::new(0, 0), Cursor::new(0, 1));
+    assert!(!editor.can_redo());
+
+    editor.set_cursor(Cursor::new(0, 0));
+    type_text(&mut editor, &mut font_system, "e");
+    editor
+        .borrow_with(&mut font_system)
+        .with_buffer_mut(|buffer| buffer.set_text("other", &Attrs::new(), Shaping::Advanced));
+    assert!(!editor.can_undo());
+
+    type_text(&mut editor, &mut font_system, "f");
+    *editor.buffer_ref_mut() = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0)).into();
+    assert!(!editor.can_undo());
+    editor.undo(&mut font_system);
+    assert_eq!(text(&editor), "");
+}
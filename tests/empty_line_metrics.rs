@@ -0,0 +1,37 @@
+use cosmic_text::{Attrs, Buffer, EmptyLineMetrics, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn empty_line_height_follows_source() {
+    let mut font_system = common::font_system();
+
+    let defaults = Attrs::new().metrics(Metrics::new(24.0, 30.0));
+    let large = Attrs::new().metrics(Metrics::new(40.0, 50.0));
+    let small = Attrs::new().metrics(Metrics::new(10.0, 12.0));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_rich_text(
+        [("Large\n\n", large), ("small", small)],
+        &defaults,
+        Shaping::Advanced,
+        None,
+    );
+    assert_eq!(buffer.lines[1].text(), "");
+
+    let line_heights =
+        |buffer: &Buffer| -> Vec<f32> { buffer.layout_runs().map(|run| run.line_height).collect() };
+
+    assert_eq!(buffer.empty_line_metrics(), EmptyLineMetrics::Attrs);
+    assert_eq!(line_heights(&buffer), [50.0, 30.0, 12.0]);
+
+    buffer.set_empty_line_metrics(EmptyLineMetrics::Buffer);
+    assert_eq!(line_heights(&buffer), [50.0, 20.0, 12.0]);
+
+    buffer.set_empty_line_metrics(EmptyLineMetrics::PreviousLine);
+    assert_eq!(line_heights(&buffer), [50.0, 50.0, 12.0]);
+
+    buffer.set_empty_line_metrics(EmptyLineMetrics::Attrs);
+    assert_eq!(line_heights(&buffer), [50.0, 30.0, 12.0]);
+}
@@ -0,0 +1,62 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Style, Weight};
+
+/// Load the font at `path` and a copy of it with `weight` and `style`, returning both ids
+fn load_with_face(
+    font_system: &mut FontSystem,
+    path: &str,
+    weight: Weight,
+    style: Style,
+) -> (fontdb::ID, fontdb::ID) {
+    let db = font_system.db_mut();
+    db.load_font_data(std::fs::read(path).unwrap());
+    let mut face = db.faces().last().unwrap().clone();
+    let id = face.id;
+    face.weight = weight;
+    face.style = style;
+    (id, db.push_face_info(face))
+}
+
+#[test]
+fn fallback_face_matching_uses_closest_face() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let (inter_id, _) = load_with_face(
+        &mut font_system,
+        "fonts/Inter-Regular.ttf",
+        Weight::BOLD,
+        Style::Italic,
+    );
+    let (_, arabic_bold_id) = load_with_face(
+        &mut font_system,
+        "fonts/NotoSansArabic.ttf",
+        Weight::BOLD,
+        Style::Normal,
+    );
+    let family = font_system.db().face(inter_id).unwrap().families[0]
+        .0
+        .clone();
+    let attrs = Attrs::new()
+        .family(Family::Name(&family))
+        .weight(Weight::BOLD)
+        .style(Style::Italic);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut fallback_id = |font_system: &mut FontSystem| {
+        buffer.set_text(font_system, "Bold ب", &attrs, Shaping::Advanced);
+        let run = buffer.layout_runs().next().unwrap();
+        run.glyphs
+            .iter()
+            .find(|glyph| glyph.start == 5)
+            .unwrap()
+            .font_id
+    };
+
+    // The Arabic font has no italic face, so it is not used at all
+    assert!(!font_system.fallback_face_matching());
+    assert_ne!(fallback_id(&mut font_system), arabic_bold_id);
+
+    // Its bold face is the closest to bold italic
+    font_system.set_fallback_face_matching(true);
+    assert_eq!(fallback_id(&mut font_system), arabic_bold_id);
+    assert_eq!(font_system.font_for_char('ب', &attrs), Some(arabic_bold_id));
+}
@@ -0,0 +1,88 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+/// Height from descender to ascender, ascent and descent of a font in em units
+fn em_metrics(font_system: &mut FontSystem, id: fontdb::ID) -> (f32, f32, f32) {
+    let font = font_system.get_font(id).unwrap();
+    let face = font.rustybuzz();
+    let units_per_em = face.units_per_em() as f32;
+    let ascent = face.ascender() as f32 / units_per_em;
+    let descent = -face.descender() as f32 / units_per_em;
+    (ascent + descent, ascent, descent)
+}
+
+fn font_system() -> (FontSystem, fontdb::ID, String, fontdb::Weight) {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let base_id = font_system
+        .db_mut()
+        .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(
+            std::fs::read("fonts/FiraMono-Medium.ttf").unwrap(),
+        )))[0];
+    for path in ["fonts/NotoSans-Regular.ttf", "fonts/NotoSansHebrew.ttf"] {
+        font_system
+            .db_mut()
+            .load_font_data(std::fs::read(path).unwrap());
+    }
+    let face = font_system.db().face(base_id).unwrap();
+    let (base_family, base_weight) = (face.families[0].0.clone(), face.weight);
+    (font_system, base_id, base_family, base_weight)
+}
+
+#[test]
+fn fallback_glyphs_scaled_within_cluster() {
+    let (mut font_system, base_id, base_family, base_weight) = font_system();
+    let font_size = 20.0;
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(font_size, 30.0));
+    let attrs = Attrs::new()
+        .family(Family::Name(&base_family))
+        .weight(base_weight);
+    // The primary font has the base but not the combining dot below, so the cluster falls back
+    let text = "xa\u{323}";
+
+    buffer.set_text(&mut font_system, text, &attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let base = run.glyphs.iter().find(|g| g.start == 0).unwrap();
+    let fallback_id = run.glyphs.iter().find(|g| g.start == 1).unwrap().font_id;
+    assert_eq!(base.font_id, base_id);
+    assert_ne!(fallback_id, base_id);
+    assert!(run.glyphs.iter().all(|g| g.font_size == font_size));
+
+    font_system.set_fallback_metric_matching(true);
+    buffer.set_text(&mut font_system, text, &attrs, Shaping::Advanced);
+    let (base_extent, base_ascent, base_descent) = em_metrics(&mut font_system, base_id);
+    let scale = base_extent / em_metrics(&mut font_system, fallback_id).0;
+    let run = buffer.layout_runs().next().unwrap();
+    for glyph in run.glyphs {
+        if glyph.start == 0 {
+            assert_eq!(glyph.font_id, base_id);
+            assert_eq!(glyph.font_size, font_size);
+        } else {
+            assert_eq!(glyph.font_id, fallback_id);
+            assert!((glyph.font_size - font_size * scale).abs() < 1e-3);
+        }
+    }
+
+    // The cluster has the ascent and descent of the primary font
+    let layout = &buffer.lines[0].layout_opt().unwrap()[0];
+    assert!((layout.max_ascent - font_size * base_ascent).abs() < 1e-3);
+    assert!((layout.max_descent - font_size * base_descent).abs() < 1e-3);
+}
+
+#[test]
+fn fallback_clusters_keep_their_size() {
+    let (mut font_system, base_id, base_family, base_weight) = font_system();
+    let font_size = 20.0;
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(font_size, 30.0));
+    let attrs = Attrs::new()
+        .family(Family::Name(&base_family))
+        .weight(base_weight);
+
+    font_system.set_fallback_metric_matching(true);
+    buffer.set_text(&mut font_system, "a א", &attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let base = run.glyphs.iter().find(|g| g.start == 0).unwrap();
+    let fallback = run.glyphs.iter().find(|g| g.start == 2).unwrap();
+    assert_eq!(base.font_id, base_id);
+    assert_ne!(fallback.font_id, base_id);
+    assert!(run.glyphs.iter().all(|g| g.font_size == font_size));
+}
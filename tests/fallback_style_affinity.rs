@@ -0,0 +1,63 @@
+use common::{be16, TestFont};
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+mod common;
+
+/// Build a font named `family` mapping `c` to glyph 1, with a `GSUB` table listing `scripts`
+fn font(family: &str, c: char, scripts: &[&[u8; 4]]) -> Vec<u8> {
+    let count = scripts.len() as u16;
+    let mut gsub = Vec::new();
+    // Header with the feature and lookup lists, both empty, after the script list
+    be16(&mut gsub, &[1, 0, 10, 12 + 16 * count, 14 + 16 * count]);
+    // Script list with a default language system without features for each script
+    be16(&mut gsub, &[count]);
+    for (i, script) in scripts.iter().enumerate() {
+        gsub.extend_from_slice(*script);
+        be16(&mut gsub, &[2 + 6 * count + 10 * i as u16]);
+    }
+    for _ in scripts {
+        be16(&mut gsub, &[4, 0, 0, 0xFFFF, 0]);
+    }
+    be16(&mut gsub, &[0, 0]);
+
+    TestFont::new(family)
+        .glyph(None, 600, false)
+        .glyph(Some(c), 600, false)
+        .table(b"GSUB", gsub)
+        .build()
+}
+
+fn fallback_font_id(font_system: &mut FontSystem) -> fontdb::ID {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        font_system,
+        "ab",
+        &Attrs::new().family(Family::Name("Primary")),
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .find(|glyph| glyph.start == 1)
+        .unwrap()
+        .font_id
+}
+
+#[test]
+fn fallback_style_affinity_prefers_shared_scripts() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let db = font_system.db_mut();
+    db.load_font_data(font("Primary", 'a', &[b"cyrl", b"grek", b"latn"]));
+    db.load_font_data(font("Unrelated", 'b', &[b"arab"]));
+    db.load_font_data(font("Related", 'b', &[b"grek", b"latn"]));
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    assert!(!font_system.fallback_style_affinity());
+
+    // Both fallbacks cover the missing glyph, so the first loaded is used
+    assert_eq!(fallback_font_id(&mut font_system), ids[1]);
+
+    // Unless the one sharing more scripts with the primary font is preferred
+    font_system.set_fallback_style_affinity(true);
+    assert_eq!(fallback_font_id(&mut font_system), ids[2]);
+}
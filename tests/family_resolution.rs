@@ -0,0 +1,25 @@
+use cosmic_text::{Attrs, Family, Weight};
+
+mod common;
+
+#[test]
+fn family_resolved_exactly_detects_fallback() {
+    let mut font_system = common::font_system();
+    let family = font_system.db().faces().next().unwrap().families[0]
+        .0
+        .clone();
+
+    assert!(!font_system.family_resolved_exactly(&Attrs::new().family(Family::Name("Helvetica"))));
+    assert!(font_system.family_resolved_exactly(&Attrs::new().family(Family::Name(&family))));
+
+    // Other weights of an installed family still resolve to it
+    let bold = Attrs::new()
+        .family(Family::Name(&family))
+        .weight(Weight::BOLD);
+    assert!(font_system.family_resolved_exactly(&bold));
+
+    // Generic families resolve to the family configured in the database
+    assert!(!font_system.family_resolved_exactly(&Attrs::new().family(Family::Serif)));
+    font_system.db_mut().set_serif_family(family);
+    assert!(font_system.family_resolved_exactly(&Attrs::new().family(Family::Serif)));
+}
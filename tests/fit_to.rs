@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn fit_to_clamps_font_size() {
+    let mut font_system = common::font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("Hello", &Attrs::new(), Shaping::Advanced);
+
+    // Short text grows only up to the ceiling
+    let size = buffer.fit_to(1000.0, 1000.0, 8.0, 24.0, "…");
+    assert_eq!(size, 24.0);
+    assert_eq!(buffer.metrics().font_size, 24.0);
+    assert!((buffer.metrics().line_height - 24.0 * 20.0 / 14.0).abs() < 1e-3);
+
+    let size = buffer.fit_to(100.0, 30.0, 8.0, 48.0, "…");
+    assert!((8.0..48.0).contains(&size));
+    assert_eq!(buffer.lines[0].text(), "Hello");
+}
+
+#[test]
+fn fit_to_truncates_below_min_size() {
+    let mut font_system = common::font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let text = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+    buffer.set_text(&text, &Attrs::new(), Shaping::Advanced);
+
+    let (width, height) = (200.0, 40.0);
+    let size = buffer.fit_to(width, height, 12.0, 32.0, "…");
+    assert_eq!(size, 12.0);
+    assert_eq!(buffer.metrics().font_size, 12.0);
+
+    let truncated = buffer.lines[0].text().to_string();
+    assert!(truncated.ends_with('…'));
+    assert!(text.starts_with(truncated.trim_end_matches('…')));
+    assert!(truncated.len() < text.len());
+
+    let content_height: f32 = buffer.layout_runs().map(|run| run.line_height).sum();
+    assert!(content_height <= height);
+    assert!(buffer.layout_runs().all(|run| run.line_w <= width));
+}
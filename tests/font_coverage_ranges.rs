@@ -0,0 +1,23 @@
+mod common;
+
+#[test]
+fn font_coverage_ranges_basic_latin() {
+    let mut font_system = common::font_system();
+    let id = font_system.db().faces().next().unwrap().id;
+
+    let ranges = font_system.font_coverage_ranges(id);
+    assert!(!ranges.is_empty());
+
+    // Basic Latin printable characters are covered by one coalesced range
+    let basic_latin: Vec<_> = ranges
+        .iter()
+        .filter(|range| range.contains(&'A') || range.contains(&'~') || range.contains(&' '))
+        .collect();
+    assert_eq!(basic_latin.len(), 1);
+    assert!(*basic_latin[0].start() <= ' ' && *basic_latin[0].end() >= '~');
+
+    // Ranges are sorted and do not touch
+    for pair in ranges.windows(2) {
+        assert!(u32::from(*pair[0].end()) + 1 < u32::from(*pair[1].start()));
+    }
+}
@@ -0,0 +1,27 @@
+use cosmic_text::{Attrs, Buffer, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn font_for_char_matches_shaping() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let family = font_system.db().face(ids[0]).unwrap().families[0].0.clone();
+    let attrs = Attrs::new().family(Family::Name(&family));
+
+    // The primary font covers Latin, the Arabic font is used as fallback
+    assert_eq!(font_system.font_for_char('a', &attrs), Some(ids[0]));
+    assert_eq!(font_system.font_for_char('ب', &attrs), Some(ids[1]));
+    // Nothing covers this, so the missing glyph of the primary font is used
+    assert_eq!(font_system.font_for_char('\u{10FF0}', &attrs), Some(ids[0]));
+    // An explicit font is always used
+    let fixed = attrs.clone().font_id(ids[1]);
+    assert_eq!(font_system.font_for_char('a', &fixed), Some(ids[1]));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    for c in ['a', 'ب', '\u{10FF0}'] {
+        buffer.set_text(&mut font_system, &c.to_string(), &attrs, Shaping::Advanced);
+        let shaped = buffer.layout_runs().next().unwrap().glyphs[0].font_id;
+        assert_eq!(font_system.font_for_char(c, &attrs), Some(shaped));
+    }
+}
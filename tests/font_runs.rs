@@ -0,0 +1,47 @@
+use cosmic_text::{Attrs, Buffer, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn font_runs_override_font_resolution() {
+    let mut font_system = common::font_system_with(&[
+        common::INTER,
+        "fonts/FiraMono-Medium.ttf",
+        "fonts/NotoSansHebrew.ttf",
+    ]);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let [inter, fira, hebrew] = ids[..] else {
+        panic!("expected three fonts");
+    };
+    let family = font_system.db().face(inter).unwrap().families[0].0.clone();
+    let attrs = Attrs::new().family(Family::Name(&family));
+
+    let text = "Hello world\nשלום";
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, text, &attrs, Shaping::Advanced);
+    let resolved: Vec<_> = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter().map(|glyph| glyph.font_id))
+        .collect();
+    assert!(resolved[..11].iter().all(|&id| id == inter));
+    assert!(resolved[11..].iter().all(|&id| id == hebrew));
+
+    // Force the second word to the monospace font and the Hebrew line to the primary font, even
+    // though the Hebrew font is a better match
+    buffer.set_text_with_font_runs(
+        &mut font_system,
+        text,
+        &[(6..11, fira), (12..text.len(), inter)],
+        &attrs,
+        Shaping::Advanced,
+    );
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            let expected = match (run.line_i, glyph.start) {
+                (0, 6..) => fira,
+                _ => inter,
+            };
+            assert_eq!(glyph.font_id, expected, "glyph {glyph:?}");
+        }
+    }
+}
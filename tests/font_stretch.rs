@@ -0,0 +1,97 @@
+use cosmic_text::{Attrs, Family, FontSystem, Stretch, Weight};
+
+// Tests choosing between two faces of the "Inter" family that only differ by stretch.
+#[test]
+fn requested_stretch_wins() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let db = font_system.db_mut();
+    db.load_font_data(font);
+    let mut condensed = db.faces().next().unwrap().clone();
+    condensed.stretch = Stretch::Condensed;
+    let condensed_id = db.push_face_info(condensed);
+    let normal_id = font_system.db().faces().next().unwrap().id;
+    let family = font_system.db().face(normal_id).unwrap().families[0]
+        .0
+        .clone();
+
+    let attrs = Attrs::new().family(Family::Name(&family));
+    for _ in 0..2 {
+        assert_eq!(
+            font_system.font_for_char('a', &attrs.clone().stretch(Stretch::Condensed)),
+            Some(condensed_id)
+        );
+        assert_eq!(font_system.font_for_char('a', &attrs), Some(normal_id));
+    }
+}
+
+// A requested stretch no face has uses the face of the family with the closest stretch.
+#[test]
+fn closest_stretch_wins() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let db = font_system.db_mut();
+    db.load_font_data(font);
+    let normal = db.faces().next().unwrap().clone();
+    let normal_id = normal.id;
+    let mut semi_condensed = normal.clone();
+    semi_condensed.stretch = Stretch::SemiCondensed;
+    let semi_condensed_id = db.push_face_info(semi_condensed);
+    let mut extra_expanded = normal.clone();
+    extra_expanded.stretch = Stretch::ExtraExpanded;
+    let extra_expanded_id = db.push_face_info(extra_expanded);
+    let family = normal.families[0].0.clone();
+
+    let attrs = Attrs::new().family(Family::Name(&family));
+    let condensed = attrs.clone().stretch(Stretch::Condensed);
+    assert_eq!(
+        font_system.font_for_char('a', &condensed),
+        Some(semi_condensed_id)
+    );
+    let ids: Vec<_> = font_system
+        .matches_for_attrs(&condensed)
+        .iter()
+        .map(|face| face.id)
+        .collect();
+    assert_eq!(ids, [semi_condensed_id, normal_id, extra_expanded_id]);
+
+    let ultra_expanded = attrs.stretch(Stretch::UltraExpanded);
+    assert_eq!(
+        font_system.font_for_char('a', &ultra_expanded),
+        Some(extra_expanded_id)
+    );
+}
+
+// A face of the requested stretch is preferred over a face of the requested weight.
+#[test]
+fn requested_stretch_wins_over_weight() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let db = font_system.db_mut();
+    db.load_font_data(font);
+    let normal = db.faces().next().unwrap().clone();
+    let mut condensed = normal.clone();
+    condensed.stretch = Stretch::Condensed;
+    let condensed_id = db.push_face_info(condensed);
+    let mut medium = normal.clone();
+    medium.weight = Weight::MEDIUM;
+    let medium_id = db.push_face_info(medium);
+    db.remove_face(normal.id);
+    let family = normal.families[0].0.clone();
+
+    let attrs = Attrs::new().family(Family::Name(&family));
+    assert_eq!(font_system.font_for_char('a', &attrs), Some(medium_id));
+    let ids: Vec<_> = font_system
+        .matches_for_attrs(&attrs)
+        .iter()
+        .map(|face| face.id)
+        .collect();
+    assert_eq!(ids, [medium_id]);
+    assert_eq!(
+        font_system.font_for_char('a', &attrs.stretch(Stretch::Condensed)),
+        Some(condensed_id)
+    );
+}
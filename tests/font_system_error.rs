@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use cosmic_text::{FontSystem, FontSystemError};
+
+#[test]
+fn try_new_requires_fonts() {
+    let empty = FontSystem::try_new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    assert_eq!(empty.unwrap_err(), FontSystemError::NoFonts);
+
+    // Data that is not a font loads no usable faces
+    let mut db = fontdb::Database::new();
+    db.load_font_data(b"not a font".to_vec());
+    let invalid = FontSystem::try_new_with_locale_and_db("en-US".into(), db);
+    assert_eq!(invalid.unwrap_err(), FontSystemError::NoFonts);
+
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let mut db = fontdb::Database::new();
+    db.load_font_data(font.clone());
+    let font_system = FontSystem::try_new_with_locale_and_db("en-US".into(), db).unwrap();
+    assert_eq!(font_system.db().len(), 1);
+
+    let source = fontdb::Source::Binary(Arc::new(font));
+    let font_system = FontSystem::try_new_with_fonts([source]).unwrap();
+    assert!(!font_system.db().is_empty());
+}
@@ -0,0 +1,86 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystemError, Metrics, Shaping, Style};
+
+mod common;
+
+fn glyphs(buffer: &Buffer) -> Vec<(fontdb::ID, u16)> {
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| (glyph.font_id, glyph.glyph_id))
+        .collect()
+}
+
+#[test]
+fn font_whitelist_excludes_other_fonts() {
+    let mut font_system = common::font_system_with(&[
+        common::INTER,
+        "fonts/FiraMono-Medium.ttf",
+        "fonts/NotoSansArabic.ttf",
+    ]);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let family = font_system.db().face(ids[0]).unwrap().families[0].0.clone();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "Hi مرحبا",
+        &Attrs::new().family(Family::Name(&family)),
+        Shaping::Advanced,
+    );
+    assert!(glyphs(&buffer).iter().any(|&(id, _)| id == ids[2]));
+
+    // The Arabic text is drawn as missing glyphs instead of using the excluded font
+    buffer
+        .set_font_whitelist(&mut font_system, Some(ids[..2].to_vec()))
+        .unwrap();
+    assert_eq!(buffer.font_whitelist(), Some(&ids[..2]));
+    let whitelisted = glyphs(&buffer);
+    assert!(whitelisted.iter().all(|&(id, _)| id != ids[2]));
+    assert!(whitelisted.iter().any(|&(_, glyph_id)| glyph_id == 0));
+    assert!(whitelisted.iter().any(|&(_, glyph_id)| glyph_id != 0));
+
+    // Other buffers are not affected
+    let mut other = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    other.set_text(&mut font_system, "مرحبا", &Attrs::new(), Shaping::Advanced);
+    assert!(glyphs(&other).iter().all(|&(id, _)| id == ids[2]));
+
+    buffer.set_font_whitelist(&mut font_system, None).unwrap();
+    assert!(glyphs(&buffer).iter().any(|&(id, _)| id == ids[2]));
+}
+
+#[test]
+fn empty_font_whitelist_is_rejected() {
+    let mut font_system = common::font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "Hi", &Attrs::new(), Shaping::Advanced);
+    let before = glyphs(&buffer);
+
+    assert_eq!(
+        buffer.set_font_whitelist(&mut font_system, Some(Vec::new())),
+        Err(FontSystemError::NoFonts)
+    );
+    assert_eq!(buffer.font_whitelist(), None);
+    assert_eq!(glyphs(&buffer), before);
+}
+
+#[test]
+fn font_whitelist_without_matching_style_uses_whitelisted_font() {
+    let mut font_system = common::font_system();
+    let db = font_system.db_mut();
+    let mut italic = db.faces().next().unwrap().clone();
+    italic.style = Style::Italic;
+    let italic_id = db.push_face_info(italic);
+
+    // The only whitelisted face does not match the upright attributes, so it draws the text
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer
+        .set_font_whitelist(&mut font_system, Some(vec![italic_id]))
+        .unwrap();
+    buffer.set_text(&mut font_system, "Hi", &Attrs::new(), Shaping::Advanced);
+    let whitelisted = glyphs(&buffer);
+    assert_eq!(whitelisted.len(), 2);
+    assert!(whitelisted.iter().all(|&(id, _)| id == italic_id));
+
+    buffer.set_text(&mut font_system, "Hi", &Attrs::new(), Shaping::Basic);
+    assert!(glyphs(&buffer).iter().all(|&(id, _)| id == italic_id));
+}
@@ -0,0 +1,32 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+mod common;
+
+fn glyph_ids(font_system: &mut FontSystem, text: &str, attrs: &Attrs) -> Vec<u16> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+}
+
+#[test]
+fn forced_script_controls_contextual_forms() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansArabic.ttf"]);
+
+    let isolated = glyph_ids(&mut font_system, "ب", &Attrs::new())[0];
+
+    // Shaped as Latin, the Arabic shaper does not run and both letters stay isolated
+    let latin = glyph_ids(&mut font_system, "بب", &Attrs::new().script(*b"latn"));
+    assert_eq!(latin, [isolated, isolated]);
+
+    // Forcing Arabic applies the initial and final forms
+    let arabic_attrs = Attrs::new().script(*b"arab");
+    let arabic = glyph_ids(&mut font_system, "بب", &arabic_attrs);
+    assert_eq!(arabic.len(), 2);
+    assert!(arabic.iter().all(|&glyph_id| glyph_id != isolated));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "بب", &arabic_attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    assert_eq!(&run.shaping_plan().unwrap().script, b"arab");
+}
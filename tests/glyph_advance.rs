@@ -0,0 +1,41 @@
+use cosmic_text::{Attrs, Buffer, Family, Metrics, Shaping, Wrap};
+
+mod common;
+
+// Tests that overriding the advance of one glyph moves the glyphs after it and widens the line,
+// without reshaping.
+#[test]
+fn set_glyph_advance_reflows_line() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 30.0));
+    buffer.set_wrap(&mut font_system, Wrap::None);
+    buffer.set_text(
+        &mut font_system,
+        "AVATAR",
+        &Attrs::new().family(Family::Name("Inter")),
+        Shaping::Advanced,
+    );
+
+    let snapshot = |buffer: &Buffer| {
+        let run = buffer.layout_runs().next().unwrap();
+        let xs: Vec<f32> = run.glyphs.iter().map(|glyph| glyph.x).collect();
+        (xs, run.glyphs[1].w, run.line_w)
+    };
+    let (xs_before, advance_before, line_w_before) = snapshot(&buffer);
+    let shaped_line_count = font_system.shaped_line_count();
+
+    buffer.set_redraw(false);
+    assert!(buffer.set_glyph_advance(0, 0, 1, advance_before + 5.0));
+    assert!(buffer.redraw());
+    assert!(!buffer.set_glyph_advance(0, 0, 100, 1.0));
+
+    let (xs_after, advance_after, line_w_after) = snapshot(&buffer);
+    assert_eq!(advance_after, advance_before + 5.0);
+    assert_eq!(line_w_after, line_w_before + 5.0);
+    assert_eq!(xs_after[..2], xs_before[..2]);
+    for (after, before) in xs_after[2..].iter().zip(&xs_before[2..]) {
+        assert_eq!(*after, before + 5.0);
+    }
+    assert_eq!(font_system.shaped_line_count(), shaped_line_count);
+}
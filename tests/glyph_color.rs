@@ -0,0 +1,39 @@
+use cosmic_text::{Attrs, Buffer, Color, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn glyphs_keep_span_colors() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansHebrew.ttf"]);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let family = font_system.db().face(ids[0]).unwrap().families[0].0.clone();
+
+    let red = Color::rgb(0xFF, 0, 0);
+    let blue = Color::rgb(0, 0, 0xFF);
+    let attrs = Attrs::new().family(Family::Name(&family));
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("ab", attrs.clone().color(red)),
+            // Shaped with the fallback font
+            ("של", attrs.clone().color(blue)),
+            ("cd", attrs.clone()),
+        ],
+        &attrs,
+        Shaping::Advanced,
+        None,
+    );
+
+    let default = Color::rgb(0, 0, 0);
+    let mut glyphs: Vec<_> = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .collect();
+    glyphs.sort_by_key(|glyph| glyph.start);
+    let colors: Vec<Color> = glyphs.iter().map(|glyph| glyph.color_or(default)).collect();
+    assert_eq!(colors, [red, red, blue, blue, default, default]);
+    assert!(glyphs[2..4].iter().all(|glyph| glyph.font_id == ids[1]));
+    assert_eq!(glyphs[0].color_opt, Some(red));
+    assert_eq!(glyphs[4].color_opt, None);
+}
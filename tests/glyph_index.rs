@@ -0,0 +1,17 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn glyph_index_matches_shaping() {
+    let mut font_system = common::font_system();
+    let id = font_system.db().faces().next().unwrap().id;
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 30.0));
+    buffer.set_text(&mut font_system, "A", &Attrs::new(), Shaping::Basic);
+    let shaped = buffer.layout_runs().next().unwrap().glyphs[0].glyph_id;
+
+    assert_ne!(shaped, 0);
+    assert_eq!(font_system.glyph_index(id, 'A'), Some(shaped));
+    assert_eq!(font_system.glyph_index(id, '\u{E000}'), None);
+}
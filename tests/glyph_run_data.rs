@@ -0,0 +1,54 @@
+use cosmic_text::{Attrs, Buffer, Color, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn glyph_run_data_batches() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/FiraMono-Medium.ttf"]);
+    let inter = Attrs::new().family(Family::Name("Inter"));
+    let big = inter.clone().metrics(Metrics::new(30.0, 40.0));
+    let red = Color::rgb(0xFF, 0, 0);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("small ", inter.clone()),
+            ("big", big),
+            (
+                " mono\nsmall",
+                Attrs::new().family(Family::Monospace).color(red),
+            ),
+        ],
+        &inter,
+        Shaping::Advanced,
+        None,
+    );
+
+    let black = Color::rgb(0, 0, 0);
+    let data = buffer.glyph_run_data(black);
+    let count = buffer
+        .layout_runs()
+        .map(|run| run.glyphs.len())
+        .sum::<usize>();
+    assert_eq!(data.glyph_ids.len(), count);
+    assert_eq!(data.font_ids.len(), count);
+    assert_eq!(data.xs.len(), count);
+    assert_eq!(data.ys.len(), count);
+    assert_eq!(data.font_sizes.len(), count);
+    assert_eq!(data.colors.len(), count);
+    assert!(data.colors.contains(&red) && data.colors.contains(&black));
+    assert!(data.batches.len() >= 2);
+
+    // Every glyph is in exactly one batch, with a matching font id and size
+    let mut seen = vec![false; count];
+    for batch in data.batches.iter() {
+        for &index in batch.indices.iter() {
+            assert!(!seen[index]);
+            seen[index] = true;
+            assert_eq!(data.font_ids[index], batch.font_id);
+            assert_eq!(data.font_sizes[index], batch.font_size);
+        }
+    }
+    assert!(seen.into_iter().all(|seen| seen));
+}
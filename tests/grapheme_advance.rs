@@ -0,0 +1,51 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+use unicode_segmentation::UnicodeSegmentation;
+
+mod common;
+
+#[test]
+fn grapheme_advances_follow_shaping() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSans-Regular.ttf"]);
+    let id = font_system.db().faces().next().unwrap().id;
+
+    let font_size = 20.0;
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(font_size, font_size));
+    let text = "AVA fit e\u{301}";
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+
+    // The advances of all graphemes add up to the line width
+    let total: f32 = text
+        .grapheme_indices(true)
+        .map(|(i, _)| run.grapheme_advance(i).unwrap())
+        .sum();
+    assert!((total - run.line_w).abs() < 0.01, "{total} {}", run.line_w);
+
+    // The kerned advance is reported for the first grapheme of a kerned pair
+    let a = font_system.glyph_index(id, 'A').unwrap();
+    let v = font_system.glyph_index(id, 'V').unwrap();
+    let kerning = font_system.kerning(id, a, v, font_size);
+    assert!(kerning < 0.0, "{kerning}");
+    let kerned = run.grapheme_advance(0).unwrap();
+
+    let mut alone = Buffer::new(&mut font_system, Metrics::new(font_size, font_size));
+    alone.set_text(&mut font_system, "A", &Attrs::new(), Shaping::Advanced);
+    let advance = alone
+        .layout_runs()
+        .next()
+        .unwrap()
+        .grapheme_advance(0)
+        .unwrap();
+    assert!(
+        (kerned - advance - kerning).abs() < 0.01,
+        "{kerned} {advance}"
+    );
+
+    // A ligature is split between its graphemes, and a combining mark belongs to its base
+    let fi = run.glyphs.iter().find(|glyph| glyph.start == 4).unwrap();
+    assert_eq!(fi.end, 6);
+    assert_eq!(run.grapheme_advance(4), Some(fi.w / 2.0));
+    assert_eq!(run.grapheme_advance(5), Some(fi.w / 2.0));
+    assert_eq!(run.grapheme_advance(9), run.grapheme_advance(8));
+    assert_eq!(run.grapheme_advance(text.len()), None);
+}
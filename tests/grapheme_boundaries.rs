@@ -0,0 +1,40 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+fn cluster_starts(buffer: &mut Buffer, font_system: &mut FontSystem) -> Vec<usize> {
+    let shape = buffer.line_shape(font_system, 0).unwrap();
+    let mut starts: Vec<usize> = shape
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.glyphs.iter())
+        .map(|glyph| glyph.start)
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    starts
+}
+
+#[test]
+fn grapheme_boundaries_match_glyph_clusters() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+
+    // An emoji ZWJ sequence and a Thai consonant with two marks, each a single grapheme
+    for (text, expected) in [
+        (
+            "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}b",
+            vec![0, 1, 19, 20],
+        ),
+        ("x\u{0E01}\u{0E35}\u{0E48}y", vec![0, 1, 10, 11]),
+    ] {
+        buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+
+        let boundaries = buffer.grapheme_boundaries(&mut font_system, 0).unwrap();
+        assert_eq!(boundaries, expected);
+
+        let starts = cluster_starts(&mut buffer, &mut font_system);
+        for i in &boundaries[..boundaries.len() - 1] {
+            assert!(starts.contains(i), "{text:?}: {i} is not a cluster start");
+        }
+    }
+}
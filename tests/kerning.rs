@@ -0,0 +1,29 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn kerning_matches_shaped_advances() {
+    let mut font_system = common::font_system();
+    let id = font_system.db().faces().next().unwrap().id;
+
+    let a = font_system.glyph_index(id, 'A').unwrap();
+    let v = font_system.glyph_index(id, 'V').unwrap();
+    let h = font_system.glyph_index(id, 'H').unwrap();
+
+    let font_size = 20.0;
+    let kerning = font_system.kerning(id, a, v, font_size);
+    assert!(kerning < 0.0, "{kerning}");
+    assert_eq!(font_system.kerning(id, h, h, font_size), 0.0);
+
+    // The kerning is applied to the advance of the first glyph when shaping
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(font_size, font_size));
+    buffer.set_text(&mut font_system, "A", &Attrs::new(), Shaping::Advanced);
+    let alone = buffer.layout_runs().next().unwrap().glyphs[0].w;
+    buffer.set_text(&mut font_system, "AV", &Attrs::new(), Shaping::Advanced);
+    let kerned = buffer.layout_runs().next().unwrap().glyphs[0].w;
+    assert!(
+        (kerned - alone - kerning).abs() < 0.01,
+        "{kerned} {alone} {kerning}"
+    );
+}
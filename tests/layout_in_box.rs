@@ -0,0 +1,52 @@
+use cosmic_text::{Align, Attrs, Buffer, Metrics, Shaping, VerticalAlign};
+
+mod common;
+
+#[test]
+fn layout_in_box_vertical_align() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo\nthree",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let (box_width, box_height) = (200.0, 300.0);
+    let content_height = 3.0 * 20.0;
+
+    let (x, y, w, h) = buffer.layout_in_box(
+        &mut font_system,
+        box_width,
+        box_height,
+        Some(Align::Center),
+        VerticalAlign::Center,
+    );
+    assert_eq!(y, (box_height - content_height) / 2.0);
+    assert_eq!(h, content_height);
+    assert!(x > 0.0 && x + w < box_width);
+
+    let tops: Vec<f32> = buffer.layout_runs().map(|run| run.line_top).collect();
+    assert_eq!(tops, [y, y + 20.0, y + 40.0]);
+
+    let (_, y, _, _) = buffer.layout_in_box(
+        &mut font_system,
+        box_width,
+        box_height,
+        None,
+        VerticalAlign::Bottom,
+    );
+    assert_eq!(y, box_height - content_height);
+    assert_eq!(buffer.layout_runs().next().unwrap().line_top, y);
+
+    let (_, y, _, _) = buffer.layout_in_box(
+        &mut font_system,
+        box_width,
+        box_height,
+        None,
+        VerticalAlign::Top,
+    );
+    assert_eq!(y, 0.0);
+}
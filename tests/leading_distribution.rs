@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, Buffer, LeadingDistribution, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn leading_distribution_moves_baseline() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 40.0));
+    buffer.set_text(
+        &mut font_system,
+        "Hello\nworld",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    assert_eq!(
+        buffer.leading_distribution(),
+        LeadingDistribution::Proportional
+    );
+
+    let line_ys =
+        |buffer: &Buffer| -> Vec<f32> { buffer.layout_runs().map(|run| run.line_y).collect() };
+    let proportional = line_ys(&buffer);
+
+    buffer.set_redraw(false);
+    buffer.set_leading_distribution(LeadingDistribution::BelowOnly);
+    assert!(buffer.redraw());
+    let below = line_ys(&buffer);
+
+    buffer.set_leading_distribution(LeadingDistribution::AboveOnly);
+    let above = line_ys(&buffer);
+
+    // Half of the leading moves between the modes, for every line
+    let half_leading = proportional[0] - below[0];
+    assert!(half_leading > 0.0);
+    for i in 0..2 {
+        assert!((proportional[i] - below[i] - half_leading).abs() < 1e-3);
+        assert!((above[i] - proportional[i] - half_leading).abs() < 1e-3);
+    }
+
+    // Lines stay one line height apart
+    assert_eq!(below[1] - below[0], 40.0);
+    assert_eq!(above[1] - above[0], 40.0);
+}
@@ -0,0 +1,39 @@
+use cosmic_text::{
+    Attrs, Buffer, FeatureTag, FontFeatures, FontSystem, Ligatures, Metrics, Shaping,
+};
+
+mod common;
+
+fn glyph_count(font_system: &mut FontSystem, attrs: &Attrs) -> usize {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, "fi", attrs, Shaping::Advanced);
+    buffer.layout_runs().next().unwrap().glyphs.len()
+}
+
+#[test]
+fn ligatures_toggle_common_ligatures() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSans-Regular.ttf"]);
+
+    assert_eq!(glyph_count(&mut font_system, &Attrs::new()), 1);
+
+    let no_common = Ligatures {
+        common: false,
+        ..Ligatures::default()
+    };
+    assert_eq!(
+        glyph_count(&mut font_system, &Attrs::new().ligatures(no_common)),
+        2
+    );
+    assert_eq!(
+        glyph_count(&mut font_system, &Attrs::new().ligatures(Ligatures::NONE)),
+        2
+    );
+
+    // Font features take precedence over ligatures
+    let mut font_features = FontFeatures::new();
+    font_features.enable(FeatureTag::STANDARD_LIGATURES);
+    let attrs = Attrs::new()
+        .ligatures(Ligatures::NONE)
+        .font_features(font_features);
+    assert_eq!(glyph_count(&mut font_system, &attrs), 1);
+}
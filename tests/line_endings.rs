@@ -0,0 +1,80 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, LineEnding, LineEndingPolicy, Metrics, Shaping};
+
+const MIXED: &str = "one\r\ntwo\nthree\rfour\r\n\r\nsix";
+
+fn read_back(buffer: &Buffer) -> String {
+    buffer
+        .lines
+        .iter()
+        .map(|line| format!("{}{}", line.text(), line.ending().as_str()))
+        .collect()
+}
+
+fn endings(buffer: &Buffer) -> Vec<LineEnding> {
+    buffer.lines.iter().map(|line| line.ending()).collect()
+}
+
+#[test]
+fn mixed_line_endings_round_trip() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let attrs = Attrs::new();
+
+    buffer.set_text(&mut font_system, MIXED, &attrs, Shaping::Advanced);
+    assert_eq!(buffer.lines.len(), 6);
+    assert_eq!(read_back(&buffer), MIXED);
+
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("one\r\ntwo\nthr", attrs.clone()),
+            ("ee\rfour\r\n\r\nsix", attrs.clone()),
+        ],
+        &attrs,
+        Shaping::Advanced,
+        None,
+    );
+    assert_eq!(buffer.lines.len(), 6);
+    assert_eq!(
+        buffer
+            .lines
+            .iter()
+            .map(|line| line.text())
+            .collect::<Vec<_>>(),
+        ["one", "two", "three", "four", "", "six"]
+    );
+    assert_eq!(read_back(&buffer), MIXED);
+}
+
+#[test]
+fn line_ending_policy_normalizes() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let attrs = Attrs::new();
+
+    buffer.set_line_ending_policy(LineEndingPolicy::CrLf);
+    buffer.set_text(&mut font_system, MIXED, &attrs, Shaping::Advanced);
+    assert_eq!(read_back(&buffer), "one\r\ntwo\r\nthree\r\nfour\r\n\r\nsix");
+
+    buffer.set_rich_text(
+        &mut font_system,
+        [(MIXED, attrs.clone())],
+        &attrs,
+        Shaping::Advanced,
+        None,
+    );
+    assert_eq!(read_back(&buffer), "one\r\ntwo\r\nthree\r\nfour\r\n\r\nsix");
+
+    buffer.set_line_ending_policy(LineEndingPolicy::Lf);
+    assert_eq!(
+        endings(&buffer),
+        [
+            LineEnding::Lf,
+            LineEnding::Lf,
+            LineEnding::Lf,
+            LineEnding::Lf,
+            LineEnding::Lf,
+            LineEnding::None
+        ]
+    );
+}
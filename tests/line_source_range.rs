@@ -0,0 +1,51 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping, Wrap};
+
+mod common;
+
+#[test]
+fn line_source_range_maps_wrapped_lines() {
+    let mut font_system = common::font_system();
+
+    let text = "The quick   brown fox jumps over the lazy dog";
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    buffer.set_size(&mut font_system, Some(80.0), None);
+    buffer.set_text(
+        &mut font_system,
+        &format!("{text}\nsecond"),
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    let wrapped = runs.iter().filter(|run| run.line_i == 0).count();
+    assert!(wrapped > 2);
+
+    let sources: Vec<_> = (0..runs.len())
+        .map(|visual_line| buffer.line_source_range(visual_line).unwrap())
+        .collect();
+    assert_eq!(buffer.line_source_range(runs.len()), None);
+
+    // Each visual line maps to the bytes of its glyphs
+    for (run, source) in runs.iter().zip(&sources) {
+        assert_eq!(source.line_i, run.line_i);
+        let start = run.glyphs.iter().map(|glyph| glyph.start).min().unwrap();
+        let end = run.glyphs.iter().map(|glyph| glyph.end).max().unwrap();
+        assert_eq!(source.range, start..end);
+    }
+
+    // Wrapped lines follow each other without overlapping, with only trimmed whitespace between
+    assert_eq!(sources[0].range.start, 0);
+    assert!(!sources[0].trimmed_start);
+    for pair in sources[..wrapped].windows(2) {
+        assert!(pair[0].range.end <= pair[1].range.start);
+        let between = &text[pair[0].range.end..pair[1].range.start];
+        assert!(between.chars().all(char::is_whitespace));
+        assert_eq!(pair[0].trimmed_end, !between.is_empty());
+        assert_eq!(pair[1].trimmed_start, !between.is_empty());
+    }
+    assert_eq!(sources[wrapped - 1].range.end, text.len());
+
+    assert_eq!(sources[wrapped].line_i, 1);
+    assert_eq!(sources[wrapped].range, 0.."second".len());
+}
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use cosmic_text::{Attrs, FontSystem, Style};
+
+#[test]
+fn load_font_source_keeps_unaffected_matches() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    let mono_ids = font_system.load_font_source(fontdb::Source::Binary(Arc::new(font)));
+    assert_eq!(mono_ids.len(), 1);
+
+    let normal = Attrs::new();
+    let italic = Attrs::new().style(Style::Italic);
+    let normal_matches = font_system.get_font_matches(&normal);
+    let italic_matches = font_system.get_font_matches(&italic);
+    assert_eq!(normal_matches.len(), 1);
+    assert!(italic_matches.is_empty());
+
+    // Loading a normal face only invalidates the matches it could be part of
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let inter_ids = font_system.load_font_source(fontdb::Source::Binary(Arc::new(font)));
+    assert_eq!(inter_ids.len(), 1);
+    assert!(Arc::ptr_eq(
+        &italic_matches,
+        &font_system.get_font_matches(&italic)
+    ));
+    let new_normal_matches = font_system.get_font_matches(&normal);
+    assert!(!Arc::ptr_eq(&normal_matches, &new_normal_matches));
+    assert_eq!(new_normal_matches.len(), 2);
+}
@@ -0,0 +1,43 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn logical_glyphs_keep_joined_forms_in_logical_order() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansArabic.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "ب", &Attrs::new(), Shaping::Advanced);
+    let isolated = buffer.layout_runs().next().unwrap().glyphs[0].glyph_id;
+
+    buffer.set_text(&mut font_system, "ببب", &Attrs::new(), Shaping::Advanced);
+    let visual = buffer.layout_runs().next().unwrap().glyphs.to_vec();
+    let logical = buffer.line_logical_glyphs(&mut font_system, 0).unwrap();
+
+    // Laid out glyphs are placed right to left
+    let mut visual_starts: Vec<(f32, usize)> =
+        visual.iter().map(|glyph| (glyph.x, glyph.start)).collect();
+    visual_starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let visual_starts: Vec<usize> = visual_starts.into_iter().map(|(_, start)| start).collect();
+    assert_eq!(visual_starts, [4, 2, 0]);
+
+    // Logical glyphs are not, but keep the initial, medial and final forms
+    let logical_starts: Vec<usize> = logical.iter().map(|glyph| glyph.start).collect();
+    assert_eq!(logical_starts, [0, 2, 4]);
+    assert!(logical.iter().all(|glyph| glyph.glyph_id != isolated));
+    for glyph in &logical {
+        let shaped = visual
+            .iter()
+            .find(|other| other.start == glyph.start)
+            .unwrap();
+        assert_eq!(glyph.glyph_id, shaped.glyph_id);
+        assert_eq!(glyph.w, shaped.w);
+        assert!(glyph.level.is_rtl());
+    }
+
+    // Each glyph follows the previous one from the left
+    assert_eq!(logical[0].x, 0.0);
+    for pair in logical.windows(2) {
+        assert_eq!(pair[1].x, pair[0].x + pair[0].w);
+    }
+}
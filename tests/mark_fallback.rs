@@ -0,0 +1,63 @@
+use common::TestFont;
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, MarkFallback, Metrics, Shaping};
+
+mod common;
+
+/// Build a font named `family` mapping each of `chars` to the glyphs from 1, with marks having
+/// no advance
+fn font(family: &str, chars: &[char]) -> Vec<u8> {
+    chars
+        .iter()
+        .fold(TestFont::new(family).glyph(None, 600, false), |font, &c| {
+            font.glyph(Some(c), if c.is_ascii() { 600 } else { 0 }, false)
+        })
+        .build()
+}
+
+fn shape_glyphs(font_system: &mut FontSystem) -> Vec<(fontdb::ID, u16, usize, usize)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        font_system,
+        "A\u{0301}",
+        &Attrs::new().family(Family::Name("Base")),
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .map(|glyph| (glyph.font_id, glyph.glyph_id, glyph.start, glyph.end))
+        .collect()
+}
+
+#[test]
+fn mark_fallback_drops_uncovered_marks() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    font_system.db_mut().load_font_data(font("Base", &['A']));
+    let base_id = font_system.db().faces().next().unwrap().id;
+    assert_eq!(font_system.mark_fallback(), MarkFallback::Allow);
+
+    // By default, the mark only the base font lacks is drawn as a missing glyph
+    assert_eq!(
+        shape_glyphs(&mut font_system),
+        [(base_id, 1, 0, 3), (base_id, 0, 0, 3)]
+    );
+
+    // Dropping it keeps the base glyph spanning the whole cluster
+    font_system.set_mark_fallback(MarkFallback::DropIfNoBaseCoverage);
+    assert_eq!(shape_glyphs(&mut font_system), [(base_id, 1, 0, 3)]);
+
+    // A font covering the base and the mark together is still used
+    font_system
+        .db_mut()
+        .load_font_data(font("Marks", &['A', '\u{0301}']));
+    let marks_id = font_system.db().faces().nth(1).unwrap().id;
+    assert_eq!(
+        shape_glyphs(&mut font_system),
+        [(marks_id, 1, 0, 3), (marks_id, 2, 0, 3)]
+    );
+
+    // Unless marks never fall back
+    font_system.set_mark_fallback(MarkFallback::Never);
+    assert_eq!(shape_glyphs(&mut font_system), [(base_id, 1, 0, 3)]);
+}
@@ -0,0 +1,28 @@
+use cosmic_text::{Attrs, FontSystem, Style, Weight};
+
+mod common;
+
+#[test]
+fn matches_for_attrs_sorted_like_keys() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/FiraMono-Medium.ttf"]);
+
+    // The closest weight comes first
+    let weights = |font_system: &mut FontSystem, weight| -> Vec<u16> {
+        font_system
+            .matches_for_attrs(&Attrs::new().weight(Weight(weight)))
+            .iter()
+            .map(|face| face.weight.0)
+            .collect()
+    };
+    assert_eq!(weights(&mut font_system, 400), [400, 500]);
+    assert_eq!(weights(&mut font_system, 600), [500, 400]);
+
+    let attrs = Attrs::new().weight(Weight(600));
+    let keys = font_system.get_font_matches(&attrs);
+    let faces = font_system.matches_for_attrs(&attrs);
+    assert_eq!(faces.len(), keys.len());
+    assert_eq!(faces[0].post_script_name, "FiraMono-Medium");
+
+    let italic = Attrs::new().style(Style::Italic);
+    assert!(font_system.matches_for_attrs(&italic).is_empty());
+}
@@ -0,0 +1,31 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+mod common;
+
+fn shape_glyphs(font_system: &mut FontSystem, text: &str) -> Vec<(u16, f32, usize, usize)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, &Attrs::new(), Shaping::Advanced);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| (glyph.glyph_id, glyph.x, glyph.start, glyph.end))
+        .collect()
+}
+
+#[test]
+fn max_run_length_chunks_without_visual_difference() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+    assert_eq!(font_system.max_run_length(), usize::MAX);
+
+    // Long runs without spaces, with ligatures and joining forms across the chunk ends
+    let latin = "office".repeat(100);
+    let arabic = "بببب".repeat(40);
+    let whole_latin = shape_glyphs(&mut font_system, &latin);
+    let whole_arabic = shape_glyphs(&mut font_system, &arabic);
+
+    for max_run_length in [7, 64, 200] {
+        font_system.set_max_run_length(max_run_length);
+        assert_eq!(shape_glyphs(&mut font_system, &latin), whole_latin);
+        assert_eq!(shape_glyphs(&mut font_system, &arabic), whole_arabic);
+    }
+}
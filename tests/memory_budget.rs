@@ -0,0 +1,50 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+mod common;
+
+const BUDGET: usize = 16 * 1024;
+
+fn new_font_system() -> FontSystem {
+    common::font_system_with(&[
+        common::INTER,
+        "fonts/FiraMono-Medium.ttf",
+        "fonts/NotoSansArabic.ttf",
+    ])
+}
+
+/// Shape lines with many different weights and families, each needing its own font matches
+fn shape_heavily(font_system: &mut FontSystem, check: impl Fn(&FontSystem)) {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    for weight in (100..=900).step_by(5) {
+        for family in [Family::SansSerif, Family::Monospace, Family::Serif] {
+            let attrs = Attrs::new().family(family).weight(Weight(weight));
+            let text = format!("Line {weight} مرحبا {family:?}");
+            buffer.set_text(font_system, &text, &attrs, Shaping::Advanced);
+            assert!(buffer.layout_runs().next().is_some());
+            check(font_system);
+        }
+    }
+}
+
+#[test]
+fn memory_budget_bounds_caches() {
+    let mut font_system = new_font_system();
+    assert_eq!(font_system.memory_budget(), None);
+    shape_heavily(&mut font_system, |_| {});
+    let unbounded = font_system.cache_memory_usage();
+    assert!(unbounded > BUDGET, "{unbounded} <= {BUDGET}");
+
+    // Setting a budget shrinks the existing caches
+    font_system.set_memory_budget(Some(BUDGET));
+    assert_eq!(font_system.memory_budget(), Some(BUDGET));
+    assert!(font_system.cache_memory_usage() <= BUDGET);
+
+    // And keeps them within it while shaping
+    let mut font_system = new_font_system();
+    font_system.set_memory_budget(Some(BUDGET));
+    shape_heavily(&mut font_system, |font_system| {
+        let usage = font_system.cache_memory_usage();
+        assert!(usage <= BUDGET, "{usage} > {BUDGET}");
+    });
+    assert!(font_system.cache_memory_usage() > 0);
+}
@@ -0,0 +1,31 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+mod common;
+
+fn line_count(buffer: &mut Buffer, font_system: &mut FontSystem, width: f32) -> usize {
+    buffer.set_size(font_system, Some(width), None);
+    buffer.layout_runs().count()
+}
+
+#[test]
+fn min_width_for_lines_is_tight() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    buffer.set_size(&mut font_system, Some(500.0), None);
+    buffer.set_text(
+        &mut font_system,
+        "The quick brown fox jumps over the lazy dog while the cat watches",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    for max_lines in 2..=4 {
+        buffer.set_size(&mut font_system, Some(500.0), None);
+        let width = buffer.min_width_for_lines(&mut font_system, max_lines);
+        assert_eq!(buffer.size().0, Some(500.0));
+        assert_eq!(line_count(&mut buffer, &mut font_system, width), max_lines);
+        assert!(line_count(&mut buffer, &mut font_system, width - 1.0) > max_lines);
+    }
+}
@@ -0,0 +1,45 @@
+use cosmic_text::{Attrs, Buffer, Color, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn opacity_combines_with_color_alpha() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("ab", Attrs::new()),
+            ("cd", Attrs::new().opacity(0.5)),
+            (
+                "ef",
+                Attrs::new().color(Color::rgba(255, 0, 0, 200)).opacity(0.5),
+            ),
+        ],
+        &Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+
+    let default_color = Color::rgb(10, 20, 30);
+    let run = buffer.layout_runs().next().unwrap();
+    let colors: Vec<Color> = run
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.color(default_color))
+        .collect();
+    assert_eq!(colors[0], default_color);
+    assert_eq!(colors[2], Color::rgba(10, 20, 30, 128));
+    assert_eq!(colors[3].a(), 128);
+    assert_eq!(colors[4], Color::rgba(255, 0, 0, 100));
+
+    // The opacity is kept separately from the color for renderers
+    assert_eq!(run.glyphs[0].opacity, 1.0);
+    assert_eq!(run.glyphs[2].opacity, 0.5);
+    assert_eq!(run.glyphs[2].color_opt, None);
+
+    // Opacity is clamped
+    let attrs = Attrs::new().opacity(2.0);
+    assert_eq!(attrs.opacity_opt.map(|opacity| opacity.0), Some(1.0));
+}
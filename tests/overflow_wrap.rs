@@ -0,0 +1,41 @@
+use cosmic_text::{Attrs, Buffer, Metrics, OverflowWrap, Shaping, Wrap};
+
+mod common;
+
+#[test]
+fn overflow_wrap_breaks_long_tokens() {
+    let mut font_system = common::font_system();
+
+    let width = 100.0;
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(width), None);
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    let token = "0123456789abcdef".repeat(13);
+    let token = &token[..200];
+    buffer.set_text(
+        &mut font_system,
+        &format!("see {token} here"),
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // The token overflows on a line of its own
+    assert_eq!(buffer.overflow_wrap(), OverflowWrap::Normal);
+    let runs: Vec<_> = buffer.layout_runs().map(|run| run.line_w).collect();
+    assert_eq!(runs.len(), 3);
+    assert!(runs[1] > width);
+
+    // The token is broken into lines that fit, starting on its own line
+    buffer.set_overflow_wrap(&mut font_system, OverflowWrap::BreakWord);
+    let break_word: Vec<_> = buffer.layout_runs().map(|run| run.line_w).collect();
+    assert!(break_word.len() > 3);
+    assert!(break_word.iter().all(|&w| w <= width));
+    let first = buffer.layout_runs().next().unwrap();
+    assert!(first.glyphs.iter().all(|glyph| glyph.end <= 4));
+
+    // The token starts right after the first word
+    buffer.set_overflow_wrap(&mut font_system, OverflowWrap::Anywhere);
+    let first = buffer.layout_runs().next().unwrap();
+    assert!(first.glyphs.iter().any(|glyph| glyph.start >= 4));
+    assert!(buffer.layout_runs().all(|run| run.line_w <= width));
+}
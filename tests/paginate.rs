@@ -0,0 +1,63 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Page, Shaping, Wrap};
+
+mod common;
+
+fn line_ranges(pages: &[Page]) -> Vec<std::ops::Range<usize>> {
+    pages.iter().map(|page| page.line_range.clone()).collect()
+}
+
+#[test]
+fn paginate_lines() {
+    let mut font_system = common::font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let text = (0..20).map(|i| format!("Line {i}\n")).collect::<String>();
+    buffer.set_text(
+        &mut font_system,
+        text.trim_end(),
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let pages = buffer.paginate(7.0 * 20.0, 1);
+    assert_eq!(line_ranges(&pages), [0..7, 7..14, 14..20]);
+    let y_offsets: Vec<f32> = pages.iter().map(|page| page.y_offset).collect();
+    assert_eq!(y_offsets, [0.0, 140.0, 280.0]);
+
+    // A line taller than the page gets a page of its own
+    assert_eq!(buffer.paginate(10.0, 1).len(), 20);
+}
+
+#[test]
+fn paginate_widows_and_orphans() {
+    let mut font_system = common::font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    buffer.set_size(&mut font_system, Some(1.0), None);
+    let set_words = |buffer: &mut Buffer, font_system: &mut FontSystem, counts: &[usize]| {
+        let text = counts
+            .iter()
+            .map(|&count| vec!["word"; count].join(" "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        buffer.set_text(font_system, &text, &Attrs::new(), Shaping::Advanced);
+        buffer.shape_until_scroll(font_system, false);
+    };
+    let page_height = 7.0 * 20.0;
+
+    // A single visual line of the second line would be left at the bottom of the first page
+    set_words(&mut buffer, &mut font_system, &[6, 4]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 1)), [0..7, 7..10]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 2)), [0..6, 6..10]);
+
+    // A single visual line of the first line would be left at the top of the second page
+    set_words(&mut buffer, &mut font_system, &[8]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 1)), [0..7, 7..8]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 2)), [0..6, 6..8]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 3)), [0..5, 5..8]);
+
+    // Lines that cannot be split keeping enough visual lines on both pages are not split
+    set_words(&mut buffer, &mut font_system, &[5, 3]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 1)), [0..7, 7..8]);
+    assert_eq!(line_ranges(&buffer.paginate(page_height, 2)), [0..5, 5..8]);
+}
@@ -0,0 +1,47 @@
+use cosmic_text::{Attrs, Buffer, CacheKey, Metrics, Shaping, SubpixelBin};
+
+mod common;
+
+#[test]
+fn physical_glyph_matches_cache_key() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(13.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "The quick brown fox jumps over the lazy dog",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let mut bins = Vec::new();
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            for (offset, scale) in [((0.0, 0.0), 1.0), ((10.3, 5.6), 1.5)] {
+                let physical = glyph.physical(offset, scale);
+                assert_eq!(physical.subpixel, physical.cache_key.x_bin);
+                assert_eq!(physical.cache_key.y_bin, SubpixelBin::Zero);
+
+                // The same as building the cache key from the fractional position
+                let x = (glyph.x + glyph.font_size * glyph.x_offset) * scale + offset.0;
+                let y = ((glyph.y - glyph.font_size * glyph.y_offset) * scale + offset.1).trunc();
+                let (cache_key, cache_x, cache_y) = CacheKey::new(
+                    glyph.font_id,
+                    glyph.glyph_id,
+                    glyph.font_size * scale,
+                    (x, y),
+                    glyph.cache_key_flags,
+                );
+                assert_eq!(physical.cache_key, cache_key);
+                assert_eq!((physical.x, physical.y), (cache_x, cache_y));
+                assert_eq!(SubpixelBin::new(x), (physical.x, physical.subpixel));
+
+                // And rounds the fractional position to within a quarter pixel
+                let rounded = physical.x as f32 + physical.subpixel.as_float();
+                assert!((rounded - x).abs() <= 0.125 + f32::EPSILON * x.abs());
+                bins.push(physical.subpixel);
+            }
+        }
+    }
+    assert!(bins.iter().any(|bin| *bin != SubpixelBin::Zero));
+}
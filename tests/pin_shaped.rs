@@ -0,0 +1,60 @@
+#![cfg(feature = "shape-run-cache")]
+
+use cosmic_text::{Attrs, AttrsOwned, Buffer, Metrics, ShapeRunKey, Shaping};
+
+mod common;
+
+fn key(text: &str, attrs: &Attrs) -> ShapeRunKey {
+    ShapeRunKey {
+        text: text.into(),
+        default_attrs: AttrsOwned::new(attrs),
+        attrs_spans: Vec::new(),
+        locale_opt: None,
+        font_whitelist_opt: None,
+    }
+}
+
+#[test]
+fn pinned_runs_survive_eviction() {
+    let mut font_system = common::font_system();
+    let attrs = Attrs::new();
+
+    font_system.pin_shaped("Save", &attrs, Shaping::Advanced);
+    assert!(font_system.shape_run_cache.is_pinned(&key("Save", &attrs)));
+
+    // Overflow the cache with unrelated text, under a tiny memory budget
+    font_system.set_memory_budget(Some(1024));
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    for i in 0..100 {
+        let text = format!("Unrelated text number {i}");
+        buffer.set_text(&mut font_system, &text, &attrs, Shaping::Advanced);
+        buffer.shape_until_scroll(&mut font_system, false);
+        font_system.shape_run_cache.trim(0);
+    }
+    assert!(font_system
+        .shape_run_cache
+        .get(&key("Unrelated", &attrs))
+        .is_none());
+
+    // Shaping the label again hits the cache
+    let pinned = font_system
+        .shape_run_cache
+        .get(&key("Save", &attrs))
+        .cloned()
+        .unwrap();
+    buffer.set_text(&mut font_system, "Save", &attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let glyph_ids: Vec<u16> = run.glyphs.iter().map(|glyph| glyph.glyph_id).collect();
+    let pinned_ids: Vec<u16> = pinned.iter().map(|glyph| glyph.glyph_id).collect();
+    assert_eq!(glyph_ids, pinned_ids);
+
+    // Once unpinned, the label can be evicted again
+    font_system.unpin_shaped("Save", &attrs, Shaping::Advanced);
+    assert!(!font_system.shape_run_cache.is_pinned(&key("Save", &attrs)));
+    font_system.shape_run_cache.trim(0);
+    font_system.shape_run_cache.trim(0);
+    assert!(font_system
+        .shape_run_cache
+        .get(&key("Save", &attrs))
+        .is_none());
+}
@@ -0,0 +1,42 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+fn glyph_positions(buffer: &Buffer) -> Vec<(f32, f32)> {
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| (glyph.x, glyph.w)).collect()
+}
+
+#[test]
+fn position_quantization_snaps_without_drift() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(13.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "The quick brown fox jumps over the lazy dog, again and again and again",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    let exact = glyph_positions(&buffer);
+    assert!(exact.iter().any(|(x, _)| (x * 4.0).fract() != 0.0));
+
+    let step = 0.25;
+    buffer.set_position_quantization(&mut font_system, Some(step));
+    assert_eq!(buffer.position_quantization(), Some(step));
+    let quantized = glyph_positions(&buffer);
+    assert_eq!(quantized.len(), exact.len());
+
+    let mut advance = 0.0;
+    for (&(x, w), &(exact_x, exact_w)) in quantized.iter().zip(exact.iter()) {
+        assert_eq!((x / step).fract(), 0.0, "{x} is not on the grid");
+        assert!((x - exact_x).abs() <= step / 2.0);
+        // Each position is rounded from the exact sum of the previous advances
+        assert!((x - advance).abs() <= step / 2.0);
+        assert_eq!(w, exact_w);
+        advance += w;
+    }
+
+    buffer.set_position_quantization(&mut font_system, None);
+    assert_eq!(glyph_positions(&buffer), exact);
+}
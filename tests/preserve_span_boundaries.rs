@@ -0,0 +1,43 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+mod common;
+
+fn glyph_advances(
+    font_system: &mut FontSystem,
+    spans: &[(&str, Attrs)],
+    preserve_span_boundaries: bool,
+) -> Vec<(usize, usize, f32)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(32.0, 40.0));
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_preserve_span_boundaries(preserve_span_boundaries);
+    buffer.set_rich_text(
+        spans.iter().map(|(text, attrs)| (*text, attrs.clone())),
+        &Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|g| (g.start, g.end, g.w)).collect()
+}
+
+#[test]
+fn identical_font_spans_split_runs() {
+    let mut font_system = common::font_system();
+
+    // Spans differ only in metadata, so they resolve to the same font
+    let spans = [
+        ("A", Attrs::new().metadata(1)),
+        ("V", Attrs::new().metadata(2)),
+    ];
+    let alone = glyph_advances(&mut font_system, &[("A", Attrs::new())], false)[0].2;
+
+    let merged = glyph_advances(&mut font_system, &spans, false);
+    assert_eq!(merged.len(), 2);
+    assert!(merged[0].2 < alone, "expected A-V to be kerned");
+
+    let split = glyph_advances(&mut font_system, &spans, true);
+    assert_eq!(split.len(), 2);
+    assert_eq!((split[0].0, split[0].1), (0, 1));
+    assert_eq!((split[1].0, split[1].1), (1, 2));
+    assert_eq!(split[0].2, alone);
+}
@@ -0,0 +1,56 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, PuaPolicy, Shaping};
+
+mod common;
+
+fn shape_glyphs(font_system: &mut FontSystem, text: &str) -> Vec<(fontdb::ID, u16)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .map(|glyph| (glyph.font_id, glyph.glyph_id))
+        .collect()
+}
+
+#[test]
+fn pua_policy_skips_fallback() {
+    let mut font_system = common::font_system_with(&[
+        common::INTER,
+        "fonts/FiraMono-Medium.ttf",
+        "fonts/NotoSans-Regular.ttf",
+    ]);
+    let icon_id = font_system.db().faces().nth(1).unwrap().id;
+    let icons = "\u{E000}\u{E001}";
+    assert_eq!(font_system.pua_policy(), PuaPolicy::default());
+
+    // By default, the missing glyphs are searched for in every other font
+    let count = font_system.fallback_font_count();
+    let glyphs = shape_glyphs(&mut font_system, icons);
+    assert!(glyphs.iter().all(|&(_, glyph_id)| glyph_id == 0));
+    assert!(font_system.fallback_font_count() > count);
+
+    // Skipping fallback leaves the glyphs missing from the primary font
+    font_system.set_pua_policy(PuaPolicy {
+        font: None,
+        skip_fallback: true,
+    });
+    let count = font_system.fallback_font_count();
+    let glyphs = shape_glyphs(&mut font_system, icons);
+    assert_eq!(glyphs.len(), 2);
+    assert!(glyphs.iter().all(|&(_, glyph_id)| glyph_id == 0));
+    assert_eq!(font_system.fallback_font_count(), count);
+
+    // Other text still falls back
+    shape_glyphs(&mut font_system, "a\u{E000}");
+    assert!(font_system.fallback_font_count() > count);
+
+    // The icon font is used directly
+    font_system.set_pua_policy(PuaPolicy {
+        font: Some(icon_id),
+        skip_fallback: true,
+    });
+    let count = font_system.fallback_font_count();
+    let glyphs = shape_glyphs(&mut font_system, icons);
+    assert!(glyphs.iter().all(|&(font_id, _)| font_id == icon_id));
+    assert_eq!(font_system.fallback_font_count(), count);
+}
@@ -0,0 +1,96 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Style, Weight};
+
+fn load_family(font_system: &mut FontSystem, path: &str) -> String {
+    let id = font_system
+        .db_mut()
+        .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(
+            std::fs::read(path).unwrap(),
+        )))[0];
+    font_system.db().face(id).unwrap().families[0].0.clone()
+}
+
+fn fallback_family(font_system: &mut FontSystem, primary_family: &str) -> String {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let attrs = Attrs::new().family(Family::Name(primary_family));
+    buffer.set_text(font_system, "Я", &attrs, Shaping::Advanced);
+    let font_id = buffer.layout_runs().next().unwrap().glyphs[0].font_id;
+    font_system.db().face(font_id).unwrap().families[0]
+        .0
+        .clone()
+}
+
+#[test]
+fn range_fallback_scoped_to_primary_family() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let arabic = load_family(&mut font_system, "fonts/NotoSansArabic.ttf");
+    let hebrew = load_family(&mut font_system, "fonts/NotoSansHebrew.ttf");
+    let sans = load_family(&mut font_system, "fonts/NotoSans-Regular.ttf");
+    let inter = load_family(&mut font_system, "fonts/Inter-Regular.ttf");
+    assert_eq!(fallback_family(&mut font_system, &arabic), sans);
+
+    font_system.add_range_fallback('\u{0400}'..='\u{04FF}', inter.clone(), Some(&arabic));
+    assert_eq!(fallback_family(&mut font_system, &arabic), inter);
+    assert_eq!(fallback_family(&mut font_system, &hebrew), sans);
+
+    font_system.clear_unicode_range_fallbacks();
+    font_system.add_range_fallback('\u{0400}'..='\u{04FF}', inter.clone(), None);
+    assert_eq!(fallback_family(&mut font_system, &hebrew), inter);
+}
+
+#[test]
+fn remove_unicode_range_fallback_exact_range() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let arabic = load_family(&mut font_system, "fonts/NotoSansArabic.ttf");
+    let hebrew = load_family(&mut font_system, "fonts/NotoSansHebrew.ttf");
+    let sans = load_family(&mut font_system, "fonts/NotoSans-Regular.ttf");
+    let inter = load_family(&mut font_system, "fonts/Inter-Regular.ttf");
+
+    // A fallback with a style limited to a primary family and a plain one for all families, for
+    // the same range
+    font_system.add_range_fallback_with_style(
+        '\u{0400}'..='\u{04FF}',
+        inter.clone(),
+        Some(&arabic),
+        Weight::NORMAL,
+        Style::Normal,
+    );
+    font_system.add_range_fallback('\u{0400}'..='\u{04FF}', inter.clone(), None);
+    // And one for an overlapping range
+    font_system.add_range_fallback('\u{0400}'..='\u{042F}', inter.clone(), Some(&hebrew));
+    assert_eq!(fallback_family(&mut font_system, &arabic), inter);
+
+    // Both fallbacks for the exact range are removed, the overlapping one is kept
+    assert!(font_system.remove_unicode_range_fallback('\u{0400}', '\u{04FF}'));
+    assert!(!font_system.remove_unicode_range_fallback('\u{0400}', '\u{04FF}'));
+    assert_eq!(fallback_family(&mut font_system, &arabic), sans);
+    assert_eq!(fallback_family(&mut font_system, &hebrew), inter);
+
+    assert!(!font_system.remove_unicode_range_fallback('\u{0400}', '\u{0430}'));
+    assert!(font_system.remove_unicode_range_fallback('\u{0400}', '\u{042F}'));
+    assert_eq!(fallback_family(&mut font_system, &hebrew), sans);
+}
+
+#[test]
+fn range_fallback_with_style_resolved_lazily() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let arabic = load_family(&mut font_system, "fonts/NotoSansArabic.ttf");
+    let sans = load_family(&mut font_system, "fonts/NotoSans-Regular.ttf");
+
+    // Registered before the font is loaded, so the fallback is skipped
+    font_system.add_range_fallback_with_style(
+        '\u{0400}'..='\u{04FF}',
+        "Inter",
+        None,
+        Weight::NORMAL,
+        Style::Normal,
+    );
+    assert_eq!(fallback_family(&mut font_system, &arabic), sans);
+
+    // And used once it is loaded
+    let inter = load_family(&mut font_system, "fonts/Inter-Regular.ttf");
+    assert_eq!(inter, "Inter");
+    assert_eq!(fallback_family(&mut font_system, &arabic), inter);
+}
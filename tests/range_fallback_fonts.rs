@@ -0,0 +1,120 @@
+use common::TestFont;
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+mod common;
+
+/// Build a font named `family` mapping each of `chars`, in increasing order, to the glyphs from 1
+fn font(family: &str, chars: &[char]) -> Vec<u8> {
+    chars
+        .iter()
+        .fold(TestFont::new(family).glyph(None, 600, false), |font, &c| {
+            font.glyph(Some(c), if c.is_ascii() { 600 } else { 1000 }, false)
+        })
+        .build()
+}
+
+fn shape_glyphs(font_system: &mut FontSystem) -> Vec<(fontdb::ID, u16)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        font_system,
+        "A\u{3042}\u{6F22}",
+        &Attrs::new().family(Family::Name("Base")),
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .map(|glyph| (glyph.font_id, glyph.glyph_id))
+        .collect()
+}
+
+#[test]
+fn range_fallback_fonts_tried_in_order() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let mut ids = Vec::new();
+    for (family, chars) in [
+        ("Base", &['A'][..]),
+        ("Other", &['\u{3042}', '\u{6F22}'][..]),
+        ("Kana", &['\u{3042}'][..]),
+        ("Kanji", &['\u{3042}', '\u{6F22}'][..]),
+    ] {
+        ids.extend(
+            font_system
+                .db_mut()
+                .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(font(
+                    family, chars,
+                )))),
+        );
+    }
+    let [base, other, kana, kanji] = ids[..] else {
+        panic!("expected four fonts");
+    };
+    assert_eq!(
+        shape_glyphs(&mut font_system),
+        [(base, 1), (other, 1), (other, 2)]
+    );
+
+    // Kana comes from the first font, the kanji it lacks from the second
+    font_system.add_range_fallback_fonts('\u{3000}'..='\u{9FFF}', &[kana, kanji], None);
+    assert_eq!(
+        shape_glyphs(&mut font_system),
+        [(base, 1), (kana, 1), (kanji, 2)]
+    );
+
+    // Characters none of the fonts have fall back to the other fonts
+    font_system.clear_unicode_range_fallbacks();
+    font_system.add_range_fallback_fonts('\u{3000}'..='\u{9FFF}', &[kana], None);
+    assert_eq!(
+        shape_glyphs(&mut font_system),
+        [(base, 1), (kana, 1), (other, 2)]
+    );
+}
+
+#[test]
+fn range_fallback_fonts_after_multibyte_char() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let mut ids = Vec::new();
+    for (family, chars) in [
+        ("Base", &['A', '\u{263A}'][..]),
+        ("Other", &['\u{3042}'][..]),
+        ("Kana", &['\u{3042}'][..]),
+    ] {
+        ids.extend(
+            font_system
+                .db_mut()
+                .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(font(
+                    family, chars,
+                )))),
+        );
+    }
+    let [base, _, kana] = ids[..] else {
+        panic!("expected three fonts");
+    };
+    font_system.add_range_fallback_fonts('\u{3000}'..='\u{9FFF}', &[kana], None);
+
+    // The three byte character before the fallback character must not shift its position
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "A\u{263A}\u{3042}\u{263A}",
+        &Attrs::new().family(Family::Name("Base")),
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    let glyphs: Vec<_> = run
+        .glyphs
+        .iter()
+        .map(|glyph| (glyph.font_id, glyph.glyph_id, glyph.start, glyph.end))
+        .collect();
+    assert_eq!(
+        glyphs,
+        [
+            (base, 1, 0, 1),
+            (base, 2, 1, 4),
+            (kana, 1, 4, 7),
+            (base, 2, 7, 10)
+        ]
+    );
+}
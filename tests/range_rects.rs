@@ -0,0 +1,62 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+mod common;
+
+fn font_system() -> FontSystem {
+    common::font_system_with(&[common::INTER, "fonts/NotoSansHebrew.ttf"])
+}
+
+#[test]
+fn range_rects_across_wrapped_lines() {
+    let mut font_system = font_system();
+    let metrics = Metrics::new(14.0, 20.0);
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    let text = "Hi wonderful";
+    let word = 3..text.len();
+    buffer.set_wrap(&mut font_system, Wrap::Glyph);
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+    let width: f32 = buffer
+        .layout_runs()
+        .next()
+        .unwrap()
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.start >= word.start)
+        .map(|glyph| glyph.w)
+        .sum();
+
+    // Wrap in the middle of the word
+    let hi_width = buffer.layout_runs().next().unwrap().glyphs[..3]
+        .iter()
+        .map(|glyph| glyph.w)
+        .sum::<f32>();
+    buffer.set_size(&mut font_system, Some(hi_width + width / 2.0), None);
+
+    let rects = buffer.range_rects(0, word.clone());
+    assert_eq!(rects.len(), 2, "{rects:?}");
+    assert_eq!(rects[0].1, 0.0);
+    assert_eq!(rects[1].1, metrics.line_height);
+    assert!(rects
+        .iter()
+        .all(|rect| rect.2 > 0.0 && rect.3 == metrics.line_height));
+    assert!((rects[0].2 + rects[1].2 - width).abs() < 0.01);
+    assert_eq!(rects[1].0, 0.0);
+
+    assert!(buffer.range_rects(0, 0..0).is_empty());
+    assert!(buffer.range_rects(1, word).is_empty());
+}
+
+#[test]
+fn range_rects_split_by_bidi_reordering() {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let text = "abc אבג def";
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+
+    // "c אב" is contiguous in logical order, but `ג` is drawn between `c ` and `בא`
+    let start = text.find('c').unwrap();
+    let end = text.find('ג').unwrap();
+    let rects = buffer.range_rects(0, start..end);
+    assert_eq!(rects.len(), 2, "{rects:?}");
+    assert!(rects[0].0 + rects[0].2 < rects[1].0);
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "swash")]
+
+use std::sync::Arc;
+
+use cosmic_text::{CacheKey, CacheKeyFlags, FontSystem, SwashCache};
+
+mod common;
+
+fn rasterize(
+    swash_cache: &mut SwashCache,
+    font_system: &mut FontSystem,
+    font_id: fontdb::ID,
+) -> Vec<CacheKey> {
+    (1..5)
+        .map(|glyph_id| {
+            let (cache_key, _, _) =
+                CacheKey::new(font_id, glyph_id, 20.0, (0.0, 0.0), CacheKeyFlags::empty());
+            assert!(swash_cache.get_image(font_system, cache_key).is_some());
+            assert!(swash_cache
+                .get_outline_commands(font_system, cache_key)
+                .is_some());
+            cache_key
+        })
+        .collect()
+}
+
+fn cached_font_ids(swash_cache: &SwashCache) -> Vec<fontdb::ID> {
+    let mut ids: Vec<fontdb::ID> = swash_cache
+        .image_cache
+        .keys()
+        .chain(swash_cache.outline_command_cache.keys())
+        .map(|cache_key| cache_key.font_id)
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[test]
+fn replaced_font_glyphs_are_purged() {
+    let data = std::fs::read(common::INTER).unwrap();
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/FiraMono-Medium.ttf"]);
+    let ids: Vec<fontdb::ID> = font_system.db().faces().map(|face| face.id).collect();
+    let (old_id, other_id) = (ids[0], ids[1]);
+
+    let mut swash_cache = SwashCache::new();
+    rasterize(&mut swash_cache, &mut font_system, old_id);
+    rasterize(&mut swash_cache, &mut font_system, other_id);
+    assert_eq!(swash_cache.image_cache.len(), 8);
+
+    let new_id = font_system
+        .replace_font_source(old_id, fontdb::Source::Binary(Arc::new(data)))
+        .unwrap();
+    assert_eq!(font_system.removed_font_ids(), [old_id]);
+
+    // The old font's glyphs are purged on the next use, other fonts keep theirs
+    rasterize(&mut swash_cache, &mut font_system, new_id);
+    let cached = cached_font_ids(&swash_cache);
+    assert!(!cached.contains(&old_id));
+    assert!(cached.contains(&other_id));
+    assert!(cached.contains(&new_id));
+    assert_eq!(swash_cache.image_cache.len(), 8);
+    assert_eq!(swash_cache.outline_command_cache.len(), 8);
+
+    // Fonts can also be removed from the cache directly
+    swash_cache.remove_font(other_id);
+    assert_eq!(cached_font_ids(&swash_cache), [new_id]);
+}
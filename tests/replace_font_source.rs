@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+mod common;
+
+fn shaped_font_id(font_system: &mut FontSystem, family: &str) -> fontdb::ID {
+    let mut buffer = Buffer::new(font_system, Metrics::new(20.0, 30.0));
+    buffer.set_text(
+        font_system,
+        "Hello",
+        &Attrs::new().family(Family::Name(family)),
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs[0].font_id
+}
+
+#[test]
+fn replace_font_source() {
+    let data = std::fs::read(common::INTER).unwrap();
+    let mut font_system = common::font_system();
+    let old_id = font_system.db().faces().next().unwrap().id;
+    let family = font_system.db().face(old_id).unwrap().families[0].0.clone();
+
+    assert_eq!(shaped_font_id(&mut font_system, &family), old_id);
+    assert!(font_system.get_font(old_id).is_some());
+
+    let new_id = font_system
+        .replace_font_source(old_id, fontdb::Source::Binary(Arc::new(data)))
+        .unwrap();
+    assert_ne!(new_id, old_id);
+    assert!(font_system.db().face(old_id).is_none());
+    assert!(font_system.get_font(old_id).is_none());
+    assert_eq!(font_system.replacement_font_id(old_id), Some(new_id));
+    assert_eq!(font_system.replacement_font_id(new_id), None);
+
+    assert_eq!(shaped_font_id(&mut font_system, &family), new_id);
+}
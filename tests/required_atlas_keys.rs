@@ -0,0 +1,31 @@
+use cosmic_text::{Attrs, Buffer, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn required_atlas_keys_are_distinct_fonts_and_sizes() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let family = font_system.db().face(ids[0]).unwrap().families[0].0.clone();
+    let attrs = Attrs::new().family(Family::Name(&family));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    assert!(buffer.required_atlas_keys().is_empty());
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("Hello ", attrs.clone()),
+            ("big", attrs.clone().metrics(Metrics::new(28.0, 40.0))),
+            (" مرحبا\n", attrs.clone()),
+            ("world", attrs.clone()),
+        ],
+        &attrs,
+        Shaping::Advanced,
+        None,
+    );
+
+    assert_eq!(
+        buffer.required_atlas_keys(),
+        [(ids[0], 14.0), (ids[0], 28.0), (ids[1], 14.0)]
+    );
+}
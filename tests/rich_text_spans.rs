@@ -0,0 +1,53 @@
+use cosmic_text::{Attrs, AttrsOwned, Buffer, Color, Cursor, Metrics, Shaping, Weight};
+
+mod common;
+
+#[test]
+fn rich_text_empty_and_mid_grapheme_spans() {
+    let mut font_system = common::font_system();
+
+    let plain = Attrs::new();
+    let bold = plain.clone().weight(Weight::BOLD);
+    let red = plain.clone().color(Color::rgb(0xFF, 0, 0));
+    let blue = plain.clone().color(Color::rgb(0, 0, 0xFF));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("", bold.clone()),
+            ("Caf", plain.clone()),
+            ("", red.clone()),
+            // The combining accent is a span of its own, in the middle of a grapheme
+            ("e", red.clone()),
+            ("\u{301}", blue.clone()),
+            ("", bold.clone()),
+            ("\n", plain.clone()),
+            ("ok", bold.clone()),
+            ("", red.clone()),
+        ],
+        &plain,
+        Shaping::Advanced,
+        None,
+    );
+
+    let texts: Vec<&str> = buffer.lines.iter().map(|line| line.text()).collect();
+    assert_eq!(texts, ["Cafe\u{301}", "ok"]);
+
+    let attrs_at = |index| buffer.attrs_at(Cursor::new(0, index)).unwrap();
+    assert_eq!(attrs_at(0), AttrsOwned::new(&plain));
+    assert_eq!(attrs_at(3), AttrsOwned::new(&red));
+    assert_eq!(attrs_at(4), AttrsOwned::new(&blue));
+    assert_eq!(
+        buffer.attrs_at(Cursor::new(1, 0)),
+        Some(AttrsOwned::new(&bold))
+    );
+
+    // The grapheme is shaped whole, with the attributes of its first character
+    buffer.shape_until_scroll(&mut font_system, false);
+    let run = buffer.layout_runs().next().unwrap();
+    let last = run.glyphs.iter().max_by_key(|glyph| glyph.start).unwrap();
+    assert_eq!(last.start, 3);
+    assert_eq!(last.end, 6);
+    assert_eq!(last.color_opt, red.color_opt);
+}
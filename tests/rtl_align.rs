@@ -0,0 +1,52 @@
+use cosmic_text::{Align, Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+mod common;
+
+const WIDTH: f32 = 120.0;
+
+fn runs(font_system: &mut FontSystem, align: Align) -> Vec<(f32, f32, usize)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, Wrap::Word);
+    buffer.set_size(font_system, Some(WIDTH), None);
+    buffer.set_text(
+        font_system,
+        "مرحبا بالعالم هذا نص عربي طويل يلتف على عدة أسطر",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.lines[0].set_align(Some(align));
+    buffer.shape_until_scroll(font_system, false);
+    buffer
+        .layout_runs()
+        .map(|run| {
+            let left = run.glyphs.iter().map(|glyph| glyph.x).fold(WIDTH, f32::min);
+            let right = run
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.x + glyph.w)
+                .fold(0.0, f32::max);
+            let start = run.glyphs.iter().map(|glyph| glyph.start).min().unwrap();
+            (left, right, start)
+        })
+        .collect()
+}
+
+#[test]
+fn rtl_align_start_anchors_right() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansArabic.ttf"]);
+
+    let start = runs(&mut font_system, Align::Start);
+    assert!(start.len() > 1);
+    // Every line starts from the right edge, and wrapping continues on the next line
+    for (_, right, _) in &start {
+        assert!((right - WIDTH).abs() < 0.01, "{right} != {WIDTH}");
+    }
+    assert_eq!(start[0].2, 0);
+    assert!(start.windows(2).all(|lines| lines[0].2 < lines[1].2));
+    assert_eq!(start, runs(&mut font_system, Align::Right));
+
+    // The end of RTL lines is the left edge
+    for (left, _, _) in runs(&mut font_system, Align::End) {
+        assert!(left.abs() < 0.01, "{left} != 0");
+    }
+}
@@ -0,0 +1,42 @@
+use cosmic_text::{Attrs, Buffer, EmptyLineMetrics, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn layout_run_line_height_is_resolved() {
+    let mut font_system = common::font_system();
+
+    let big = Attrs::new().metrics(Metrics::new(28.0, 40.0));
+    let huge = Attrs::new().metrics(Metrics::new(36.0, 50.0));
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("plain\n", Attrs::new()),
+            ("mixed ", Attrs::new()),
+            ("big ", big.clone()),
+            ("huge\n", huge),
+            ("\n", Attrs::new()),
+            ("plain", Attrs::new()),
+        ],
+        &big,
+        Shaping::Advanced,
+        None,
+    );
+
+    let heights =
+        |buffer: &Buffer| -> Vec<f32> { buffer.layout_runs().map(|run| run.line_height).collect() };
+    assert_eq!(heights(&buffer), [20.0, 50.0, 40.0, 20.0]);
+
+    // Each run starts where the previous one ends
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    for pair in runs.windows(2) {
+        assert_eq!(pair[1].line_top, pair[0].line_top + pair[0].line_height);
+    }
+
+    // Empty lines follow the configured source
+    buffer.set_empty_line_metrics(&mut font_system, EmptyLineMetrics::Buffer);
+    assert_eq!(heights(&buffer), [20.0, 50.0, 20.0, 20.0]);
+    buffer.set_empty_line_metrics(&mut font_system, EmptyLineMetrics::PreviousLine);
+    assert_eq!(heights(&buffer), [20.0, 50.0, 50.0, 20.0]);
+}
@@ -0,0 +1,68 @@
+use common::{be16, TestFont};
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+mod common;
+
+/// Build a font mapping `A` to glyph 1, with a `ccmp` feature substituting glyph 2 only for the
+/// Greek script
+fn greek_only_font() -> Vec<u8> {
+    let mut gsub = Vec::new();
+    // Header with script list at 10, feature list at 30 and lookup list at 44
+    be16(&mut gsub, &[1, 0, 10, 30, 44]);
+    // Script list with only a Greek script enabling the feature
+    be16(&mut gsub, &[1]);
+    gsub.extend_from_slice(b"grek");
+    be16(&mut gsub, &[8, 4, 0, 0, 0xFFFF, 1, 0]);
+    // Feature list with ccmp, which is always applied
+    be16(&mut gsub, &[1]);
+    gsub.extend_from_slice(b"ccmp");
+    be16(&mut gsub, &[8, 0, 1, 0]);
+    // Lookup list with a single substitution of glyph 1 by glyph 2
+    be16(&mut gsub, &[1, 4]);
+    be16(&mut gsub, &[1, 0, 1, 8]);
+    be16(&mut gsub, &[2, 8, 1, 2]);
+    be16(&mut gsub, &[1, 1, 1]);
+
+    TestFont::new("Greek Test")
+        .glyph(None, 600, false)
+        .glyph(Some('A'), 600, false)
+        .glyph(None, 600, false)
+        .table(b"GSUB", gsub)
+        .build()
+}
+
+fn glyph_ids(font_system: &mut FontSystem) -> Vec<u16> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, "AA", &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+}
+
+#[test]
+fn script_detector_overrides_script() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    font_system.db_mut().load_font_data(greek_only_font());
+
+    // Latin text is shaped as Latin, without the Greek substitution
+    assert_eq!(glyph_ids(&mut font_system), [1, 1]);
+
+    font_system.set_script_detector(Some(Box::new(|text: &str| {
+        vec![(0..text.len(), *b"grek", None)]
+    })));
+    assert_eq!(glyph_ids(&mut font_system), [2, 2]);
+
+    // Ranges are shaped separately
+    font_system.set_script_detector(Some(Box::new(|text: &str| {
+        text.char_indices()
+            .map(|(i, c)| {
+                let script = if i == 0 { *b"latn" } else { *b"grek" };
+                (i..i + c.len_utf8(), script, None)
+            })
+            .collect()
+    })));
+    assert_eq!(glyph_ids(&mut font_system), [1, 2]);
+
+    font_system.set_script_detector(None);
+    assert_eq!(glyph_ids(&mut font_system), [1, 1]);
+}
@@ -0,0 +1,54 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn script_runs_split_lines_by_script() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let text = "Hello مرحبا world";
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+
+    // A single line has runs of each script, in visual order, with spaces shaped separately as
+    // unknown script
+    let runs: Vec<_> = run
+        .script_runs()
+        .map(|(script, glyphs)| {
+            let start = glyphs.iter().map(|glyph| glyph.start).min().unwrap();
+            let end = glyphs.iter().map(|glyph| glyph.end).max().unwrap();
+            (script, text[start..end].trim())
+        })
+        .collect();
+    assert_eq!(
+        runs,
+        [
+            (*b"Latn", "Hello"),
+            (*b"Zzzz", ""),
+            (*b"Arab", "مرحبا"),
+            (*b"Zzzz", ""),
+            (*b"Latn", "world")
+        ]
+    );
+    let run_glyphs: usize = run.script_runs().map(|(_, glyphs)| glyphs.len()).sum();
+    assert_eq!(run_glyphs, run.glyphs.len());
+
+    // Text without a specific script is unknown
+    buffer.set_text(&mut font_system, "123", &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let scripts: Vec<_> = run.script_runs().map(|(script, _)| script).collect();
+    assert_eq!(scripts, [*b"Zzzz"]);
+}
+
+#[cfg(feature = "swash")]
+#[test]
+fn script_runs_basic_shaping() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "Hello 123", &Attrs::new(), Shaping::Basic);
+    let run = buffer.layout_runs().next().unwrap();
+    let scripts: Vec<_> = run.script_runs().map(|(script, _)| script).collect();
+    assert_eq!(scripts, [*b"Latn", *b"Zzzz"]);
+}
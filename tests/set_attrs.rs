@@ -0,0 +1,53 @@
+use cosmic_text::{Attrs, Buffer, Color, Metrics, Shaping, Weight};
+
+mod common;
+
+#[test]
+fn set_attrs_reshapes_only_for_shaping_changes() {
+    let mut font_system = common::font_system();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "let x = 1;\nlet y = 2;",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    let glyph_ids = |buffer: &Buffer| -> Vec<u16> {
+        let run = buffer.layout_runs().next().unwrap();
+        run.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+    };
+    let before = glyph_ids(&buffer);
+
+    // Recoloring updates the glyphs without shaping
+    buffer.set_redraw(false);
+    let shaped = font_system.shaped_line_count();
+    let red = Color::rgb(255, 0, 0);
+    buffer.set_attrs(&mut font_system, 0, 0..3, &Attrs::new().color(red));
+    buffer.set_attrs(&mut font_system, 0, 4..5, &Attrs::new().opacity(0.5));
+    assert_eq!(font_system.shaped_line_count(), shaped);
+    assert_eq!(glyph_ids(&buffer), before);
+    assert!(buffer.redraw());
+
+    let run = buffer.layout_runs().next().unwrap();
+    for glyph in run.glyphs.iter() {
+        let color_opt = (glyph.end <= 3).then_some(red);
+        assert_eq!(glyph.color_opt, color_opt);
+        let opacity = if glyph.start == 4 { 0.5 } else { 1.0 };
+        assert_eq!(glyph.opacity, opacity);
+    }
+    let shape = buffer.lines[0].shape_opt().unwrap();
+    let first = &shape.spans[0].words[0].glyphs[0];
+    assert_eq!(first.color_opt, Some(red));
+
+    // Changing the weight reshapes the changed line only
+    let bold = Attrs::new().color(red).weight(Weight::BOLD);
+    buffer.set_attrs(&mut font_system, 0, 0..3, &bold);
+    assert_eq!(font_system.shaped_line_count(), shaped + 1);
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run
+        .glyphs
+        .iter()
+        .all(|glyph| glyph.end > 3 || glyph.color_opt == Some(red)));
+    assert!(buffer.lines[1].shape_opt().is_some());
+}
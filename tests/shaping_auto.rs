@@ -0,0 +1,75 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{
+    Attrs, Buffer, FeatureTag, FontFeatures, FontSystem, Ligatures, Metrics, Shaping,
+};
+
+mod common;
+
+fn shaped(font_system: &mut FontSystem, text: &str, shaping: Shaping) -> Vec<(u16, f32)> {
+    shaped_with_attrs(font_system, text, &Attrs::new(), shaping)
+}
+
+fn shaped_with_attrs(
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs: &Attrs,
+    shaping: Shaping,
+) -> Vec<(u16, f32)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(20.0, 30.0));
+    buffer.set_text(font_system, text, attrs, shaping);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| (glyph.glyph_id, glyph.x))
+        .collect()
+}
+
+#[test]
+fn auto_shaping_picks_per_run() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+
+    // Latin text is shaped without kerning
+    let basic = shaped(&mut font_system, "AVA", Shaping::Basic);
+    let advanced = shaped(&mut font_system, "AVA", Shaping::Advanced);
+    assert_ne!(basic, advanced);
+    assert_eq!(shaped(&mut font_system, "AVA", Shaping::Auto), basic);
+
+    // Unless a character is missing from the font, which needs fallback
+    let text = "AVA\u{AB70}";
+    let advanced = shaped(&mut font_system, text, Shaping::Advanced);
+    assert_eq!(shaped(&mut font_system, text, Shaping::Auto), advanced);
+
+    // Arabic always needs advanced shaping to join its letters
+    let arabic = "مرحبا";
+    let advanced = shaped(&mut font_system, arabic, Shaping::Advanced);
+    assert_eq!(shaped(&mut font_system, arabic, Shaping::Auto), advanced);
+
+    // Even next to Latin text, which is still shaped without kerning
+    let text = "AVA مرحبا";
+    let glyph_ids = |glyphs: &[(u16, f32)]| glyphs.iter().map(|glyph| glyph.0).collect::<Vec<_>>();
+    let advanced = shaped(&mut font_system, text, Shaping::Advanced);
+    let auto = shaped(&mut font_system, text, Shaping::Auto);
+    assert_eq!(glyph_ids(&auto), glyph_ids(&advanced));
+    assert_eq!(auto[..3], basic);
+}
+
+#[test]
+fn auto_shaping_applies_requested_features() {
+    let mut font_system = common::font_system();
+    let basic = shaped(&mut font_system, "AVA", Shaping::Basic);
+
+    let mut features = FontFeatures::new();
+    features.enable(FeatureTag::KERNING);
+    for attrs in [
+        Attrs::new().font_features(features),
+        Attrs::new().ligatures(Ligatures::NONE),
+    ] {
+        let advanced = shaped_with_attrs(&mut font_system, "AVA", &attrs, Shaping::Advanced);
+        assert_ne!(advanced, basic);
+        assert_eq!(
+            shaped_with_attrs(&mut font_system, "AVA", &attrs, Shaping::Auto),
+            advanced
+        );
+    }
+}
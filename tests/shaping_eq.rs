@@ -0,0 +1,15 @@
+use cosmic_text::{Attrs, Color, FeatureTag, Metrics, Weight};
+
+#[test]
+fn shaping_eq_ignores_color_only() {
+    let attrs = Attrs::new();
+    let red = attrs.clone().color(Color::rgb(0xFF, 0, 0)).metadata(1);
+    assert!(attrs.shaping_eq(&red));
+    assert!(red.shaping_eq(&attrs));
+
+    assert!(!attrs.shaping_eq(&attrs.clone().weight(Weight::BOLD)));
+    assert!(!attrs.shaping_eq(&attrs.clone().metrics(Metrics::new(20.0, 24.0))));
+    let mut features = attrs.clone();
+    features.font_features.disable(FeatureTag::KERNING);
+    assert!(!attrs.shaping_eq(&features));
+}
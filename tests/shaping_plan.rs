@@ -0,0 +1,124 @@
+use cosmic_text::{
+    ArabicForm, Attrs, Buffer, Feature, FeatureTag, FontFeatures, FontSystem, Ligatures, Metrics,
+    Shaping,
+};
+
+mod common;
+
+fn font_system() -> FontSystem {
+    common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"])
+}
+
+fn disabled(tag: &[u8; 4]) -> Feature {
+    Feature {
+        tag: FeatureTag::new(tag),
+        value: 0,
+    }
+}
+
+#[test]
+fn shaping_plan_reports_script_and_direction() {
+    let mut font_system = font_system();
+
+    let mut features = FontFeatures::new();
+    features.disable(FeatureTag::KERNING);
+    let attrs = Attrs::new().font_features(features);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "مرحبا\nHello", &attrs, Shaping::Advanced);
+
+    let plans: Vec<_> = buffer
+        .layout_runs()
+        .map(|run| run.shaping_plan().unwrap())
+        .collect();
+    assert_eq!(plans.len(), 2);
+
+    assert_eq!(&plans[0].script, b"arab");
+    assert!(plans[0].rtl);
+    assert_eq!(plans[0].language, None);
+
+    assert_eq!(&plans[1].script, b"latn");
+    assert!(!plans[1].rtl);
+    assert_eq!(plans[1].features, [disabled(b"kern")]);
+
+    buffer.set_locale(&mut font_system, Some("en"));
+    let run = buffer.layout_runs().nth(1).unwrap();
+    assert_eq!(run.shaping_plan().unwrap().language.as_deref(), Some("en"));
+}
+
+#[test]
+fn shaping_plan_reports_ligatures_and_arabic_form() {
+    let mut font_system = font_system();
+
+    let mut features = FontFeatures::new();
+    features.disable(FeatureTag::KERNING);
+    let attrs = Attrs::new()
+        .font_features(features)
+        .ligatures(Ligatures::NONE)
+        .arabic_form(ArabicForm::Isolated);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "مرحبا", &attrs, Shaping::Advanced);
+    let plan = buffer.layout_runs().next().unwrap().shaping_plan().unwrap();
+    assert_eq!(
+        plan.features,
+        [
+            disabled(b"liga"),
+            disabled(b"clig"),
+            disabled(b"kern"),
+            disabled(b"init"),
+            disabled(b"medi"),
+            disabled(b"fina"),
+            disabled(b"med2"),
+            disabled(b"fin2"),
+            disabled(b"fin3"),
+        ]
+    );
+}
+
+#[test]
+fn shaping_plan_reports_detected_script_and_language() {
+    let mut font_system = font_system();
+    font_system.set_script_detector(Some(Box::new(|text: &str| {
+        vec![(0..text.len(), *b"grek", Some("el".parse().unwrap()))]
+    })));
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_locale(&mut font_system, Some("en"));
+    buffer.set_text(&mut font_system, "Hello", &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let plan = run.shaping_plan().unwrap();
+    assert_eq!(&plan.script, b"grek");
+    assert_eq!(plan.language.as_deref(), Some("el"));
+    assert!(run.glyphs.iter().all(|glyph| &glyph.script == b"Grek"));
+}
+
+#[cfg(feature = "swash")]
+#[test]
+fn shaping_plan_absent_without_shaper() {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "Hello", &Attrs::new(), Shaping::Basic);
+    let run = buffer.layout_runs().next().unwrap();
+    assert_eq!(run.shaping_plan(), None);
+}
+
+#[cfg(feature = "shape-run-cache")]
+#[test]
+fn shaping_plan_kept_for_cached_runs() {
+    let mut font_system = font_system();
+    let mut features = FontFeatures::new();
+    features.disable(FeatureTag::KERNING);
+    let attrs = Attrs::new().font_features(features);
+
+    let mut plans = Vec::new();
+    for _ in 0..2 {
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+        buffer.set_text(&mut font_system, "Hello مرحبا", &attrs, Shaping::Advanced);
+        let run = buffer.layout_runs().next().unwrap();
+        plans.push(run.shaping_plan().unwrap());
+    }
+    assert_eq!(&plans[0].script, b"latn");
+    assert_eq!(plans[0].features, [disabled(b"kern")]);
+    assert_eq!(plans[0], plans[1]);
+}
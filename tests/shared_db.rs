@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, PlatformFallback, Shaping};
+
+fn shape(font_system: &mut FontSystem) -> fontdb::ID {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, "Hello", &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs[0].font_id
+}
+
+#[test]
+fn font_systems_share_database() {
+    let mut db = fontdb::Database::new();
+    db.load_font_data(std::fs::read("fonts/Inter-Regular.ttf").unwrap());
+    let font_id = db.faces().next().unwrap().id;
+    let db = Arc::new(db);
+
+    let mut a = FontSystem::new_from_shared_db("en-US".into(), db.clone(), PlatformFallback);
+    let mut b = FontSystem::new_from_shared_db("en-US".into(), db.clone(), PlatformFallback);
+    assert_eq!(shape(&mut a), font_id);
+    assert_eq!(shape(&mut b), font_id);
+
+    // Resolving fonts does not copy the database
+    assert!(Arc::ptr_eq(a.shared_db(), &db));
+    assert!(Arc::ptr_eq(b.shared_db(), &db));
+    assert_eq!(Arc::strong_count(&db), 3);
+
+    // Modifying the database of one system copies it, leaving the other untouched
+    a.remove_font(font_id);
+    assert!(!Arc::ptr_eq(a.shared_db(), &db));
+    assert!(a.db().face(font_id).is_none());
+    assert!(b.db().face(font_id).is_some());
+    assert_eq!(shape(&mut b), font_id);
+}
@@ -0,0 +1,95 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+mod common;
+
+const WORD: &str = "hy\u{AD}phen\u{AD}ation";
+
+fn new_buffer(font_system: &mut FontSystem, text: &str, width: Option<f32>, wrap: Wrap) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(20.0, 25.0));
+    buffer.set_wrap(font_system, wrap);
+    buffer.set_size(font_system, width, None);
+    buffer.set_text(font_system, text, &Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(font_system, false);
+    buffer
+}
+
+/// Text of each visual line, with shown hyphens as `-`
+fn lines(buffer: &Buffer, hyphen_id: u16) -> Vec<String> {
+    buffer
+        .layout_runs()
+        .map(|run| {
+            run.glyphs
+                .iter()
+                .map(|glyph| {
+                    if glyph.glyph_id == hyphen_id {
+                        "-"
+                    } else {
+                        run.text[glyph.start..glyph.end].trim_end_matches('\u{AD}')
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn soft_hyphen_breaks() {
+    let mut font_system = common::font_system();
+    let id = font_system.db().faces().next().unwrap().id;
+    // The shown hyphen is U+2010 HYPHEN, or HYPHEN-MINUS if the font lacks it
+    let hyphen = if font_system.glyph_index(id, '\u{2010}').is_some() {
+        "\u{2010}"
+    } else {
+        "-"
+    };
+    let hyphen_id = font_system
+        .glyph_index(id, hyphen.chars().next().unwrap())
+        .unwrap();
+
+    // Without a break, soft hyphens are not shown
+    let buffer = new_buffer(&mut font_system, WORD, None, Wrap::Word);
+    assert_eq!(lines(&buffer, hyphen_id), ["hyphenation"]);
+    let glyphs = buffer.layout_runs().next().unwrap().glyphs.to_vec();
+    let x_at = |index| glyphs.iter().find(|glyph| glyph.start == index).unwrap().x;
+    let hyphen_w = new_buffer(&mut font_system, hyphen, None, Wrap::Word)
+        .layout_runs()
+        .next()
+        .unwrap()
+        .line_w;
+    let width_before = |index| x_at(index) + hyphen_w + 0.5;
+
+    for (width, expected) in [
+        (width_before(4) - 1.0, &["hy-", "phen-", "ation"][..]),
+        (width_before(4), &["hy-", "phen-", "ation"]),
+        (width_before(10), &["hyphen-", "ation"]),
+        (x_at(10) + 1.0, &["hy-", "phen-", "ation"]),
+        (1000.0, &["hyphenation"]),
+    ] {
+        let buffer = new_buffer(&mut font_system, WORD, Some(width), Wrap::Word);
+        assert_eq!(lines(&buffer, hyphen_id), expected, "width {width}");
+        // The first line fits, including its hyphen
+        if width >= width_before(4) {
+            let line_w = buffer.layout_runs().next().unwrap().line_w;
+            assert!(line_w <= width, "{line_w} > {width}");
+        }
+    }
+
+    // Consecutive soft hyphens show a single hyphen, and a soft hyphen at the start of a line
+    // shows none
+    let width = width_before(4);
+    let buffer = new_buffer(
+        &mut font_system,
+        "hy\u{AD}\u{AD}phen",
+        Some(width),
+        Wrap::Word,
+    );
+    assert_eq!(lines(&buffer, hyphen_id), ["hy-", "phen"]);
+    let buffer = new_buffer(&mut font_system, "\u{AD}hyphen", Some(width), Wrap::Word);
+    assert!(!lines(&buffer, hyphen_id).concat().contains('-'));
+
+    // Glyph wrapping only shows a hyphen when it breaks at a soft hyphen
+    let buffer = new_buffer(&mut font_system, WORD, Some(width_before(4)), Wrap::Glyph);
+    let glyph_lines = lines(&buffer, hyphen_id);
+    assert_eq!(glyph_lines[0], "hy-");
+    assert_eq!(glyph_lines.concat().replace('-', ""), "hyphenation");
+}
@@ -0,0 +1,70 @@
+use common::TestFont;
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SpaceAdvanceSource};
+
+mod common;
+
+/// Build an icon font named "Icon Test" that only maps `A` to a square, without a space glyph
+fn icon_font() -> Vec<u8> {
+    TestFont::new("Icon Test")
+        .glyph(None, 1000, true)
+        .glyph(Some('A'), 1000, true)
+        .build()
+}
+
+/// The font id and width of the space between two icons
+fn space(font_system: &mut FontSystem) -> (fontdb::ID, f32) {
+    let mut buffer = Buffer::new(font_system, Metrics::new(20.0, 24.0));
+    let attrs = Attrs::new().family(Family::Name("Icon Test"));
+    buffer.set_text(font_system, "A A", &attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    assert_eq!(run.glyphs.len(), 3);
+    (run.glyphs[1].font_id, run.glyphs[1].w)
+}
+
+#[test]
+fn space_advance_follows_configured_source() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let icon_id = font_system
+        .db_mut()
+        .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(icon_font())))[0];
+    let mut text_ids = Vec::new();
+    for path in ["fonts/Inter-Regular.ttf", "fonts/FiraMono-Medium.ttf"] {
+        let font = std::fs::read(path).unwrap();
+        text_ids.extend(
+            font_system
+                .db_mut()
+                .load_font_source(fontdb::Source::Binary(std::sync::Arc::new(font))),
+        );
+    }
+
+    // The space comes from one of the text fonts
+    let (fallback_id, fallback_w) = space(&mut font_system);
+    assert!(text_ids.contains(&fallback_id));
+
+    font_system.set_space_advance_source(SpaceAdvanceSource::Em(0.1));
+    let (_, em_w) = space(&mut font_system);
+    assert!((em_w - 2.0).abs() < 0.001, "{em_w}");
+    assert_ne!(em_w, fallback_w);
+
+    // Nominate the other text font
+    let nominated_id = text_ids.into_iter().find(|&id| id != fallback_id).unwrap();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 24.0));
+    let attrs = Attrs::new().font_id(nominated_id);
+    buffer.set_text(&mut font_system, " ", &attrs, Shaping::Advanced);
+    let nominated_w = buffer.layout_runs().next().unwrap().glyphs[0].w;
+    assert_ne!(nominated_w, fallback_w);
+
+    font_system.set_space_advance_source(SpaceAdvanceSource::Font(nominated_id));
+    assert_eq!(space(&mut font_system), (nominated_id, nominated_w));
+
+    // Text the primary font supports is not affected
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 24.0));
+    let attrs = Attrs::new().family(Family::Name("Icon Test"));
+    buffer.set_text(&mut font_system, "AA", &attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run
+        .glyphs
+        .iter()
+        .all(|glyph| glyph.font_id == icon_id && glyph.w == 20.0));
+}
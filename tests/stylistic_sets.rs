@@ -0,0 +1,70 @@
+use common::{be16, TestFont};
+use cosmic_text::{Attrs, Buffer, FeatureTag, FontSystem, Metrics, Shaping};
+
+mod common;
+
+/// Build a font mapping `A` to glyph 1, with a stylistic set `ss01` named "Alternate A" that
+/// substitutes glyph 2, and an unnamed `ss02` that does nothing
+fn stylistic_set_font() -> Vec<u8> {
+    let mut gsub = Vec::new();
+    // Header with script list at 10, feature list at 32 and lookup list at 60
+    be16(&mut gsub, &[1, 0, 10, 32, 60]);
+    // Script list with a default script enabling both features
+    be16(&mut gsub, &[1]);
+    gsub.extend_from_slice(b"DFLT");
+    be16(&mut gsub, &[8, 4, 0, 0, 0xFFFF, 2, 0, 1]);
+    // Feature list, ss01 with feature params naming it and ss02 without
+    be16(&mut gsub, &[2]);
+    gsub.extend_from_slice(b"ss01");
+    be16(&mut gsub, &[14]);
+    gsub.extend_from_slice(b"ss02");
+    be16(&mut gsub, &[24]);
+    be16(&mut gsub, &[6, 1, 0, 0, 256]);
+    be16(&mut gsub, &[0, 0]);
+    // Lookup list with a single substitution of glyph 1 by glyph 2
+    be16(&mut gsub, &[1, 4]);
+    be16(&mut gsub, &[1, 0, 1, 8]);
+    be16(&mut gsub, &[2, 8, 1, 2]);
+    be16(&mut gsub, &[1, 1, 1]);
+
+    TestFont::new("Stylistic Test")
+        .glyph(None, 600, false)
+        .glyph(Some('A'), 600, false)
+        .glyph(None, 600, false)
+        .name(256, "Alternate A")
+        .table(b"GSUB", gsub)
+        .build()
+}
+
+fn glyph_ids(font_system: &mut FontSystem, attrs: &Attrs) -> Vec<u16> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, "AA", attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+}
+
+#[test]
+fn stylistic_sets_are_named_and_enabled() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    font_system.db_mut().load_font_data(stylistic_set_font());
+    let font_id = font_system.db().faces().next().unwrap().id;
+
+    assert_eq!(
+        font_system.stylistic_sets(font_id),
+        [
+            (
+                FeatureTag::stylistic_set(1),
+                Some("Alternate A".to_string())
+            ),
+            (FeatureTag::stylistic_set(2), None),
+        ]
+    );
+    assert_eq!(FeatureTag::stylistic_set(1), FeatureTag::STYLISTIC_SET_1);
+
+    assert_eq!(glyph_ids(&mut font_system, &Attrs::new()), [1, 1]);
+    assert_eq!(
+        glyph_ids(&mut font_system, &Attrs::new().stylistic_set(1)),
+        [2, 2]
+    );
+}
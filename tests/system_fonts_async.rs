@@ -0,0 +1,28 @@
+#![cfg(all(feature = "std", not(target_arch = "wasm32")))]
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn merge_system_fonts_adds_faces() {
+    let mut font_system = common::font_system();
+    let loader = FontSystem::load_system_fonts_async();
+
+    // The font system is usable while system fonts are loading
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "Hello", &Attrs::new(), Shaping::Advanced);
+    assert_eq!(buffer.layout_runs().count(), 1);
+
+    font_system.merge_system_fonts(loader);
+
+    let mut system_db = fontdb::Database::new();
+    system_db.load_system_fonts();
+    assert_eq!(font_system.db().len(), 1 + system_db.len());
+    for face in system_db.faces() {
+        assert!(font_system
+            .db()
+            .faces()
+            .any(|merged| merged.post_script_name == face.post_script_name));
+    }
+}
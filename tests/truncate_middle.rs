@@ -0,0 +1,41 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping, Wrap};
+
+mod common;
+
+fn line_width(buffer: &Buffer) -> f32 {
+    buffer
+        .layout_runs()
+        .map(|run| run.line_w)
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn truncate_middle_drops_whole_segments() {
+    let mut font_system = common::font_system();
+
+    let path = "/a/b/c/d/e/f/g/h/x/y/z";
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::None);
+    buffer.set_text(path, &Attrs::new(), Shaping::Advanced);
+    let full_width = line_width(&buffer);
+
+    // Fits already
+    assert!(!buffer.truncate_middle_at(full_width + 1.0, &['/'], "…"));
+    assert_eq!(buffer.lines[0].text(), path);
+
+    let budget = full_width * 0.6;
+    assert!(buffer.truncate_middle_at(budget, &['/'], "…"));
+    let text = buffer.lines[0].text().to_string();
+    assert!(line_width(&buffer) <= budget);
+
+    let (start, end) = text.split_once('…').unwrap();
+    assert!(start.starts_with("/a/") && start.ends_with('/'));
+    assert!(end.starts_with('/') && end.ends_with("/z"));
+    assert!(path.starts_with(start) && path.ends_with(end));
+    assert!(text.len() < path.len());
+
+    // Nothing fits, so only the first and last segments are kept
+    assert!(buffer.truncate_middle_at(1.0, &['/'], "…"));
+    assert_eq!(buffer.lines[0].text(), "/…/z");
+}
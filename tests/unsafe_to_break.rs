@@ -0,0 +1,46 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn unsafe_to_break_marks_joined_clusters() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansArabic.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "ببب ببب",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let run = buffer.layout_runs().next().unwrap();
+    let mut flags: Vec<(usize, bool)> = run
+        .glyphs
+        .iter()
+        .map(|glyph| (glyph.start, glyph.unsafe_to_break))
+        .collect();
+    flags.sort();
+
+    // Breaking between joined letters changes their forms, while words are shaped on their own
+    assert_eq!(
+        flags,
+        [
+            (0, false),
+            (2, true),
+            (4, true),
+            (6, false),
+            (7, false),
+            (9, true),
+            (11, true),
+        ]
+    );
+
+    let shape = buffer.lines[0].shape_opt().unwrap();
+    let shaped: Vec<bool> = shape.spans[0].words[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.unsafe_to_break)
+        .collect();
+    assert_eq!(shaped.iter().filter(|&&flag| flag).count(), 2);
+}
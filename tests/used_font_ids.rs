@@ -0,0 +1,21 @@
+use cosmic_text::{Attrs, Buffer, Family, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn used_font_ids_include_fallbacks() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansArabic.ttf"]);
+    let ids: Vec<_> = font_system.db().faces().map(|face| face.id).collect();
+    let family = font_system.db().face(ids[0]).unwrap().families[0].0.clone();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    assert!(buffer.used_font_ids().is_empty());
+    buffer.set_text(
+        &mut font_system,
+        "Hello مرحبا\nworld",
+        &Attrs::new().family(Family::Name(&family)),
+        Shaping::Advanced,
+    );
+
+    assert_eq!(buffer.used_font_ids().into_iter().collect::<Vec<_>>(), ids);
+}
@@ -0,0 +1,30 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+// A base character followed by a variation selector should stay one cluster, and the variation
+// selector must not produce a separate missing glyph. With a font lacking the variant, the base
+// glyph is used.
+#[test]
+fn variation_sequence_stays_together() {
+    let mut font_system = common::font_system();
+    let id = font_system.db().faces().next().unwrap().id;
+    let base_glyph = font_system.glyph_index(id, 'A').unwrap();
+
+    for shaping in [Shaping::Basic, Shaping::Advanced] {
+        for text in ["A\u{FE00}", "A\u{E0100}"] {
+            let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 30.0));
+            buffer.set_text(&mut font_system, text, &Attrs::new(), shaping);
+            let run = buffer.layout_runs().next().unwrap();
+
+            assert!(
+                run.glyphs.iter().all(|glyph| glyph.start == 0),
+                "{shaping:?} {text:?}: {:?}",
+                run.glyphs
+            );
+            assert_eq!(run.glyphs[0].glyph_id, base_glyph);
+            assert_eq!(run.glyphs.last().unwrap().end, text.len());
+            assert!(run.glyphs.iter().all(|glyph| glyph.glyph_id != 0));
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn visual_order_reverses_rtl_run() {
+    let mut font_system = common::font_system_with(&[common::INTER, "fonts/NotoSansHebrew.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "abc אבג def",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(!run.rtl);
+    assert_eq!(run.glyphs.len(), 11);
+    // The RTL run is reversed, the spaces around it resolve to the LTR paragraph direction
+    assert_eq!(run.visual_order(), [0, 1, 2, 3, 6, 5, 4, 7, 8, 9, 10]);
+}
@@ -0,0 +1,201 @@
+#![cfg(feature = "woff")]
+
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, WoffError};
+
+mod common;
+
+fn be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Wrap the tables of the sfnt font `sfnt` in a WOFF font, compressing them where it helps
+fn encode_woff(sfnt: &[u8]) -> Vec<u8> {
+    let num_tables = u16::from_be_bytes([sfnt[4], sfnt[5]]) as usize;
+    let mut directory = Vec::new();
+    let mut tables = Vec::new();
+    let mut offset = 44 + 20 * num_tables;
+    for table_i in 0..num_tables {
+        let record = 12 + 16 * table_i;
+        let (tag, checksum) = (be32(sfnt, record), be32(sfnt, record + 4));
+        let table_offset = be32(sfnt, record + 8) as usize;
+        let length = be32(sfnt, record + 12) as usize;
+        let table = &sfnt[table_offset..table_offset + length];
+
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(table, 6);
+        let data = if compressed.len() < table.len() {
+            compressed
+        } else {
+            table.to_vec()
+        };
+        for value in [
+            tag,
+            offset as u32,
+            data.len() as u32,
+            length as u32,
+            checksum,
+        ] {
+            directory.extend_from_slice(&value.to_be_bytes());
+        }
+        offset += data.len().next_multiple_of(4);
+        tables.extend_from_slice(&data);
+        tables.resize(tables.len().next_multiple_of(4), 0);
+    }
+
+    let mut woff = Vec::new();
+    for value in [0x774F_4646, be32(sfnt, 0), offset as u32] {
+        woff.extend_from_slice(&value.to_be_bytes());
+    }
+    woff.extend_from_slice(&(num_tables as u16).to_be_bytes());
+    woff.extend_from_slice(&0u16.to_be_bytes());
+    woff.extend_from_slice(&(sfnt.len() as u32).to_be_bytes());
+    woff.extend_from_slice(&[0, 1, 0, 0]);
+    woff.extend_from_slice(&[0; 20]);
+    woff.extend_from_slice(&directory);
+    woff.extend_from_slice(&tables);
+    woff
+}
+
+fn glyph_ids(font_system: &mut FontSystem, family: &str) -> Vec<u16> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let attrs = Attrs::new().family(Family::Name(family));
+    buffer.set_text(font_system, "Hello, WOFF!", &attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| glyph.glyph_id).collect()
+}
+
+fn glyph_positions(font_system: &mut FontSystem, family: &str) -> Vec<(u16, f32, f32)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let attrs = Attrs::new().family(Family::Name(family));
+    buffer.set_text(
+        font_system,
+        "Quick WOFF2 fonts, Ävä!",
+        &attrs,
+        Shaping::Advanced,
+    );
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .map(|glyph| (glyph.glyph_id, glyph.x, glyph.w))
+        .collect()
+}
+
+#[test]
+fn load_woff_decodes_faces() {
+    let sfnt = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let woff = encode_woff(&sfnt);
+    assert!(woff.len() < sfnt.len());
+
+    let mut sfnt_system = common::font_system_with(&[]);
+    sfnt_system.db_mut().load_font_data(sfnt);
+    let family = sfnt_system.db().faces().next().unwrap().families[0]
+        .0
+        .clone();
+
+    let mut woff_system = common::font_system_with(&[]);
+    let ids = woff_system.load_woff(&woff).unwrap();
+    assert_eq!(ids.len(), 1);
+    let face = woff_system.db().face(ids[0]).unwrap();
+    assert_eq!(face.families[0].0, family);
+
+    assert_eq!(
+        glyph_ids(&mut woff_system, &family),
+        glyph_ids(&mut sfnt_system, &family)
+    );
+}
+
+#[test]
+fn load_woff2_decodes_faces() {
+    let sfnt = std::fs::read("fonts/Cantarell-Regular.ttf").unwrap();
+    let woff2 = std::fs::read("fonts/Cantarell-Regular.woff2").unwrap();
+
+    let mut sfnt_system = common::font_system_with(&[]);
+    sfnt_system.db_mut().load_font_data(sfnt);
+    let family = sfnt_system.db().faces().next().unwrap().families[0]
+        .0
+        .clone();
+    let expected = glyph_positions(&mut sfnt_system, &family);
+    assert!(expected.iter().all(|&(glyph_id, _, _)| glyph_id != 0));
+
+    let mut woff2_system = common::font_system_with(&[]);
+    let ids = woff2_system.load_woff2(&woff2).unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(woff2_system.db().len(), 1);
+    let face = woff2_system.db().face(ids[0]).unwrap();
+    assert_eq!(face.families[0].0, family);
+    assert_eq!(glyph_positions(&mut woff2_system, &family), expected);
+
+    // Outlines come from the reconstructed glyf and loca tables
+    let font = woff2_system.get_font(ids[0]).unwrap();
+    let glyph_id = cosmic_text::ttf_parser::GlyphId(expected[0].0);
+    assert!(font.rustybuzz().glyph_bounding_box(glyph_id).is_some());
+
+    // WOFF2 fonts also load through load_woff
+    let mut font_system = common::font_system_with(&[]);
+    assert_eq!(font_system.load_woff(&woff2).unwrap().len(), 1);
+    assert_eq!(glyph_positions(&mut font_system, &family), expected);
+}
+
+#[test]
+fn load_woff2_rejects_other_data() {
+    let woff = encode_woff(&std::fs::read("fonts/Cantarell-Regular.ttf").unwrap());
+    let mut woff2 = std::fs::read("fonts/Cantarell-Regular.woff2").unwrap();
+    let mut font_system = common::font_system_with(&[]);
+    assert_eq!(font_system.load_woff2(&woff), Err(WoffError::NotWoff));
+    assert_eq!(
+        font_system.load_woff2(&woff2[..woff2.len() / 2]),
+        Err(WoffError::InvalidData)
+    );
+    let last = woff2.len() - 1;
+    woff2[last / 2..].fill(0xFF);
+    assert_eq!(
+        font_system.load_woff2(&woff2),
+        Err(WoffError::Decompression)
+    );
+    assert_eq!(font_system.db().len(), 0);
+}
+
+/// Build a WOFF2 header with one `cmap` table of `orig_length` bytes, encoded as UIntBase128,
+/// in a font of `total_sfnt_size` bytes, without any compressed data
+fn woff2_header(total_sfnt_size: u32, orig_length: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    common::be32(&mut data, &[0x774F_4632, 0x0001_0000, 0]);
+    common::be16(&mut data, &[1, 0]);
+    common::be32(&mut data, &[total_sfnt_size, 0]);
+    data.extend_from_slice(&[0; 24]);
+    data.push(0);
+    data.extend_from_slice(orig_length);
+    data
+}
+
+#[test]
+fn load_woff2_rejects_oversized_tables() {
+    let mut font_system = common::font_system_with(&[]);
+    // Tables larger than the whole font
+    assert_eq!(
+        font_system.load_woff2(&woff2_header(1024, &[0x84, 0x80, 0x80, 0x80, 0x00])),
+        Err(WoffError::InvalidData)
+    );
+    // Fonts larger than any real font
+    assert_eq!(
+        font_system.load_woff2(&woff2_header(u32::MAX, &[0x8F, 0xFF, 0xFF, 0xFF, 0x7F])),
+        Err(WoffError::InvalidData)
+    );
+}
+
+#[test]
+fn load_woff_rejects_other_data() {
+    let mut font_system = common::font_system_with(&[]);
+    assert_eq!(
+        font_system.load_woff(b"wOF2\0\x01\0\0"),
+        Err(WoffError::InvalidData)
+    );
+    assert_eq!(
+        font_system.load_woff(b"\0\x01\0\0\0\0"),
+        Err(WoffError::NotWoff)
+    );
+    assert_eq!(
+        font_system.load_woff(b"wOFF\0\x01\0\0"),
+        Err(WoffError::InvalidData)
+    );
+    assert_eq!(font_system.db().len(), 0);
+}
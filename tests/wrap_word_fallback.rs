@@ -35,3 +35,71 @@ fn measure(buffer: &Buffer) -> f32 {
         .layout_runs()
         .fold(0.0f32, |width, run| width.max(run.line_w))
 }
+
+// Only words wider than the buffer are broken, other words wrap as with Wrap::Word
+#[test]
+fn wrap_word_fallback_long_token() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let metrics = Metrics::new(14.0, 20.0);
+
+    let token = "0123456789abcdefghijklmnopqrstuvwxyz";
+    let words = ["Lorem", "ipsum", "dolor", "sit", "amet"];
+    let text = format!("Lorem ipsum {token} dolor sit amet");
+
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let mut layout_text = |text: &str, wrap, width| {
+        buffer.set_wrap(wrap);
+        buffer.set_size(Some(width), None);
+        buffer.set_text(text, &Attrs::new(), Shaping::Advanced);
+        buffer.shape_until_scroll(false);
+        buffer
+            .layout_runs()
+            .map(|run| {
+                let start = run.glyphs.first().map_or(0, |glyph| glyph.start);
+                let end = run.glyphs.last().map_or(0, |glyph| glyph.end);
+                (run.text[start..end].to_string(), run.line_w)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let token_width = layout_text(token, Wrap::None, 1000.0)[0].1;
+    let mut layout = |wrap, width| layout_text(&text, wrap, width);
+    let full_width = layout(Wrap::None, 1000.0)[0].1;
+
+    // Wide enough for everything, or at least for the token
+    assert_eq!(layout(Wrap::WordOrGlyph, full_width + 1.0).len(), 1);
+    for width in [token_width + 1.0, token_width * 1.5] {
+        assert_eq!(
+            layout(Wrap::WordOrGlyph, width),
+            layout(Wrap::Word, width),
+            "width {width}"
+        );
+    }
+
+    // Narrower than the token, which is broken but lines still fit
+    for width in [token_width / 2.0, token_width / 3.0, 60.0] {
+        let word_lines = layout(Wrap::Word, width);
+        let lines = layout(Wrap::WordOrGlyph, width);
+        assert!(lines.len() > word_lines.len(), "width {width}");
+        // The words before and after the token are on their own lines
+        assert!(
+            lines.len() >= (token_width / width).ceil() as usize + 2,
+            "width {width}"
+        );
+        for (line, line_w) in &lines {
+            assert!(*line_w <= width, "{line:?} {line_w} > {width}");
+        }
+        let joined: String = lines.iter().map(|(line, _)| line.trim()).collect();
+        assert!(joined.contains(token), "width {width}");
+        for word in words {
+            assert!(
+                lines.iter().any(|(line, _)| line.contains(word)),
+                "{word} broken at width {width}"
+            );
+        }
+    }
+}
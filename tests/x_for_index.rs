@@ -0,0 +1,41 @@
+use cosmic_text::{Attrs, Buffer, Metrics, Shaping};
+
+mod common;
+
+#[test]
+fn x_for_index_ltr_and_rtl() {
+    let mut font_system =
+        common::font_system_with(&["fonts/NotoSans-Regular.ttf", "fonts/NotoSansArabic.ttf"]);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 20.0));
+    let text = "fit e\u{301}";
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+
+    // Positions inside a ligature are interpolated
+    let fi = run.glyphs.iter().find(|glyph| glyph.start == 0).unwrap();
+    assert_eq!(fi.end, 2);
+    assert_eq!(run.x_for_index(0), Some(fi.x));
+    assert_eq!(run.x_for_index(1), Some(fi.x + fi.w / 2.0));
+    assert_eq!(run.x_for_index(2), Some(fi.x + fi.w));
+
+    // A combining mark is at the start of its grapheme, and the end of the text is the trailing
+    // edge of the last cluster
+    assert_eq!(run.x_for_index(5), run.x_for_index(4));
+    assert_eq!(run.x_for_index(text.len()), Some(run.line_w));
+    assert_eq!(run.x_for_index(text.len() + 1), None);
+
+    // RTL positions move left as the index increases
+    let text = "مرحبا";
+    buffer.set_text(&mut font_system, text, &Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let xs: Vec<f32> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([text.len()])
+        .map(|i| run.x_for_index(i).unwrap())
+        .collect();
+    assert!((xs[0] - run.line_w).abs() < 0.01, "{xs:?}");
+    assert!(xs[xs.len() - 1].abs() < 0.01, "{xs:?}");
+    assert!(xs.windows(2).all(|pair| pair[1] < pair[0]), "{xs:?}");
+}
@@ -0,0 +1,40 @@
+use cosmic_text::{Attrs, Buffer, Color, FontSystem, Metrics, Shaping, SwashCache};
+
+mod common;
+
+fn layout_and_draw(
+    font_system: &mut FontSystem,
+    text: &str,
+    emit_zero_advance_glyphs: bool,
+) -> (usize, Vec<(i32, i32, u32)>) {
+    let mut buffer = Buffer::new(font_system, Metrics::new(32.0, 40.0));
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_emit_zero_advance_glyphs(emit_zero_advance_glyphs);
+    buffer.set_text(text, &Attrs::new(), Shaping::Advanced);
+    let glyph_count = buffer
+        .layout_runs()
+        .map(|run| run.glyphs.len())
+        .sum::<usize>();
+
+    let mut pixels = Vec::new();
+    buffer.draw(
+        &mut SwashCache::new(),
+        Color::rgb(0, 0, 0),
+        |x, y, _, _, color| pixels.push((x, y, color.0)),
+    );
+    pixels.sort();
+    (glyph_count, pixels)
+}
+
+#[test]
+fn zero_advance_glyphs_folded_into_base() {
+    let mut font_system = common::font_system_with(&["fonts/NotoSansHebrew.ttf"]);
+
+    // Letters with niqqud, which are shaped as zero-advance marks
+    let text = "שָׁלוֹם";
+    let (emitted_count, emitted_pixels) = layout_and_draw(&mut font_system, text, true);
+    let (folded_count, folded_pixels) = layout_and_draw(&mut font_system, text, false);
+
+    assert!(folded_count < emitted_count);
+    assert_eq!(folded_pixels, emitted_pixels);
+}